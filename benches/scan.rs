@@ -0,0 +1,83 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use scar::project_scanner::ProjectScanner;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use tempdir::TempDir;
+
+const NUM_MODULES: usize = 40;
+const NUM_FILES_PER_MODULE: usize = 100;
+const NUM_INCLUDES_PER_FILE: usize = 8;
+
+/**
+ * Generates a project of NUM_MODULES directories, each with NUM_FILES_PER_MODULE translation
+ * units and headers, every file including some headers of its module and of the previous one.
+ */
+fn generate_project(base_path: &Path) -> Result<(), Box<dyn Error>> {
+    for module in 0..NUM_MODULES {
+        let directory = base_path.join(format!("Source/Module{}", module));
+        fs::create_dir_all(&directory)?;
+
+        for file in 0..NUM_FILES_PER_MODULE {
+            let mut content = String::from("#pragma once\n\n");
+            for include in 0..NUM_INCLUDES_PER_FILE {
+                let included_module = module.saturating_sub(include % 2);
+                let included_file = (file + include + 1) % NUM_FILES_PER_MODULE;
+                content.push_str(&format!(
+                    "#include \"Module{}/header{}.h\"\n",
+                    included_module, included_file
+                ));
+            }
+            content.push_str("\n#include <vector>\n\n");
+            for function in 0..50 {
+                content.push_str(&format!(
+                    "// function {} of file {}\nint function{}(int value) {{ return value + {}; }}\n",
+                    function, file, function, file
+                ));
+            }
+
+            fs::write(directory.join(format!("header{}.h", file)), &content)?;
+            fs::write(directory.join(format!("source{}.cpp", file)), &content)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn scan_benchmark(c: &mut Criterion) {
+    let temp_dir = TempDir::new("scar_scan_benchmark").expect("Cannot create the project");
+    generate_project(temp_dir.path()).expect("Cannot generate the project");
+
+    let mut group = c.benchmark_group("scan_files");
+    group.sample_size(10);
+
+    let max_threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut num_threads = 1;
+    loop {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .build()
+            .expect("Cannot build the thread pool");
+        group.bench_with_input(
+            BenchmarkId::from_parameter(num_threads),
+            &num_threads,
+            |b, _| {
+                b.iter(|| {
+                    let mut project = ProjectScanner::make(temp_dir.path()).unwrap();
+                    pool.install(|| project.scan_files().map_err(|e| e.to_string()))
+                        .unwrap()
+                })
+            },
+        );
+
+        if num_threads >= max_threads {
+            break;
+        }
+        num_threads = (num_threads * 2).min(max_threads);
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, scan_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,530 @@
+use crate::include_resolver::IncludeSettings;
+use serde::Deserialize;
+use std::error::Error;
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+
+/**
+ * Maximum nesting of response files (i.e., "@file" arguments), to protect against response files
+ * including themselves.
+ */
+const MAX_RESPONSE_FILE_DEPTH: usize = 8;
+
+#[derive(Deserialize)]
+struct RawCompileCommand {
+    directory: String,
+    file: String,
+    command: Option<String>,
+    arguments: Option<Vec<String>>,
+}
+
+/**
+ * A macro definition or removal passed on the command line, in the same order it appears.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MacroFlag {
+    /// -DNAME or -DNAME=VALUE
+    Define { name: String, value: Option<String> },
+    /// -UNAME
+    Undefine(String),
+}
+
+impl MacroFlag {
+    /**
+     * Parses the argument of a -D flag, e.g., "NAME" or "NAME=VALUE".
+     */
+    pub fn parse_define(definition: &str) -> MacroFlag {
+        match definition.split_once('=') {
+            Some((name, value)) => MacroFlag::Define {
+                name: name.to_string(),
+                value: Some(value.to_string()),
+            },
+            None => MacroFlag::Define {
+                name: definition.to_string(),
+                value: None,
+            },
+        }
+    }
+}
+
+/**
+ * The compilation of a single translation unit, with the flags relevant for include analysis.
+ * All the paths are absolute.
+ */
+#[derive(Debug, Default)]
+pub struct CompileCommand {
+    file: PathBuf,
+    quote_dirs: Vec<PathBuf>,
+    user_dirs: Vec<PathBuf>,
+    system_dirs: Vec<PathBuf>,
+    macros: Vec<MacroFlag>,
+    forced_includes: Vec<PathBuf>,
+}
+
+impl CompileCommand {
+    pub fn get_file(&self) -> &Path {
+        &self.file
+    }
+
+    pub fn get_quote_dirs(&self) -> &[PathBuf] {
+        &self.quote_dirs
+    }
+
+    pub fn get_user_dirs(&self) -> &[PathBuf] {
+        &self.user_dirs
+    }
+
+    pub fn get_system_dirs(&self) -> &[PathBuf] {
+        &self.system_dirs
+    }
+
+    pub fn get_macros(&self) -> &[MacroFlag] {
+        &self.macros
+    }
+
+    /**
+     * Returns the files included before the first line of the translation unit (e.g., "-include
+     * file").
+     */
+    pub fn get_forced_includes(&self) -> &[PathBuf] {
+        &self.forced_includes
+    }
+}
+
+/**
+ * A JSON compilation database, i.e., the compile_commands.json file generated by build systems
+ * such as CMake or UnrealBuildTool.
+ */
+#[derive(Debug)]
+pub struct CompilationDatabase {
+    commands: Vec<CompileCommand>,
+}
+
+impl CompilationDatabase {
+    pub fn load(path: &Path) -> Result<CompilationDatabase, Box<dyn Error>> {
+        let content = read_to_string(path)
+            .map_err(|e| format!("Error while reading {}: {}", path.display(), e))?;
+
+        Self::parse(&content)
+    }
+
+    pub fn parse(content: &str) -> Result<CompilationDatabase, Box<dyn Error>> {
+        let raw_commands: Vec<RawCompileCommand> = serde_json::from_str(content)
+            .map_err(|e| format!("Invalid compilation database: {}", e))?;
+
+        let commands = raw_commands
+            .into_iter()
+            .map(Self::make_compile_command)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(CompilationDatabase { commands })
+    }
+
+    pub fn get_commands(&self) -> &[CompileCommand] {
+        &self.commands
+    }
+
+    /**
+     * Returns the include settings to use for the whole project, i.e., the union of the include
+     * directories of all the translation units, in order of first appearance, followed by the
+     * given additional directories.
+     */
+    pub fn make_include_settings(&self, additional: &IncludeSettings) -> IncludeSettings {
+        fn merge(dirs: Vec<&[PathBuf]>, additional: &[String]) -> Vec<String> {
+            let mut merged: Vec<String> = Vec::new();
+            let all_dirs = dirs
+                .into_iter()
+                .flatten()
+                .map(|d| d.to_string_lossy().to_string())
+                .chain(additional.iter().cloned());
+            for dir in all_dirs {
+                if !merged.contains(&dir) {
+                    merged.push(dir);
+                }
+            }
+            merged
+        }
+
+        IncludeSettings::make(
+            merge(
+                self.commands
+                    .iter()
+                    .map(|c| c.quote_dirs.as_slice())
+                    .collect(),
+                additional.get_quote_dirs(),
+            ),
+            merge(
+                self.commands
+                    .iter()
+                    .map(|c| c.user_dirs.as_slice())
+                    .collect(),
+                additional.get_user_dirs(),
+            ),
+            merge(
+                self.commands
+                    .iter()
+                    .map(|c| c.system_dirs.as_slice())
+                    .collect(),
+                additional.get_system_dirs(),
+            ),
+            additional.is_suffix_fallback_enabled(),
+        )
+    }
+
+    fn make_compile_command(raw: RawCompileCommand) -> Result<CompileCommand, Box<dyn Error>> {
+        let directory = PathBuf::from(&raw.directory);
+
+        let arguments = match (raw.arguments, raw.command) {
+            (Some(arguments), _) => arguments,
+            (None, Some(command)) => split_command_line(&command),
+            (None, None) => {
+                return Err(
+                    format!("Invalid compilation database: no command for {}", raw.file).into(),
+                )
+            }
+        };
+        let arguments = expand_response_files(arguments, &directory, 0)?;
+
+        let mut command = parse_arguments(&arguments, &directory);
+        command.file = directory.join(&raw.file);
+
+        Ok(command)
+    }
+}
+
+/**
+ * Extracts the include analysis relevant flags from the compiler arguments, the first one being
+ * the compiler itself. MSVC style flags (e.g., "/I") are recognised only for MSVC compatible
+ * drivers.
+ */
+fn parse_arguments(arguments: &[String], directory: &Path) -> CompileCommand {
+    let is_msvc = arguments.first().is_some_and(|compiler| {
+        let name = compiler
+            .rsplit(['/', '\\'])
+            .next()
+            .unwrap_or(compiler)
+            .to_lowercase();
+        name.trim_end_matches(".exe") == "cl" || name.trim_end_matches(".exe") == "clang-cl"
+    });
+
+    let mut flags: Vec<(&str, FlagKind)> = vec![
+        ("-isystem", FlagKind::SystemDir),
+        ("-idirafter", FlagKind::SystemDir),
+        ("-iquote", FlagKind::QuoteDir),
+        ("-include-pch", FlagKind::Ignored),
+        ("-include", FlagKind::ForcedInclude),
+        ("--include-directory=", FlagKind::UserDir),
+        ("-I", FlagKind::UserDir),
+        ("-D", FlagKind::Define),
+        ("-U", FlagKind::Undefine),
+    ];
+    if is_msvc {
+        flags.extend([
+            ("/external:I", FlagKind::SystemDir),
+            ("/FI", FlagKind::ForcedInclude),
+            ("/I", FlagKind::UserDir),
+            ("/D", FlagKind::Define),
+            ("/U", FlagKind::Undefine),
+        ]);
+    }
+
+    let mut command = CompileCommand::default();
+    let mut index = 1;
+    while index < arguments.len() {
+        let argument = &arguments[index];
+        index += 1;
+
+        let Some((flag, kind)) = flags.iter().find(|(f, _)| argument.starts_with(f)) else {
+            continue;
+        };
+
+        // the value is either attached to the flag (e.g., "-Iinclude") or the next argument
+        let value = if argument.len() > flag.len() || flag.ends_with('=') {
+            argument[flag.len()..].to_string()
+        } else {
+            match arguments.get(index) {
+                Some(next) => {
+                    index += 1;
+                    next.clone()
+                }
+                None => continue,
+            }
+        };
+
+        match kind {
+            FlagKind::QuoteDir => command.quote_dirs.push(directory.join(value)),
+            FlagKind::UserDir => command.user_dirs.push(directory.join(value)),
+            FlagKind::SystemDir => command.system_dirs.push(directory.join(value)),
+            FlagKind::Define => command.macros.push(MacroFlag::parse_define(&value)),
+            FlagKind::Undefine => command.macros.push(MacroFlag::Undefine(value)),
+            FlagKind::ForcedInclude => command.forced_includes.push(directory.join(value)),
+            FlagKind::Ignored => {}
+        }
+    }
+
+    command
+}
+
+enum FlagKind {
+    QuoteDir,
+    UserDir,
+    SystemDir,
+    Define,
+    Undefine,
+    ForcedInclude,
+    /// A flag whose name starts like a relevant one, but is not relevant itself.
+    Ignored,
+}
+
+/**
+ * Replaces the "@file" arguments with the arguments contained in the response file.
+ */
+fn expand_response_files(
+    arguments: Vec<String>,
+    directory: &Path,
+    depth: usize,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut expanded = Vec::new();
+    for argument in arguments {
+        match argument.strip_prefix('@') {
+            Some(response_file) if depth < MAX_RESPONSE_FILE_DEPTH => {
+                let path = directory.join(response_file);
+                let content = read_to_string(&path).map_err(|e| {
+                    format!(
+                        "Error while reading response file {}: {}",
+                        path.display(),
+                        e
+                    )
+                })?;
+                expanded.extend(expand_response_files(
+                    split_command_line(&content),
+                    directory,
+                    depth + 1,
+                )?);
+            }
+            _ => expanded.push(argument),
+        }
+    }
+
+    Ok(expanded)
+}
+
+/**
+ * Splits a command line into arguments, honouring single and double quotes. A backslash escapes
+ * the next character only when it is a quote, a backslash or a whitespace, so that Windows paths
+ * are preserved.
+ */
+pub fn split_command_line(command: &str) -> Vec<String> {
+    let mut arguments = Vec::new();
+    let mut current = String::new();
+    let mut in_argument = false;
+    let mut quote: Option<char> = None;
+
+    let mut chars = command.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, quote) {
+            ('\\', q) if q != Some('\'') => match chars.peek() {
+                Some(&next)
+                    if next == '"'
+                        || next == '\\'
+                        || (q.is_none() && (next == '\'' || next.is_whitespace())) =>
+                {
+                    current.push(next);
+                    chars.next();
+                    in_argument = true;
+                }
+                _ => {
+                    current.push(c);
+                    in_argument = true;
+                }
+            },
+            ('"' | '\'', None) => {
+                quote = Some(c);
+                in_argument = true;
+            }
+            (c, Some(q)) if c == q => quote = None,
+            (c, None) if c.is_whitespace() => {
+                if in_argument {
+                    arguments.push(std::mem::take(&mut current));
+                    in_argument = false;
+                }
+            }
+            (c, _) => {
+                current.push(c);
+                in_argument = true;
+            }
+        }
+    }
+
+    if in_argument {
+        arguments.push(current);
+    }
+
+    arguments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static DATABASE: &str = r#"[
+    {
+        "directory": "/media/workspace/Project/build",
+        "command": "/usr/bin/c++ -DWITH_EDITOR=1 -UNDEBUG -I../Source/Core/Public -isystem /usr/include/boost -include ../Source/Core/Public/Prefix.h -o main.o -c ../Source/main.cpp",
+        "file": "../Source/main.cpp"
+    },
+    {
+        "directory": "/media/workspace/Project/build",
+        "arguments": ["clang++", "-I", "../Source/Render/Public", "-iquote", "../Source", "-I../Source/Core/Public", "-c", "../Source/render.cpp"],
+        "file": "/media/workspace/Project/Source/render.cpp"
+    }
+]"#;
+
+    #[test]
+    fn compilation_database_parsing_test() -> Result<(), Box<dyn Error>> {
+        let database = CompilationDatabase::parse(DATABASE)?;
+        let build_dir = Path::new("/media/workspace/Project/build");
+
+        assert_eq!(2, database.get_commands().len());
+        assert_eq!(
+            vec![
+                build_dir.join("../Source/main.cpp"),
+                PathBuf::from("/media/workspace/Project/Source/render.cpp")
+            ],
+            database
+                .get_commands()
+                .iter()
+                .map(|c| c.get_file())
+                .collect::<Vec<_>>()
+        );
+
+        let main = &database.get_commands()[0];
+        assert_eq!(
+            vec![build_dir.join("../Source/Core/Public")],
+            main.get_user_dirs()
+        );
+        assert_eq!(
+            vec![PathBuf::from("/usr/include/boost")],
+            main.get_system_dirs()
+        );
+        assert_eq!(
+            vec![build_dir.join("../Source/Core/Public/Prefix.h")],
+            main.get_forced_includes()
+        );
+        assert_eq!(
+            vec![
+                MacroFlag::Define {
+                    name: String::from("WITH_EDITOR"),
+                    value: Some(String::from("1"))
+                },
+                MacroFlag::Undefine(String::from("NDEBUG")),
+            ],
+            main.get_macros()
+        );
+
+        let render = &database.get_commands()[1];
+        assert_eq!(
+            vec![
+                build_dir.join("../Source/Render/Public"),
+                build_dir.join("../Source/Core/Public")
+            ],
+            render.get_user_dirs()
+        );
+        assert_eq!(vec![build_dir.join("../Source")], render.get_quote_dirs());
+
+        Ok(())
+    }
+
+    #[test]
+    fn include_settings_union_test() -> Result<(), Box<dyn Error>> {
+        let database = CompilationDatabase::parse(DATABASE)?;
+        let additional =
+            IncludeSettings::make(vec![], vec![String::from("ThirdParty")], vec![], false);
+
+        let settings = database.make_include_settings(&additional);
+
+        assert_eq!(
+            vec![
+                String::from("/media/workspace/Project/build/../Source/Core/Public"),
+                String::from("/media/workspace/Project/build/../Source/Render/Public"),
+                String::from("ThirdParty"),
+            ],
+            settings.get_user_dirs()
+        );
+        assert_eq!(
+            vec![String::from("/usr/include/boost")],
+            settings.get_system_dirs()
+        );
+        assert!(!settings.is_suffix_fallback_enabled());
+
+        Ok(())
+    }
+
+    #[test]
+    fn msvc_flags_test() {
+        let arguments = split_command_line(
+            r#""C:\Program Files\LLVM\bin\clang-cl.exe" /DUE_BUILD_DEVELOPMENT=1 /I "C:\Project\Source" /FIPrefix.h -c C:\Project\Source\main.cpp"#,
+        );
+        let command = parse_arguments(&arguments, Path::new("C:\\Project"));
+
+        assert_eq!(
+            vec![MacroFlag::Define {
+                name: String::from("UE_BUILD_DEVELOPMENT"),
+                value: Some(String::from("1"))
+            }],
+            command.get_macros()
+        );
+        assert_eq!(1, command.get_user_dirs().len());
+        assert_eq!(1, command.get_forced_includes().len());
+    }
+
+    #[test]
+    fn similar_flags_ignored_test() {
+        let arguments = split_command_line("clang++ -include-pch pch.h.pch -c main.cpp");
+        let command = parse_arguments(&arguments, Path::new("/Data"));
+
+        assert!(command.get_forced_includes().is_empty());
+    }
+
+    #[test]
+    fn msvc_flags_ignored_for_other_drivers_test() {
+        let arguments = split_command_line("clang++ -c /Data/main.cpp");
+        let command = parse_arguments(&arguments, Path::new("/Data"));
+
+        assert!(command.get_macros().is_empty());
+    }
+
+    #[test]
+    fn split_command_line_test() {
+        assert_eq!(
+            vec![
+                "c++",
+                "-DNAME=\"value with spaces\"",
+                "-I",
+                "my dir",
+                "C:\\dir\\a.cpp"
+            ],
+            split_command_line(r#"c++ -DNAME=\"value\ with\ spaces\" -I 'my dir' C:\dir\a.cpp"#)
+        );
+        assert_eq!(
+            vec!["-Iquoted dir", "x"],
+            split_command_line(r#"  "-Iquoted dir"   x  "#)
+        );
+    }
+
+    #[test]
+    fn response_file_test() -> Result<(), Box<dyn Error>> {
+        let temp_dir = tempdir::TempDir::new("scar_compilation_database_test")?;
+        std::fs::write(temp_dir.path().join("main.rsp"), "-I include\n-DFOO")?;
+
+        let arguments = expand_response_files(
+            vec![String::from("c++"), String::from("@main.rsp")],
+            temp_dir.path(),
+            0,
+        )?;
+
+        assert_eq!(vec!["c++", "-I", "include", "-DFOO"], arguments);
+
+        Ok(())
+    }
+}
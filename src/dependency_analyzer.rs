@@ -1,40 +1,116 @@
 use crate::file::File;
+use crate::file::IncludeDirective;
+use crate::file::{IncludeKind, IncludeMode};
+use crate::include_graph::{Direction, IncludeGraph};
+use crate::include_resolver::{self, IncludeResolver, IncludeSettings, Origin, Resolution};
+use crate::preprocessor::{Conditionality, MacroTable};
+use crate::source_extensions::SourceExtensions;
 use colored::Colorize;
+use serde::Deserialize;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::collections::VecDeque;
 use std::error::Error;
+use std::io::Write;
+use std::path::Path;
+
+/**
+ * Maximum indentation level of the printed DFS trees, to keep very deep trees readable.
+ */
+const MAX_INDENTATION_LEVEL: usize = 32;
 
 pub struct DependencyAnalyzer<'a> {
-    _files: &'a [File],
+    files: &'a [File],
 
     /**
      * The hashmap containing dependencies.
-     * - key: the dependency file (e.g., "stdio.h")
+     * - key: the dependency file, identified by its path relative to the project root when it
+     *   belongs to the project (e.g., "Source/Public/foobar.h"), or by the include spelling
+     *   otherwise (e.g., "stdio.h")
      * - value: a set of files directly including the dependency file (e.g., "main.cpp",
-     * "foobar.cpp")
+     *   "Source/Private/foobar.cpp")
      */
     modules_inclusion: HashMap<&'a str, HashSet<&'a str>>,
 
+    /**
+     * The include edges of each file, in the same order of the include directives.
+     * - key: the including file path
+     * - value: the include edges, each one recording the file it resolved to
+     */
+    modules_dependencies: HashMap<&'a str, Vec<IncludeEdge<'a>>>,
+
+    /**
+     * The same dependencies of modules_inclusion, with integer node ids.
+     */
+    include_graph: IncludeGraph<'a>,
+
+    /**
+     * The extensions telling translation units and headers apart.
+     */
+    source_extensions: SourceExtensions,
+
     debug: bool,
 }
 
 impl<'a> DependencyAnalyzer<'a> {
+    /**
+     * Builds the analyzer resolving includes without any search directory, i.e., relative to the
+     * including file or by matching the project files ending with the include spelling.
+     */
     pub fn make(files: &'a [File], debug: bool) -> Result<DependencyAnalyzer<'a>, Box<dyn Error>> {
+        let resolver = IncludeResolver::make(Path::new(""), &IncludeSettings::default())?;
+        Self::make_with_resolver(files, &resolver, debug)
+    }
+
+    pub fn make_with_resolver(
+        files: &'a [File],
+        resolver: &IncludeResolver,
+        debug: bool,
+    ) -> Result<DependencyAnalyzer<'a>, Box<dyn Error>> {
+        Self::make_with_edge_filter(files, resolver, ConditionalEdges::All, debug)
+    }
+
+    /**
+     * Builds the analyzer keeping only the include directives accepted by the filter, depending
+     * on the conditions enclosing them (e.g., dropping the ones under "#if 0").
+     */
+    pub fn make_with_edge_filter(
+        files: &'a [File],
+        resolver: &IncludeResolver,
+        filter: ConditionalEdges,
+        debug: bool,
+    ) -> Result<DependencyAnalyzer<'a>, Box<dyn Error>> {
+        let index = ProjectIndex::make(
+            files.iter().map(|f| f.get_path()),
+            files
+                .iter()
+                .filter_map(|f| f.get_module_declaration())
+                .filter(|d| filter.accepts(d.get_conditionality()))
+                .map(|d| d.get_spelling()),
+        );
+
         let mut modules_inclusion = HashMap::new();
+        let mut modules_dependencies: HashMap<&'a str, Vec<IncludeEdge<'a>>> = HashMap::new();
 
         for f in files {
-            let path = f.get_name();
-            let current_file_name = Self::extract_filename_from_path(path);
+            let path = f.get_path();
+
+            let edges: Vec<IncludeEdge<'a>> = f
+                .get_include_directives()
+                .iter()
+                .filter(|directive| filter.accepts(directive.get_conditionality()))
+                .map(|directive| Self::resolve_include(path, directive, resolver, &index))
+                .collect();
 
-            let mut dependencies = HashSet::new();
-            f.get_used_modules().iter().for_each(|p| {
-                let dependency_name = Self::extract_filename_from_path(p);
-                dependencies.insert(dependency_name);
-            });
+            // include probes check whether files exist, without including them
+            let dependencies: HashSet<&'a str> = edges
+                .iter()
+                .filter(|e| !e.is_soft())
+                .map(|e| e.target)
+                .collect();
+            modules_dependencies.insert(path, edges);
 
-            modules_inclusion
-                .entry(current_file_name)
-                .or_insert(HashSet::new());
+            modules_inclusion.entry(path).or_insert(HashSet::new());
 
             for d in dependencies {
                 modules_inclusion
@@ -50,23 +126,332 @@ impl<'a> DependencyAnalyzer<'a> {
             }
         }
 
+        // C++20 modules are nodes including the files declaring them, so that importing a module
+        // depends on its interface units
+        for f in files {
+            let Some(declaration) = f
+                .get_module_declaration()
+                .filter(|d| filter.accepts(d.get_conditionality()))
+            else {
+                continue;
+            };
+
+            let (module, path) = (declaration.get_spelling(), f.get_path());
+            modules_dependencies
+                .entry(module)
+                .or_default()
+                .push(IncludeEdge {
+                    includer: module,
+                    directive: declaration,
+                    target: path,
+                    resolution: Resolution::Project {
+                        path: path.to_string(),
+                        origin: Origin::ModuleDeclaration,
+                    },
+                });
+            modules_inclusion.entry(module).or_default();
+            modules_inclusion.entry(path).or_default().insert(module);
+        }
+
+        let include_graph = IncludeGraph::make(&modules_inclusion);
+
         Ok(DependencyAnalyzer {
-            _files: files,
+            files,
             modules_inclusion,
+            modules_dependencies,
+            include_graph,
+            source_extensions: SourceExtensions::default(),
             debug,
         })
     }
 
+    /**
+     * Expands the computed includes which the macros of their own file could not expand (e.g.,
+     * "#include PLATFORM_HEADER(Foo.h)" with PLATFORM_HEADER defined by another header), with the
+     * macros defined along the include chains reaching them, i.e., by all the files included by
+     * the same root files. Expanded includes may lead to further files defining macros, so
+     * expansion is repeated until no more includes are expanded, resolving again only the
+     * includes of the files expanded.
+     * Returns the number of expanded includes.
+     *
+     * - macros: the macros defined before any file (e.g., "-D" flags)
+     * - root_macros: the macros defined before the root files of the include chains, following
+     *   the given ones, by root file path (e.g., the "-D" flags of each translation unit of a
+     *   compilation database)
+     */
+    pub fn expand_computed_includes(
+        files: &mut [File],
+        resolver: &IncludeResolver,
+        macros: &MacroTable,
+        root_macros: &HashMap<String, MacroTable>,
+    ) -> Result<usize, Box<dyn Error>> {
+        let paths: Vec<String> = files.iter().map(|f| f.get_path().to_string()).collect();
+        let modules: Vec<Option<String>> = files
+            .iter()
+            .map(|f| {
+                f.get_module_declaration()
+                    .map(|d| d.get_spelling().to_string())
+            })
+            .collect();
+        let index = ProjectIndex::make(
+            paths.iter().map(|p| p.as_str()),
+            modules.iter().flatten().map(|m| m.as_str()),
+        );
+
+        // the nodes included by each file, include probes excluded
+        let make_included_nodes = |file: &File| -> Vec<String> {
+            file.get_include_directives()
+                .iter()
+                .filter(|d| !d.is_soft())
+                .map(|d| DependencyAnalyzer::resolve_include(file.get_path(), d, resolver, &index))
+                .map(|e| e.target.to_string())
+                .collect()
+        };
+        let mut included_nodes: Vec<Vec<String>> = files.iter().map(make_included_nodes).collect();
+
+        let mut num_expanded = 0;
+        loop {
+            let expandable: Vec<usize> = (0..files.len())
+                .filter(|&i| files[i].has_unexpanded_includes())
+                .collect();
+            if expandable.is_empty() {
+                break;
+            }
+
+            let mut modules_inclusion: HashMap<&str, HashSet<&str>> = HashMap::new();
+            for (path, nodes) in paths.iter().zip(&included_nodes) {
+                modules_inclusion.entry(path).or_default();
+                for node in nodes {
+                    modules_inclusion.entry(node).or_default().insert(path);
+                }
+            }
+            for (path, module) in paths.iter().zip(&modules) {
+                if let Some(module) = module {
+                    modules_inclusion.entry(module).or_default();
+                    modules_inclusion.entry(path).or_default().insert(module);
+                }
+            }
+            let graph = IncludeGraph::make(&modules_inclusion);
+
+            let visible_macros = DependencyAnalyzer::make_visible_macros(
+                &graph,
+                files,
+                &expandable,
+                macros,
+                root_macros,
+            );
+
+            let mut expanded_files = Vec::new();
+            for (i, visible_macros) in visible_macros {
+                let expanded = files[i].expand_computed_includes(&visible_macros);
+                if expanded > 0 {
+                    num_expanded += expanded;
+                    expanded_files.push(i);
+                }
+            }
+            if expanded_files.is_empty() {
+                break;
+            }
+
+            for i in expanded_files {
+                included_nodes[i] = make_included_nodes(&files[i]);
+            }
+        }
+
+        Ok(num_expanded)
+    }
+
+    /**
+     * Returns, for the given files, the macros visible along the include chains reaching them:
+     * the given macros, followed by the ones of the root files including them, i.e., the files
+     * not included by any other one, and by the ones defined by the files reachable from these
+     * root files. The visible macros of all the files are computed in a single pass over the
+     * include graph.
+     */
+    fn make_visible_macros(
+        graph: &IncludeGraph,
+        files: &[File],
+        expandable: &[usize],
+        macros: &MacroTable,
+        root_macros: &HashMap<String, MacroTable>,
+    ) -> Vec<(usize, MacroTable)> {
+        let files_by_node: HashMap<usize, &File> = files
+            .iter()
+            .filter_map(|f| Some((graph.get_id(f.get_path())?, f)))
+            .collect();
+        let get_root_macros = |node: usize| match graph.get_includers(node).is_empty() {
+            true => root_macros.get(graph.get_node(node)),
+            false => None,
+        };
+        let is_marked = |node: usize| {
+            get_root_macros(node).is_some()
+                || files_by_node
+                    .get(&node)
+                    .is_some_and(|f| !f.get_macro_definitions().is_empty())
+        };
+
+        let targets: Vec<(usize, usize)> = expandable
+            .iter()
+            .filter_map(|&i| Some((i, graph.get_id(files[i].get_path())?)))
+            .collect();
+        let target_nodes: Vec<usize> = targets.iter().map(|&(_, node)| node).collect();
+        let reachable = graph.reachable_from_roots(&target_nodes, is_marked);
+
+        targets
+            .iter()
+            .zip(reachable)
+            .map(|(&(i, _), nodes)| {
+                let mut visible_macros = macros.clone();
+                // a root is reachable from the roots only when it is one of them
+                for &node in &nodes {
+                    if let Some(root_macros) = get_root_macros(node) {
+                        visible_macros.extend(root_macros);
+                    }
+                }
+                for node in &nodes {
+                    if let Some(file) = files_by_node.get(node) {
+                        visible_macros.extend(file.get_macro_definitions());
+                    }
+                }
+
+                (i, visible_macros)
+            })
+            .collect()
+    }
+
+    /**
+     * Sets the extensions identifying translation units and headers, replacing the default ones.
+     */
+    pub fn set_source_extensions(&mut self, extensions: &SourceExtensions) {
+        self.source_extensions = extensions.clone();
+    }
+
     pub fn get_inclusion_map(&self) -> &HashMap<&'a str, HashSet<&'a str>> {
         &self.modules_inclusion
     }
 
+    /**
+     * Returns the include edges of the given file, in the same order of its include directives.
+     */
+    pub fn get_include_edges(&self, path: &str) -> &[IncludeEdge<'a>] {
+        self.modules_dependencies
+            .get(path)
+            .map_or(&[], |edges| edges.as_slice())
+    }
+
+    /**
+     * Returns the include edges which could not be resolved to any file, sorted by including file.
+     * Include probes (e.g., "__has_include(<optional>)") are not included, since probing for
+     * missing files is what they are for.
+     */
+    pub fn get_unresolved_includes(&self) -> Vec<&IncludeEdge<'a>> {
+        let mut unresolved: Vec<&IncludeEdge<'a>> = self
+            .modules_dependencies
+            .values()
+            .flatten()
+            .filter(|e| !e.is_resolved() && !e.is_soft())
+            .collect();
+        unresolved.sort_by(|a, b| a.includer.cmp(b.includer));
+
+        unresolved
+    }
+
+    /**
+     * Returns the include probes (e.g., "__has_include(<optional>)"), sorted by including file.
+     * Probes are soft edges: they do not include the probed file, so they are not part of the
+     * include graph.
+     */
+    pub fn get_include_probes(&self) -> Vec<&IncludeEdge<'a>> {
+        self.get_sorted_edges(|e| e.is_soft())
+    }
+
+    /**
+     * Returns the includes of a file repeating a previous include of the same file, each one
+     * together with the first include it repeats, sorted by including file and line. Includes in
+     * different conditional branches (e.g., in the "#if" and "#else" of the same group) are not
+     * duplicates, while an include under some conditions repeats an include outside of them.
+     */
+    pub fn get_duplicate_includes(&self) -> Vec<(&IncludeEdge<'a>, &IncludeEdge<'a>)> {
+        let mut duplicates = Vec::new();
+
+        for edges in self.modules_dependencies.values() {
+            let edges: Vec<&IncludeEdge<'a>> = edges.iter().filter(|e| e.is_lintable()).collect();
+
+            for (index, e) in edges.iter().enumerate() {
+                let repeated = edges[..index].iter().find(|previous| {
+                    let (conditions, previous_conditions) = (
+                        e.directive.get_conditions(),
+                        previous.directive.get_conditions(),
+                    );
+                    previous.target == e.target
+                        && (conditions.starts_with(previous_conditions)
+                            || previous_conditions.starts_with(conditions))
+                });
+
+                if let Some(&previous) = repeated {
+                    duplicates.push((previous, *e));
+                }
+            }
+        }
+
+        duplicates.sort_by(|(_, a), (_, b)| {
+            a.includer
+                .cmp(b.includer)
+                .then(a.get_line().cmp(&b.get_line()))
+        });
+
+        duplicates
+    }
+
+    /**
+     * Returns the includes of a file resolved to the file itself, sorted by including file and
+     * line.
+     */
+    pub fn get_self_includes(&self) -> Vec<&IncludeEdge<'a>> {
+        self.get_sorted_edges(|e| e.is_lintable() && e.is_resolved() && e.target == e.includer)
+    }
+
+    /**
+     * Returns the includes of translation units (e.g., "#include \"foo.cpp\"") by other files,
+     * sorted by including file and line.
+     */
+    pub fn get_translation_unit_includes(&self) -> Vec<&IncludeEdge<'a>> {
+        self.get_sorted_edges(|e| {
+            e.is_lintable()
+                && e.is_resolved()
+                && e.target != e.includer
+                && self.is_translation_unit(e.target)
+        })
+    }
+
+    /**
+     * Returns the include edges accepted by the predicate, sorted by including file and line.
+     */
+    fn get_sorted_edges<P>(&self, predicate: P) -> Vec<&IncludeEdge<'a>>
+    where
+        P: Fn(&IncludeEdge<'a>) -> bool,
+    {
+        let mut edges: Vec<&IncludeEdge<'a>> = self
+            .modules_dependencies
+            .values()
+            .flatten()
+            .filter(|e| predicate(e))
+            .collect();
+        edges.sort_by(|a, b| {
+            a.includer
+                .cmp(b.includer)
+                .then(a.get_line().cmp(&b.get_line()))
+        });
+
+        edges
+    }
+
     /**
      * Returns the list of direct inclusions for the current file.
      * Useful when the actual number of direct inclusions is needed, without counting for multiple
      * levels of inclusions.
      */
-    pub fn get_sorted_inclusion(&self) -> Vec<DependencyEntry> {
+    pub fn get_sorted_inclusion(&self) -> Vec<DependencyEntry<'a>> {
         let mut included_files: Vec<&str> = self.modules_inclusion.keys().cloned().collect();
         // decreasing order: from most to least included
         included_files.sort_by(|&a, &b| {
@@ -92,8 +477,10 @@ impl<'a> DependencyAnalyzer<'a> {
     /**
      * Returns the list of dependency impacts, i.e., the actual number of files impacted by the
      * current file (considering multiple-levels of inclusions).
+     * Kept as the reference implementation of get_sorted_impact_counts, one traversal per file.
      */
-    pub fn get_sorted_impact(&self) -> Vec<DependencyEntry> {
+    #[cfg(test)]
+    fn get_sorted_impact(&self) -> Vec<DependencyEntry<'a>> {
         let included_files: Vec<&str> = self.modules_inclusion.keys().cloned().collect();
         assert!(!included_files.is_empty());
 
@@ -102,7 +489,7 @@ impl<'a> DependencyAnalyzer<'a> {
             match self.dfs_tree(inc) {
                 Ok(tree) => {
                     if self.debug {
-                        tree.print_tree(inc, 0);
+                        tree.print_tree(inc);
                     }
 
                     // create the impacted list from the visited files, excluding the current file
@@ -128,276 +515,2062 @@ impl<'a> DependencyAnalyzer<'a> {
         dependencies
     }
 
-    pub fn extract_filename_from_path(path: &str) -> &str {
-        match path.split("/").last() {
-            Some(last_token) => last_token,
-            None => path,
+    /**
+     * Returns the number of files impacted by each file, i.e., the same numbers of
+     * get_sorted_impact, sorted in decreasing order (ties sorted by file name).
+     * Impacts are computed once for the whole graph, instead of one traversal per file, which
+     * makes this suitable for large projects.
+     *
+     * - parallel: whether to distribute the computation over multiple threads
+     */
+    pub fn get_sorted_impact_counts(&self, parallel: bool) -> Vec<ImpactEntry<'a>> {
+        let graph = &self.include_graph;
+        if graph.is_empty() {
+            return Vec::new();
         }
-    }
 
-    fn dfs_tree(&self, start_node: &'a str) -> Result<DFSTree, Box<dyn Error>> {
-        assert!(!self.modules_inclusion.is_empty());
+        let weights = vec![1; graph.len()];
+        let impacts = graph.reachable_weight_sums(Direction::Includers, &weights, parallel);
 
-        if !self.modules_inclusion.contains_key(start_node) {
-            return Err(format!("Starting node {} not found.", start_node).into());
-        }
+        let mut entries: Vec<ImpactEntry<'a>> = impacts
+            .into_iter()
+            .enumerate()
+            .map(|(id, num_impacted_files)| ImpactEntry {
+                file_name: graph.get_node(id),
+                num_impacted_files: num_impacted_files as usize,
+            })
+            .collect();
 
-        let mut visited = HashSet::new();
-        let mut dfs_tree = DFSTree::make();
+        // decreasing order: from most to least impacting
+        entries.sort_by(|a, b| {
+            b.num_impacted_files
+                .cmp(&a.num_impacted_files)
+                .then_with(|| a.file_name.cmp(b.file_name))
+        });
 
-        fn dfs_recursive<'a>(
-            current: &'a str,
-            parent: Option<&'a str>,
-            adj_list: &HashMap<&'a str, HashSet<&'a str>>,
-            visited: &mut HashSet<&'a str>,
-            tree: &mut DFSTree<'a>,
-        ) {
-            visited.insert(current);
-            tree.visit_order.push(current);
-
-            if let Some(p) = parent {
-                tree.add_edge(p, current);
-            }
+        entries
+    }
 
-            if let Some(neighbors) = adj_list.get(current) {
-                for neighbor in neighbors {
-                    if !visited.contains(neighbor) {
-                        dfs_recursive(neighbor, Some(current), adj_list, visited, tree);
-                    }
-                }
-            }
+    /**
+     * Prints the tree of the files impacted by the given one, i.e., the files including it
+     * directly or indirectly, in debug mode only.
+     */
+    pub fn print_impact_tree(&self, path: &str) {
+        if !self.debug {
+            return;
         }
 
-        dfs_recursive(
-            start_node,
-            None,
-            &self.modules_inclusion,
-            &mut visited,
-            &mut dfs_tree,
-        );
+        let Some((&node, _)) = self.modules_inclusion.get_key_value(path) else {
+            println!("Error while printing impact tree: {} not found.", path);
+            return;
+        };
 
-        Ok(dfs_tree)
+        match self.dfs_tree(node) {
+            Ok(tree) => tree.print_tree(node),
+            Err(e) => println!("Error while printing impact tree: {}", e),
+        }
     }
-}
 
-#[derive(Debug)]
-pub struct DependencyEntry<'a> {
-    file_name: &'a str,
-    including_files_paths: HashSet<&'a str>,
-}
-
-impl<'a> DependencyEntry<'a> {
-    pub fn get_file_name(&self) -> &'a str {
-        self.file_name
-    }
+    /**
+     * Returns the include weight of each translation unit, i.e., the number of files it includes
+     * directly or indirectly, sorted in decreasing order (ties sorted by file name).
+     *
+     * - parallel: whether to distribute the computation over multiple threads
+     */
+    pub fn get_sorted_include_weights(&self, parallel: bool) -> Vec<IncludeWeightEntry<'a>> {
+        let graph = &self.include_graph;
+        if graph.is_empty() {
+            return Vec::new();
+        }
 
-    pub fn get_including_file_paths(&self) -> &HashSet<&'a str> {
-        &self.including_files_paths
-    }
-}
+        let weights = vec![1; graph.len()];
+        let closure_sizes = graph.reachable_weight_sums(Direction::Includes, &weights, parallel);
+
+        let mut entries: Vec<IncludeWeightEntry<'a>> = self
+            .files
+            .iter()
+            .map(|f| f.get_path())
+            .filter(|&path| self.is_translation_unit(path))
+            .filter_map(|path| graph.get_id(path))
+            .map(|id| IncludeWeightEntry {
+                file_name: graph.get_node(id),
+                num_included_files: closure_sizes[id] as usize,
+            })
+            .collect();
 
-#[derive(Debug)]
-struct DFSTree<'a> {
-    tree: HashMap<&'a str, Vec<&'a str>>,
-    visit_order: Vec<&'a str>,
-}
+        // decreasing order: from most to least including
+        entries.sort_by(|a, b| {
+            b.num_included_files
+                .cmp(&a.num_included_files)
+                .then_with(|| a.file_name.cmp(b.file_name))
+        });
 
-impl<'a> DFSTree<'a> {
-    fn make() -> Self {
-        DFSTree {
-            tree: HashMap::new(),
-            visit_order: Vec::new(),
-        }
+        entries
     }
 
-    fn add_edge(&mut self, parent: &'a str, child: &'a str) {
-        self.tree.entry(parent).or_insert_with(Vec::new).push(child);
-    }
-
-    fn print_tree(&self, node: &str, level: usize) {
-        let message = format!("{}{}", "    ".repeat(level), node);
-        match level % 5{
-            0 => println!("{}", message.red()),
-            1 => println!("{}", message.yellow()),
-            2 => println!("{}", message.green()),
-            3 => println!("{}", message.blue()),
-            4 => println!("{}", message.purple()),
-            _ => unreachable!(),
-        }
-        if let Some(children) = self.tree.get(node) {
-            for child in children {
-                self.print_tree(child, level + 1);
-            }
+    /**
+     * Returns the estimated preprocessed size of each translation unit, i.e., the sum of the sizes
+     * of the translation unit itself and of all the project files it includes directly or
+     * indirectly, sorted in decreasing order of lines (ties sorted by file name). Files outside of
+     * the project are not scanned, and therefore not counted.
+     *
+     * - parallel: whether to distribute the computation over multiple threads
+     */
+    pub fn get_sorted_preprocessed_sizes(&self, parallel: bool) -> Vec<PreprocessedSizeEntry<'a>> {
+        let graph = &self.include_graph;
+        if graph.is_empty() {
+            return Vec::new();
         }
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
 
-    static FIRST_FILE_CONTENT: &str = "\
-#include <iostream>
-#include \"foobar.h\"
-//#include \"commented_out.h\"
-/*#include \"another_commented_out.h\"
+        let lines = self.make_file_weights(|f| f.get_num_lines() as u64);
+        let bytes = self.make_file_weights(|f| f.get_num_bytes() as u64);
+        let included_lines = graph.reachable_weight_sums(Direction::Includes, &lines, parallel);
+        let included_bytes = graph.reachable_weight_sums(Direction::Includes, &bytes, parallel);
+
+        let mut entries: Vec<PreprocessedSizeEntry<'a>> = self
+            .files
+            .iter()
+            .map(|f| f.get_path())
+            .filter(|&path| self.is_translation_unit(path))
+            .filter_map(|path| graph.get_id(path))
+            .map(|id| PreprocessedSizeEntry {
+                file_name: graph.get_node(id),
+                num_lines: (lines[id] + included_lines[id]) as usize,
+                num_bytes: (bytes[id] + included_bytes[id]) as usize,
+            })
+            .collect();
 
-int main(void) {
-    printf(\"Hello world\");
-    return 0;
-};
-    ";
+        // decreasing order: from largest to smallest
+        entries.sort_by(|a, b| {
+            b.num_lines
+                .cmp(&a.num_lines)
+                .then_with(|| a.file_name.cmp(b.file_name))
+        });
 
-    static SECOND_FILE_CONTENT: &str = "\
-#include \"blablah.h\"
+        entries
+    }
 
-class Point {{
-    explicit Point() = default;
-    virtual ~Point() = default;
-}};
-    ";
+    /**
+     * Returns the estimated compile cost of each project header, i.e., its size multiplied by the
+     * number of translation units including it directly or indirectly, sorted in decreasing order
+     * of cost in lines (ties sorted by file name).
+     *
+     * - parallel: whether to distribute the computation over multiple threads
+     */
+    pub fn get_sorted_header_costs(&self, parallel: bool) -> Vec<HeaderCostEntry<'a>> {
+        let graph = &self.include_graph;
+        if graph.is_empty() {
+            return Vec::new();
+        }
 
-    static THIRD_FILE_CONTENT: &str = "\
-#include \"foobar.h\"
+        let num_translation_units = self.make_translation_unit_counts(parallel);
+
+        let mut entries: Vec<HeaderCostEntry<'a>> = self
+            .files
+            .iter()
+            .filter(|f| !self.is_translation_unit(f.get_path()))
+            .filter_map(|f| graph.get_id(f.get_path()).map(|id| (f, id)))
+            .map(|(f, id)| HeaderCostEntry {
+                file_name: graph.get_node(id),
+                num_lines: f.get_num_lines(),
+                num_bytes: f.get_num_bytes(),
+                num_translation_units: num_translation_units[id] as usize,
+            })
+            .collect();
 
-namespace Leviathan {
+        // decreasing order: from most to least expensive
+        entries.sort_by(|a, b| {
+            b.get_cost_lines()
+                .cmp(&a.get_cost_lines())
+                .then_with(|| a.file_name.cmp(b.file_name))
+        });
 
-void DoSomeStuff(uint8_t value) {}
+        entries
+    }
 
-}
-    ";
+    /**
+     * Returns the number of translation units impacted by each header, i.e., the number of
+     * translation units including it directly or indirectly, sorted in decreasing order (ties
+     * sorted by file name). Translation units themselves are not reported.
+     *
+     * - parallel: whether to distribute the computation over multiple threads
+     */
+    pub fn get_sorted_translation_unit_impact(&self, parallel: bool) -> Vec<ImpactEntry<'a>> {
+        let graph = &self.include_graph;
+        if graph.is_empty() {
+            return Vec::new();
+        }
 
-    fn create_sample_files() -> Result<Vec<File>, Box<dyn Error>> {
-        let first_name = "main.cpp";
-        let first = File::make(first_name, FIRST_FILE_CONTENT)?;
+        let num_translation_units = self.make_translation_unit_counts(parallel);
 
-        let second_name = "foobar.h";
-        let second = File::make(second_name, SECOND_FILE_CONTENT)?;
+        let mut entries: Vec<ImpactEntry<'a>> = (0..graph.len())
+            .filter(|&id| !self.is_translation_unit(graph.get_node(id)))
+            .map(|id| ImpactEntry {
+                file_name: graph.get_node(id),
+                num_impacted_files: num_translation_units[id] as usize,
+            })
+            .collect();
 
-        let third_name = "leviathan.h";
-        let third = File::make(third_name, THIRD_FILE_CONTENT)?;
+        // decreasing order: from most to least impacting
+        entries.sort_by(|a, b| {
+            b.num_impacted_files
+                .cmp(&a.num_impacted_files)
+                .then_with(|| a.file_name.cmp(b.file_name))
+        });
 
-        let fourth_name = "blablah.h";
-        let fourth = File::make(
-            fourth_name,
-            "\
-namespace BlaBlah {
+        entries
+    }
 
-}
-",
-        )?;
+    /**
+     * Returns the translation units including the given file directly or indirectly, sorted.
+     */
+    pub fn get_impacted_translation_units(&self, path: &str) -> Vec<&'a str> {
+        let graph = &self.include_graph;
+        let Some(start) = graph.get_id(path) else {
+            return Vec::new();
+        };
+
+        let mut visited = vec![false; graph.len()];
+        visited[start] = true;
+        let mut queue = VecDeque::from([start]);
+        let mut translation_units = Vec::new();
+
+        while let Some(current) = queue.pop_front() {
+            for &includer in graph.get_includers(current) {
+                if !visited[includer] {
+                    visited[includer] = true;
+                    queue.push_back(includer);
+
+                    if self.is_translation_unit(graph.get_node(includer)) {
+                        translation_units.push(graph.get_node(includer));
+                    }
+                }
+            }
+        }
 
-        Ok(vec![first, second, third, fourth])
+        translation_units.sort();
+        translation_units
     }
 
-    #[test]
-    fn simple_parsing_test() -> Result<(), Box<dyn Error>> {
-        let files = create_sample_files()?;
+    /**
+     * Returns, by node id, the number of translation units including each node directly or
+     * indirectly.
+     */
+    fn make_translation_unit_counts(&self, parallel: bool) -> Vec<u64> {
+        let graph = &self.include_graph;
+        let translation_units = (0..graph.len())
+            .map(|id| self.is_translation_unit(graph.get_node(id)) as u64)
+            .collect::<Vec<u64>>();
 
-        let debug = true;
-        let analyzer = DependencyAnalyzer::make(&files, debug)?;
-        let inclusion_map = analyzer.get_inclusion_map();
+        graph.reachable_weight_sums(Direction::Includers, &translation_units, parallel)
+    }
 
-        assert_eq!(5, inclusion_map.len());
+    /**
+     * Returns the weight of each node of the include graph, by node id, as given by the weight
+     * function for project files, 0 for the others.
+     */
+    fn make_file_weights(&self, weight: impl Fn(&File) -> u64) -> Vec<u64> {
+        let mut weights = vec![0; self.include_graph.len()];
+        for f in self.files {
+            if let Some(id) = self.include_graph.get_id(f.get_path()) {
+                weights[id] = weight(f);
+            }
+        }
 
-        let expected_main = HashSet::new();
-        assert_eq!(expected_main, inclusion_map["main.cpp"]);
+        weights
+    }
 
-        let expected_foobar = HashSet::from(["main.cpp", "leviathan.h"]);
-        assert_eq!(expected_foobar, inclusion_map["foobar.h"]);
+    /**
+     * Returns the include chains explaining why a file includes another one, directly or
+     * indirectly, e.g., [main.cpp -> a.h, a.h -> b.h].
+     * Files are identified by their path relative to the project root, or by a suffix of it
+     * (e.g., "b.h") when only one file matches.
+     *
+     * - from: the including file (e.g., "Source/Private/main.cpp")
+     * - to: the included file (e.g., "Source/Public/b.h")
+     * - query: which chains to return
+     */
+    pub fn get_include_chains(
+        &self,
+        from: &str,
+        to: &str,
+        query: IncludeChainQuery,
+    ) -> Result<Vec<IncludeChain<'a>>, Box<dyn Error>> {
+        let graph = &self.include_graph;
+        let from = self.find_node(from)?;
+        let to = self.find_node(to)?;
+
+        let paths = match query {
+            IncludeChainQuery::Shortest => graph.k_shortest_paths(from, to, 1),
+            IncludeChainQuery::KShortest(k) => graph.k_shortest_paths(from, to, k),
+            IncludeChainQuery::AllSimple(limit) => graph.simple_paths(from, to, limit),
+        };
+
+        let chains = paths
+            .iter()
+            .map(|path| IncludeChain {
+                edges: path
+                    .windows(2)
+                    .filter_map(|pair| {
+                        let (includer, included) =
+                            (graph.get_node(pair[0]), graph.get_node(pair[1]));
+                        self.get_include_edges(includer)
+                            .iter()
+                            .find(|e| e.target == included && !e.is_soft())
+                            .cloned()
+                    })
+                    .collect(),
+            })
+            .collect();
 
-        let expected_leviathan = HashSet::new();
-        assert_eq!(expected_leviathan, inclusion_map["leviathan.h"]);
+        Ok(chains)
+    }
 
-        let expected_iostream = HashSet::from(["main.cpp"]);
-        assert_eq!(expected_iostream, inclusion_map["iostream"]);
+    /**
+     * Returns the id of the node with the given path, or of the only node whose path ends with
+     * the given one.
+     */
+    fn find_node(&self, path: &str) -> Result<usize, Box<dyn Error>> {
+        let graph = &self.include_graph;
+        if let Some(id) = graph.get_id(path) {
+            return Ok(id);
+        }
+
+        let suffix = format!("/{}", path.trim_start_matches('/'));
+        let candidates: Vec<usize> = (0..graph.len())
+            .filter(|&id| graph.get_node(id).ends_with(&suffix))
+            .collect();
+
+        match candidates.as_slice() {
+            [id] => Ok(*id),
+            [] => Err(format!("File not found: {}", path).into()),
+            _ => Err(format!(
+                "Ambiguous file: {}, candidates: {}",
+                path,
+                candidates
+                    .iter()
+                    .map(|&id| graph.get_node(id))
+                    .collect::<Vec<&str>>()
+                    .join(", ")
+            )
+            .into()),
+        }
+    }
+
+    /**
+     * Returns the include cycles, i.e., the strongly connected components of the include graph
+     * made of more than one file, or of a single file including itself. Each cycle comes with an
+     * example include path going through it, starting from its lexicographically first file.
+     * Cycles are sorted by decreasing size.
+     */
+    pub fn get_include_cycles(&self) -> Vec<IncludeCycle<'a>> {
+        let graph = &self.include_graph;
+
+        let mut cycles: Vec<IncludeCycle<'a>> = graph
+            .strongly_connected_components()
+            .into_iter()
+            .filter(|c| c.len() > 1 || graph.get_includes(c[0]).contains(&c[0]))
+            .map(|component| {
+                let members: HashSet<usize> = component.iter().cloned().collect();
+                let start = component[0];
+                let example_path = graph
+                    .shortest_path(start, start, |id| members.contains(&id))
+                    .unwrap_or_default();
+
+                IncludeCycle {
+                    files: component.iter().map(|&id| graph.get_node(id)).collect(),
+                    example_path: example_path.iter().map(|&id| graph.get_node(id)).collect(),
+                }
+            })
+            .collect();
+
+        cycles.sort_by(|a, b| {
+            b.files
+                .len()
+                .cmp(&a.files.len())
+                .then_with(|| a.files.cmp(&b.files))
+        });
+
+        cycles
+    }
+
+    /**
+     * Returns the include guard problems of the project headers, i.e., the files which are not
+     * translation units: headers protected neither by "#pragma once" nor by a guard macro, guard
+     * macros shared by different headers, and guard macros not wrapping the whole header when
+     * there is no "#pragma once". Headers are sorted by path.
+     */
+    pub fn get_include_guard_issues(&self) -> IncludeGuardIssues<'a> {
+        let mut headers: Vec<&'a File> = self
+            .files
+            .iter()
+            .filter(|f| !self.is_translation_unit(f.get_path()))
+            .collect();
+        headers.sort_by_key(|f| f.get_path());
+
+        let mut files_by_guard: HashMap<&'a str, Vec<&'a str>> = HashMap::new();
+        for f in &headers {
+            if let Some(guard_macro) = f.get_include_guard().get_guard_macro() {
+                files_by_guard
+                    .entry(guard_macro)
+                    .or_default()
+                    .push(f.get_path());
+            }
+        }
+
+        let mut colliding_guards: Vec<GuardCollision<'a>> = files_by_guard
+            .into_iter()
+            .filter(|(_, files)| files.len() > 1)
+            .map(|(guard_macro, files)| GuardCollision { guard_macro, files })
+            .collect();
+        colliding_guards.sort_by_key(|c| c.guard_macro);
+
+        IncludeGuardIssues {
+            missing_guards: headers
+                .iter()
+                .filter(|f| f.get_include_guard().is_missing())
+                .cloned()
+                .collect(),
+            colliding_guards,
+            partial_guards: headers
+                .iter()
+                .filter(|f| {
+                    let guard = f.get_include_guard();
+                    guard.get_pragma_once_line().is_none()
+                        && guard.get_guard_macro().is_some()
+                        && !guard.wraps_file()
+                })
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /**
+     * Returns whether the given file is a translation unit, according to its extension.
+     */
+    pub fn is_translation_unit(&self, path: &str) -> bool {
+        self.source_extensions.is_translation_unit(path)
+    }
+
+    pub fn extract_filename_from_path(path: &str) -> &str {
+        match path.split("/").last() {
+            Some(last_token) => last_token,
+            None => path,
+        }
+    }
+
+    /**
+     * Resolves the include directive found in the includer file through the include resolver.
+     * When the resolver does not find the file and the suffix fallback is enabled, the project
+     * file whose path ends with the spelling is used, preferring the one closest to the includer
+     * when several files share the same name. Files outside the project and unresolved includes
+     * are identified by their spelling (e.g., system or third-party headers).
+     */
+    fn resolve_include(
+        includer: &'a str,
+        directive: &'a IncludeDirective,
+        resolver: &IncludeResolver,
+        index: &ProjectIndex<'a>,
+    ) -> IncludeEdge<'a> {
+        let spelling = directive.get_spelling();
+        if directive.get_kind() == IncludeKind::Module {
+            return IncludeEdge {
+                includer,
+                directive,
+                target: spelling,
+                resolution: Resolution::Module {
+                    node: spelling.to_string(),
+                    declared: index.declared_modules.contains(spelling),
+                },
+            };
+        }
+
+        let resolution = resolver.resolve_directive(includer, directive, &index.project_files);
+
+        let target = match &resolution {
+            Resolution::Project { path, .. } => index.project_files.get(path.as_str()).copied(),
+            Resolution::External { .. } | Resolution::Module { .. } => Some(spelling),
+            Resolution::Unresolved => None,
+        };
+
+        if let Some(target) = target {
+            return IncludeEdge {
+                includer,
+                directive,
+                target,
+                resolution,
+            };
+        }
+
+        if resolver.is_suffix_fallback_enabled() && directive.get_kind() != IncludeKind::Computed {
+            // #include_next never finds the including file itself
+            let excluded = directive.is_next().then_some(includer);
+            if let Some(path) =
+                Self::find_closest_by_suffix(includer, spelling, &index.files_by_name, excluded)
+            {
+                return IncludeEdge {
+                    includer,
+                    directive,
+                    target: path,
+                    resolution: Resolution::Project {
+                        path: path.to_string(),
+                        origin: Origin::SuffixMatch,
+                    },
+                };
+            }
+        }
+
+        IncludeEdge {
+            includer,
+            directive,
+            target: spelling,
+            resolution: Resolution::Unresolved,
+        }
+    }
+
+    fn find_closest_by_suffix(
+        includer: &str,
+        spelling: &str,
+        files_by_name: &HashMap<&'a str, Vec<&'a str>>,
+        excluded: Option<&str>,
+    ) -> Option<&'a str> {
+        let includer_dir = include_resolver::extract_directory_from_path(includer);
+        let normalized_spelling = include_resolver::normalize_path(spelling);
+        let suffix = format!("/{}", normalized_spelling);
+
+        files_by_name
+            .get(Self::extract_filename_from_path(&normalized_spelling))
+            .into_iter()
+            .flatten()
+            .filter(|&&c| c == normalized_spelling || c.ends_with(&suffix))
+            .filter(|&&c| Some(c) != excluded)
+            .max_by(|&&a, &&b| {
+                let a_score = Self::common_directory_depth(includer_dir, a);
+                let b_score = Self::common_directory_depth(includer_dir, b);
+                // on equal distance prefer the lexicographically smaller path, for determinism
+                a_score.cmp(&b_score).then_with(|| b.cmp(a))
+            })
+            .copied()
+    }
+
+    /**
+     * Returns the number of leading directories shared by the given directory and the directory
+     * containing the given file.
+     */
+    fn common_directory_depth(directory: &str, file: &str) -> usize {
+        directory
+            .split('/')
+            .zip(include_resolver::extract_directory_from_path(file).split('/'))
+            .take_while(|(a, b)| !a.is_empty() && a == b)
+            .count()
+    }
+
+    fn dfs_tree(&self, start_node: &'a str) -> Result<DFSTree<'a>, Box<dyn Error>> {
+        assert!(!self.modules_inclusion.is_empty());
+
+        if !self.modules_inclusion.contains_key(start_node) {
+            return Err(format!("Starting node {} not found.", start_node).into());
+        }
+
+        let mut visited = HashSet::from([start_node]);
+        let mut dfs_tree = DFSTree::make();
+        dfs_tree.visit_order.push(start_node);
+
+        // explicit stack of (node, its neighbors, position of the next neighbor to visit), so
+        // that arbitrarily deep inclusion chains do not overflow the call stack
+        let neighbors_of = |node: &'a str| -> Vec<&'a str> {
+            self.modules_inclusion
+                .get(node)
+                .map(|n| n.iter().cloned().collect())
+                .unwrap_or_default()
+        };
+        let mut stack = vec![(start_node, neighbors_of(start_node), 0)];
+
+        while let Some((current, neighbors, position)) = stack.last_mut() {
+            let current = *current;
+            let next = neighbors[*position..]
+                .iter()
+                .position(|n| !visited.contains(n))
+                .map(|offset| *position + offset);
+
+            match next {
+                Some(index) => {
+                    *position = index + 1;
+                    let neighbor = neighbors[index];
+
+                    visited.insert(neighbor);
+                    dfs_tree.visit_order.push(neighbor);
+                    dfs_tree.add_edge(current, neighbor);
+                    stack.push((neighbor, neighbors_of(neighbor), 0));
+                }
+                None => {
+                    stack.pop();
+                }
+            }
+        }
+
+        Ok(dfs_tree)
+    }
+}
+
+/**
+ * The project files and the C++20 modules they declare, which include directives are resolved
+ * against.
+ */
+struct ProjectIndex<'a> {
+    project_files: HashSet<&'a str>,
+
+    /**
+     * The project files by file name, for the suffix fallback (e.g., "foo.h" for
+     * "Source/Public/foo.h").
+     */
+    files_by_name: HashMap<&'a str, Vec<&'a str>>,
+
+    declared_modules: HashSet<&'a str>,
+}
+
+impl<'a> ProjectIndex<'a> {
+    fn make(
+        paths: impl Iterator<Item = &'a str>,
+        declared_modules: impl Iterator<Item = &'a str>,
+    ) -> Self {
+        let paths: Vec<&'a str> = paths.collect();
+
+        let mut files_by_name: HashMap<&'a str, Vec<&'a str>> = HashMap::new();
+        for &path in &paths {
+            files_by_name
+                .entry(DependencyAnalyzer::extract_filename_from_path(path))
+                .or_default()
+                .push(path);
+        }
+
+        ProjectIndex {
+            project_files: paths.into_iter().collect(),
+            files_by_name,
+            declared_modules: declared_modules.collect(),
+        }
+    }
+}
+
+/**
+ * An include directive of a file, together with the file it has been resolved to.
+ */
+#[derive(Debug, Clone)]
+pub struct IncludeEdge<'a> {
+    includer: &'a str,
+    directive: &'a IncludeDirective,
+    target: &'a str,
+    resolution: Resolution,
+}
+
+impl<'a> IncludeEdge<'a> {
+    pub fn get_includer(&self) -> &'a str {
+        self.includer
+    }
+
+    /**
+     * Returns the include directive of the edge, locating it in the includer.
+     */
+    pub fn get_directive(&self) -> &'a IncludeDirective {
+        self.directive
+    }
+
+    pub fn get_spelling(&self) -> &'a str {
+        self.directive.get_spelling()
+    }
+
+    /**
+     * Returns the 1-based line of the include directive in the includer, 0 for forced includes.
+     */
+    pub fn get_line(&self) -> usize {
+        self.directive.get_line()
+    }
+
+    /**
+     * Returns the node the edge points to: the project-relative path for project files, the
+     * include spelling otherwise.
+     */
+    pub fn get_target(&self) -> &'a str {
+        self.target
+    }
+
+    pub fn get_resolution(&self) -> &Resolution {
+        &self.resolution
+    }
+
+    pub fn is_resolved(&self) -> bool {
+        self.resolution != Resolution::Unresolved
+    }
+
+    /**
+     * Returns the file where the directive of the edge is written: the includer, except for
+     * module declarations, where the includer is the module node and the directive is in the
+     * declaring file.
+     */
+    pub fn get_directive_file(&self) -> &'a str {
+        match self.directive.get_mode() {
+            IncludeMode::ModuleDeclaration => self.target,
+            _ => self.includer,
+        }
+    }
+
+    /**
+     * Returns whether the edge comes from a directive written in the includer itself, and
+     * actually including the target, i.e., neither an include probe, nor a forced include, nor
+     * the declaration of a module.
+     */
+    fn is_lintable(&self) -> bool {
+        !self.is_soft()
+            && !self.directive.is_forced()
+            && self.directive.get_mode() != IncludeMode::ModuleDeclaration
+    }
+
+    /**
+     * Returns whether the edge is an include probe (e.g., "__has_include(<optional>)"), which
+     * does not include the probed file.
+     */
+    pub fn is_soft(&self) -> bool {
+        self.directive.is_soft()
+    }
+
+    /**
+     * Returns whether the include directive is always compiled, only under some conditions, or
+     * never.
+     */
+    pub fn get_conditionality(&self) -> Conditionality {
+        self.directive.get_conditionality()
+    }
+}
+
+/**
+ * Which include directives become edges of the analysis, depending on the conditions enclosing
+ * them.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConditionalEdges {
+    /// All the include directives, dead ones included.
+    #[default]
+    All,
+    /// The include directives which may be compiled, i.e., not dead (e.g., under "#if 0").
+    Live,
+    /// The include directives compiled regardless of the defined macros.
+    Always,
+}
+
+impl ConditionalEdges {
+    pub fn accepts(&self, conditionality: Conditionality) -> bool {
+        match self {
+            ConditionalEdges::All => true,
+            ConditionalEdges::Live => conditionality != Conditionality::Dead,
+            ConditionalEdges::Always => conditionality == Conditionality::Always,
+        }
+    }
+}
+
+/**
+ * Which include chains to search for between two files.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IncludeChainQuery {
+    /// The shortest chain only.
+    #[default]
+    Shortest,
+    /// The k shortest chains.
+    KShortest(usize),
+    /// All the chains without repeated files, up to the given number of chains.
+    AllSimple(usize),
+}
+
+/**
+ * A sequence of include directives leading from a file to another one.
+ */
+#[derive(Debug)]
+pub struct IncludeChain<'a> {
+    edges: Vec<IncludeEdge<'a>>,
+}
+
+impl<'a> IncludeChain<'a> {
+    /**
+     * Returns the include directives of the chain, from the first including file.
+     */
+    pub fn get_edges(&self) -> &[IncludeEdge<'a>] {
+        &self.edges
+    }
+
+    /**
+     * Returns the files of the chain, from the first including file to the last included one.
+     */
+    pub fn get_files(&self) -> Vec<&'a str> {
+        self.edges
+            .first()
+            .map(|e| e.includer)
+            .into_iter()
+            .chain(self.edges.iter().map(|e| e.target))
+            .collect()
+    }
+}
+
+/**
+ * A set of files including each other, directly or indirectly.
+ */
+#[derive(Debug)]
+pub struct IncludeCycle<'a> {
+    files: Vec<&'a str>,
+    example_path: Vec<&'a str>,
+}
+
+impl<'a> IncludeCycle<'a> {
+    /**
+     * Returns the files of the cycle, sorted.
+     */
+    pub fn get_files(&self) -> &[&'a str] {
+        &self.files
+    }
+
+    /**
+     * Returns an include path through the cycle, whose first and last files are the same one
+     * (e.g., ["a.h", "b.h", "a.h"]).
+     */
+    pub fn get_example_path(&self) -> &[&'a str] {
+        &self.example_path
+    }
+}
+
+/**
+ * The include guard problems of the project headers.
+ */
+pub struct IncludeGuardIssues<'a> {
+    missing_guards: Vec<&'a File>,
+    colliding_guards: Vec<GuardCollision<'a>>,
+    partial_guards: Vec<&'a File>,
+}
+
+impl<'a> IncludeGuardIssues<'a> {
+    /**
+     * Returns the headers protected neither by "#pragma once" nor by a guard macro.
+     */
+    pub fn get_missing_guards(&self) -> &[&'a File] {
+        &self.missing_guards
+    }
+
+    /**
+     * Returns the guard macros shared by different headers, sorted by macro.
+     */
+    pub fn get_colliding_guards(&self) -> &[GuardCollision<'a>] {
+        &self.colliding_guards
+    }
+
+    /**
+     * Returns the headers whose guard macro does not wrap the whole file, without
+     * "#pragma once".
+     */
+    pub fn get_partial_guards(&self) -> &[&'a File] {
+        &self.partial_guards
+    }
+}
+
+/**
+ * A guard macro shared by different headers, only the first of which can be included.
+ */
+#[derive(Debug)]
+pub struct GuardCollision<'a> {
+    guard_macro: &'a str,
+    files: Vec<&'a str>,
+}
+
+impl<'a> GuardCollision<'a> {
+    pub fn get_guard_macro(&self) -> &'a str {
+        self.guard_macro
+    }
+
+    /**
+     * Returns the headers sharing the guard macro, sorted.
+     */
+    pub fn get_files(&self) -> &[&'a str] {
+        &self.files
+    }
+}
+
+#[derive(Debug)]
+pub struct ImpactEntry<'a> {
+    file_name: &'a str,
+    num_impacted_files: usize,
+}
+
+impl<'a> ImpactEntry<'a> {
+    pub fn get_file_name(&self) -> &'a str {
+        self.file_name
+    }
+
+    pub fn get_num_impacted_files(&self) -> usize {
+        self.num_impacted_files
+    }
+}
+
+#[derive(Debug)]
+pub struct IncludeWeightEntry<'a> {
+    file_name: &'a str,
+    num_included_files: usize,
+}
+
+impl<'a> IncludeWeightEntry<'a> {
+    pub fn get_file_name(&self) -> &'a str {
+        self.file_name
+    }
+
+    pub fn get_num_included_files(&self) -> usize {
+        self.num_included_files
+    }
+}
+
+#[derive(Debug)]
+pub struct PreprocessedSizeEntry<'a> {
+    file_name: &'a str,
+    num_lines: usize,
+    num_bytes: usize,
+}
+
+impl<'a> PreprocessedSizeEntry<'a> {
+    pub fn get_file_name(&self) -> &'a str {
+        self.file_name
+    }
+
+    pub fn get_num_lines(&self) -> usize {
+        self.num_lines
+    }
+
+    pub fn get_num_bytes(&self) -> usize {
+        self.num_bytes
+    }
+}
+
+#[derive(Debug)]
+pub struct HeaderCostEntry<'a> {
+    file_name: &'a str,
+    num_lines: usize,
+    num_bytes: usize,
+    num_translation_units: usize,
+}
+
+impl<'a> HeaderCostEntry<'a> {
+    pub fn get_file_name(&self) -> &'a str {
+        self.file_name
+    }
+
+    /**
+     * Returns the number of lines of the header itself.
+     */
+    pub fn get_num_lines(&self) -> usize {
+        self.num_lines
+    }
+
+    /**
+     * Returns the size in bytes of the header itself.
+     */
+    pub fn get_num_bytes(&self) -> usize {
+        self.num_bytes
+    }
+
+    /**
+     * Returns the number of translation units including the header, directly or indirectly.
+     */
+    pub fn get_num_translation_units(&self) -> usize {
+        self.num_translation_units
+    }
+
+    /**
+     * Returns the total number of lines the header adds to the build.
+     */
+    pub fn get_cost_lines(&self) -> usize {
+        self.num_lines * self.num_translation_units
+    }
+
+    /**
+     * Returns the total number of bytes the header adds to the build.
+     */
+    pub fn get_cost_bytes(&self) -> usize {
+        self.num_bytes * self.num_translation_units
+    }
+}
+
+#[derive(Debug)]
+pub struct DependencyEntry<'a> {
+    file_name: &'a str,
+    including_files_paths: HashSet<&'a str>,
+}
+
+impl<'a> DependencyEntry<'a> {
+    pub fn get_file_name(&self) -> &'a str {
+        self.file_name
+    }
+
+    pub fn get_including_file_paths(&self) -> &HashSet<&'a str> {
+        &self.including_files_paths
+    }
+}
+
+#[derive(Debug)]
+struct DFSTree<'a> {
+    tree: HashMap<&'a str, Vec<&'a str>>,
+    visit_order: Vec<&'a str>,
+}
+
+impl<'a> DFSTree<'a> {
+    fn make() -> Self {
+        DFSTree {
+            tree: HashMap::new(),
+            visit_order: Vec::new(),
+        }
+    }
+
+    fn add_edge(&mut self, parent: &'a str, child: &'a str) {
+        self.tree.entry(parent).or_default().push(child);
+    }
+
+    fn print_tree(&self, root: &str) {
+        if let Err(e) = self.write_tree(&mut std::io::stdout().lock(), root) {
+            println!("Error while printing tree: {}", e);
+        }
+    }
+
+    /**
+     * Writes the tree rooted at the given node, one node per line, indented by level. Indentation
+     * is capped to MAX_INDENTATION_LEVEL, deeper nodes being prefixed by their level instead.
+     */
+    fn write_tree(&self, out: &mut impl Write, root: &str) -> std::io::Result<()> {
+        let mut stack = vec![(root, 0)];
+
+        while let Some((node, level)) = stack.pop() {
+            let message = if level <= MAX_INDENTATION_LEVEL {
+                format!("{}{}", "    ".repeat(level), node)
+            } else {
+                format!(
+                    "{}[{}] {}",
+                    "    ".repeat(MAX_INDENTATION_LEVEL),
+                    level,
+                    node
+                )
+            };
+
+            match level % 5 {
+                0 => writeln!(out, "{}", message.red())?,
+                1 => writeln!(out, "{}", message.yellow())?,
+                2 => writeln!(out, "{}", message.green())?,
+                3 => writeln!(out, "{}", message.blue())?,
+                4 => writeln!(out, "{}", message.purple())?,
+                _ => unreachable!(),
+            }
+
+            // children are pushed in reverse order, so that they are written in order
+            if let Some(children) = self.tree.get(node) {
+                stack.extend(children.iter().rev().map(|&child| (child, level + 1)));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compilation_database::MacroFlag;
+
+    static FIRST_FILE_CONTENT: &str = "\
+#include <iostream>
+#include \"foobar.h\"
+//#include \"commented_out.h\"
+/*#include \"another_commented_out.h\"
+#include \"commented_out_block.h\" */
+
+int main(void) {
+    printf(\"Hello world\");
+    return 0;
+};
+    ";
+
+    static SECOND_FILE_CONTENT: &str = "\
+#include \"blablah.h\"
+
+class Point {{
+    explicit Point() = default;
+    virtual ~Point() = default;
+}};
+    ";
+
+    static THIRD_FILE_CONTENT: &str = "\
+#include \"foobar.h\"
+
+namespace Leviathan {
+
+void DoSomeStuff(uint8_t value) {}
+
+}
+    ";
+
+    fn create_sample_files() -> Result<Vec<File>, Box<dyn Error>> {
+        let first_name = "main.cpp";
+        let first = File::make(first_name, FIRST_FILE_CONTENT)?;
+
+        let second_name = "foobar.h";
+        let second = File::make(second_name, SECOND_FILE_CONTENT)?;
+
+        let third_name = "leviathan.h";
+        let third = File::make(third_name, THIRD_FILE_CONTENT)?;
+
+        let fourth_name = "blablah.h";
+        let fourth = File::make(
+            fourth_name,
+            "\
+namespace BlaBlah {
+
+}
+",
+        )?;
+
+        Ok(vec![first, second, third, fourth])
+    }
+
+    #[test]
+    fn simple_parsing_test() -> Result<(), Box<dyn Error>> {
+        let files = create_sample_files()?;
+
+        let debug = true;
+        let analyzer = DependencyAnalyzer::make(&files, debug)?;
+        let inclusion_map = analyzer.get_inclusion_map();
+
+        assert_eq!(5, inclusion_map.len());
+
+        let expected_main = HashSet::new();
+        assert_eq!(expected_main, inclusion_map["main.cpp"]);
+
+        let expected_foobar = HashSet::from(["main.cpp", "leviathan.h"]);
+        assert_eq!(expected_foobar, inclusion_map["foobar.h"]);
+
+        let expected_leviathan = HashSet::new();
+        assert_eq!(expected_leviathan, inclusion_map["leviathan.h"]);
+
+        let expected_iostream = HashSet::from(["main.cpp"]);
+        assert_eq!(expected_iostream, inclusion_map["iostream"]);
+
+        let expected_blablah = HashSet::from(["foobar.h"]);
+        assert_eq!(expected_blablah, inclusion_map["blablah.h"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn path_parsing_test_simple() {
+        let simple_path = "include/foobar.h";
+        assert_eq!(
+            "foobar.h",
+            DependencyAnalyzer::extract_filename_from_path(simple_path)
+        );
+    }
+
+    #[test]
+    fn path_parsing_test_single_token() {
+        let simple_path = "foobar.h";
+        assert_eq!(
+            "foobar.h",
+            DependencyAnalyzer::extract_filename_from_path(simple_path)
+        );
+    }
+
+    #[test]
+    fn path_parsing_test_backslash() {
+        // Note: C and C++ standards explicitly say that it's UB using something other than '/'.
+        // Hence here we assume that our logic has to report the full string slice in case of
+        // backslash.
+        let simple_path = "include\\foobar.h";
+        assert_eq!(
+            "include\\foobar.h",
+            DependencyAnalyzer::extract_filename_from_path(simple_path)
+        );
+    }
+
+    #[test]
+    fn top_included_sort_test() -> Result<(), Box<dyn Error>> {
+        let files = create_sample_files()?;
+
+        let debug = true;
+        let analyzer = DependencyAnalyzer::make(&files, debug)?;
+        let sorted_list = analyzer.get_sorted_inclusion();
+
+        let expected = [
+            ("foobar.h", 2),
+            ("iostream", 1),
+            ("blablah.h", 1),
+            ("main.cpp", 0),
+            ("leviathan.h", 0),
+        ];
+
+        assert_eq!(5, sorted_list.len());
+
+        for e in expected.into_iter() {
+            assert!(sorted_list
+                .iter()
+                .any(|entry| entry.file_name == e.0 && entry.including_files_paths.len() == e.1));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn top_impact_sort_test() -> Result<(), Box<dyn Error>> {
+        let files = create_sample_files()?;
+
+        let debug = true;
+        let analyzer = DependencyAnalyzer::make(&files, debug)?;
+        let sorted_impacts = analyzer.get_sorted_impact();
+
+        let expected = [
+            ("foobar.h", 2),
+            ("iostream", 1),
+            ("blablah.h", 3),
+            ("main.cpp", 0),
+            ("leviathan.h", 0),
+        ];
+
+        assert_eq!(5, sorted_impacts.len());
+
+        for e in expected.into_iter() {
+            assert!(sorted_impacts
+                .iter()
+                .any(|entry| entry.file_name == e.0 && entry.including_files_paths.len() == e.1));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn same_name_headers_are_distinct_nodes_test() -> Result<(), Box<dyn Error>> {
+        let files = vec![
+            File::make("Engine/Core/utils.h", "")?,
+            File::make("Engine/Render/utils.h", "")?,
+            File::make("Engine/Core/core.cpp", "#include \"utils.h\"")?,
+            File::make("Engine/Render/render.cpp", "#include \"utils.h\"")?,
+            File::make("Engine/Render/mesh.cpp", "#include \"Core/utils.h\"")?,
+        ];
+
+        let analyzer = DependencyAnalyzer::make(&files, false)?;
+        let inclusion_map = analyzer.get_inclusion_map();
+
+        assert_eq!(5, inclusion_map.len());
+        assert_eq!(
+            HashSet::from(["Engine/Core/core.cpp", "Engine/Render/mesh.cpp"]),
+            inclusion_map["Engine/Core/utils.h"]
+        );
+        assert_eq!(
+            HashSet::from(["Engine/Render/render.cpp"]),
+            inclusion_map["Engine/Render/utils.h"]
+        );
+        assert!(!inclusion_map.contains_key("utils.h"));
 
-        let expected_blablah = HashSet::from(["foobar.h"]);
-        assert_eq!(expected_blablah, inclusion_map["blablah.h"]);
+        Ok(())
+    }
+
+    #[test]
+    fn include_resolution_closest_candidate_test() -> Result<(), Box<dyn Error>> {
+        let files = vec![
+            File::make("Plugins/Foo/Source/types.h", "")?,
+            File::make("Source/Game/Public/types.h", "")?,
+            File::make("Source/Game/Private/game.cpp", "#include \"types.h\"")?,
+            File::make(
+                "Source/Game/Private/parent.cpp",
+                "#include \"../Public/types.h\"",
+            )?,
+        ];
+
+        let analyzer = DependencyAnalyzer::make(&files, false)?;
+        let inclusion_map = analyzer.get_inclusion_map();
+
+        assert_eq!(
+            HashSet::from([
+                "Source/Game/Private/game.cpp",
+                "Source/Game/Private/parent.cpp"
+            ]),
+            inclusion_map["Source/Game/Public/types.h"]
+        );
+        assert!(inclusion_map["Plugins/Foo/Source/types.h"].is_empty());
 
         Ok(())
     }
 
     #[test]
-    fn path_parsing_test_simple() {
-        let simple_path = "include/foobar.h";
+    fn include_edges_resolution_test() -> Result<(), Box<dyn Error>> {
+        let files = vec![
+            File::make("Source/Core/Public/types.h", "")?,
+            File::make("Source/Render/Public/types.h", "")?,
+            File::make(
+                "Source/Render/Private/render.cpp",
+                "#include <types.h>\n#include \"missing.h\"\n#include <vector>",
+            )?,
+        ];
+
+        let settings = IncludeSettings::make(
+            vec![],
+            vec![String::from("Source/Core/Public")],
+            vec![],
+            false,
+        );
+        let resolver = IncludeResolver::make(Path::new(""), &settings)?;
+        let analyzer = DependencyAnalyzer::make_with_resolver(&files, &resolver, false)?;
+
+        let edges = analyzer.get_include_edges("Source/Render/Private/render.cpp");
+        assert_eq!(3, edges.len());
+
+        // the user include directory wins over the closest file with the same name
+        assert_eq!("Source/Core/Public/types.h", edges[0].get_target());
         assert_eq!(
-            "foobar.h",
-            DependencyAnalyzer::extract_filename_from_path(simple_path)
+            &Resolution::Project {
+                path: String::from("Source/Core/Public/types.h"),
+                origin: Origin::SearchDirectory(0),
+            },
+            edges[0].get_resolution()
+        );
+
+        assert_eq!("missing.h", edges[1].get_target());
+        assert!(!edges[1].is_resolved());
+        assert_eq!("vector", edges[2].get_target());
+        assert!(!edges[2].is_resolved());
+
+        let unresolved: Vec<&str> = analyzer
+            .get_unresolved_includes()
+            .iter()
+            .map(|e| e.get_spelling())
+            .collect();
+        assert_eq!(vec!["missing.h", "vector"], unresolved);
+
+        let impacts = analyzer.get_sorted_impact();
+        let core_types = impacts
+            .iter()
+            .find(|e| e.get_file_name() == "Source/Core/Public/types.h")
+            .ok_or("Missing impact entry")?;
+        assert_eq!(
+            &HashSet::from(["Source/Render/Private/render.cpp"]),
+            core_types.get_including_file_paths()
         );
+
+        Ok(())
     }
 
     #[test]
-    fn path_parsing_test_single_token() {
-        let simple_path = "foobar.h";
+    fn suffix_fallback_origin_test() -> Result<(), Box<dyn Error>> {
+        let files = vec![
+            File::make("include/nested/nested.h", "")?,
+            File::make("main.cpp", "#include \"nested.h\"")?,
+        ];
+
+        let analyzer = DependencyAnalyzer::make(&files, false)?;
+        let edges = analyzer.get_include_edges("main.cpp");
+
+        assert_eq!("include/nested/nested.h", edges[0].get_target());
         assert_eq!(
-            "foobar.h",
-            DependencyAnalyzer::extract_filename_from_path(simple_path)
+            &Resolution::Project {
+                path: String::from("include/nested/nested.h"),
+                origin: Origin::SuffixMatch,
+            },
+            edges[0].get_resolution()
         );
+
+        Ok(())
     }
 
     #[test]
-    fn path_parsing_test_backslash() {
-        // Note: C and C++ standards explicitly say that it's UB using something other than '/'.
-        // Hence here we assume that our logic has to report the full string slice in case of
-        // backslash.
-        let simple_path = "include\\foobar.h";
+    fn include_cycles_test() -> Result<(), Box<dyn Error>> {
+        let files = vec![
+            File::make("main.cpp", "#include \"a.h\"\n#include \"self.h\"")?,
+            File::make("a.h", "#include \"b.h\"")?,
+            File::make("b.h", "#include \"c.h\"\n#include \"d.h\"")?,
+            File::make("c.h", "#include \"a.h\"")?,
+            File::make("d.h", "#include \"e.h\"")?,
+            File::make("e.h", "#include \"d.h\"\n#include \"b.h\"")?,
+            File::make("self.h", "#include \"self.h\"")?,
+        ];
+
+        let analyzer = DependencyAnalyzer::make(&files, false)?;
+        let cycles = analyzer.get_include_cycles();
+
+        assert_eq!(2, cycles.len());
+
         assert_eq!(
-            "include\\foobar.h",
-            DependencyAnalyzer::extract_filename_from_path(simple_path)
+            vec!["a.h", "b.h", "c.h", "d.h", "e.h"],
+            cycles[0].get_files()
+        );
+        assert_eq!(
+            vec!["a.h", "b.h", "c.h", "a.h"],
+            cycles[0].get_example_path()
         );
+
+        assert_eq!(vec!["self.h"], cycles[1].get_files());
+        assert_eq!(vec!["self.h", "self.h"], cycles[1].get_example_path());
+
+        Ok(())
     }
 
     #[test]
-    fn top_included_sort_test() -> Result<(), Box<dyn Error>> {
+    fn no_include_cycles_test() -> Result<(), Box<dyn Error>> {
         let files = create_sample_files()?;
 
-        let debug = true;
-        let analyzer = DependencyAnalyzer::make(&files, debug)?;
-        let sorted_list = analyzer.get_sorted_inclusion();
+        let analyzer = DependencyAnalyzer::make(&files, false)?;
 
-        let expected = [
-            ("foobar.h", 2),
-            ("iostream", 1),
-            ("blablah.h", 1),
-            ("main.cpp", 0),
-            ("leviathan.h", 0),
-        ];
+        assert!(analyzer.get_include_cycles().is_empty());
 
-        assert_eq!(5, sorted_list.len());
+        Ok(())
+    }
 
-        for e in expected.into_iter() {
-            assert!(sorted_list
+    fn assert_same_impacts(analyzer: &DependencyAnalyzer) {
+        let expected: HashMap<&str, usize> = analyzer
+            .get_sorted_impact()
+            .iter()
+            .map(|e| (e.get_file_name(), e.get_including_file_paths().len()))
+            .collect();
+
+        for parallel in [false, true] {
+            let impacts = analyzer.get_sorted_impact_counts(parallel);
+            let actual: HashMap<&str, usize> = impacts
                 .iter()
-                .any(|entry| entry.file_name == e.0 && entry.including_files_paths.len() == e.1));
+                .map(|e| (e.get_file_name(), e.get_num_impacted_files()))
+                .collect();
+            assert_eq!(expected, actual);
+
+            assert!(impacts
+                .windows(2)
+                .all(|w| w[0].get_num_impacted_files() >= w[1].get_num_impacted_files()));
         }
+    }
+
+    #[test]
+    fn impact_counts_match_impact_test() -> Result<(), Box<dyn Error>> {
+        let files = create_sample_files()?;
+        let analyzer = DependencyAnalyzer::make(&files, false)?;
+
+        assert_same_impacts(&analyzer);
 
         Ok(())
     }
 
     #[test]
-    fn top_impact_sort_test() -> Result<(), Box<dyn Error>> {
+    fn impact_counts_match_impact_on_test_projects_test() -> Result<(), Box<dyn Error>> {
+        for project in ["tests/simple", "tests/complex", "tests/cycles"] {
+            let mut scanner = crate::project_scanner::ProjectScanner::make(Path::new(project))?;
+            let files = scanner.scan_files()?;
+            let analyzer = DependencyAnalyzer::make(&files, false)?;
+
+            assert_same_impacts(&analyzer);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn empty_project_test() -> Result<(), Box<dyn Error>> {
+        let analyzer = DependencyAnalyzer::make(&[], false)?;
+
+        assert!(analyzer.get_sorted_impact_counts(false).is_empty());
+        assert!(analyzer.get_sorted_impact_counts(true).is_empty());
+        assert!(analyzer.get_sorted_include_weights(false).is_empty());
+        assert!(analyzer.get_sorted_preprocessed_sizes(false).is_empty());
+        assert!(analyzer.get_sorted_header_costs(false).is_empty());
+        assert!(analyzer
+            .get_sorted_translation_unit_impact(false)
+            .is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn include_weights_test() -> Result<(), Box<dyn Error>> {
+        let mut files = create_sample_files()?;
+        files.push(File::make("other.cpp", "#include \"leviathan.h\"")?);
+        files.push(File::make("empty.cpp", "")?);
+
+        let analyzer = DependencyAnalyzer::make(&files, false)?;
+        let weights: Vec<(&str, usize)> = analyzer
+            .get_sorted_include_weights(false)
+            .iter()
+            .map(|e| (e.get_file_name(), e.get_num_included_files()))
+            .collect();
+
+        // headers are not reported, only translation units
+        assert_eq!(
+            vec![("main.cpp", 3), ("other.cpp", 3), ("empty.cpp", 0)],
+            weights
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn compile_cost_test() -> Result<(), Box<dyn Error>> {
+        let files = vec![
+            File::make("a.cpp", "#include \"big.h\"\n#include \"small.h\"\n")?,
+            File::make("b.cpp", "#include \"small.h\"\n")?,
+            File::make(
+                "big.h",
+                "#include <vector>\n#include \"small.h\"\n\n\n\n\n\n\n\n\n",
+            )?,
+            File::make("small.h", "#pragma once\n")?,
+        ];
+
+        let analyzer = DependencyAnalyzer::make(&files, false)?;
+
+        let sizes: Vec<(&str, usize, usize)> = analyzer
+            .get_sorted_preprocessed_sizes(false)
+            .iter()
+            .map(|e| (e.get_file_name(), e.get_num_lines(), e.get_num_bytes()))
+            .collect();
+        assert_eq!(vec![("a.cpp", 13, 94), ("b.cpp", 2, 32)], sizes);
+
+        let costs: Vec<(&str, usize, usize, usize)> = analyzer
+            .get_sorted_header_costs(false)
+            .iter()
+            .map(|e| {
+                (
+                    e.get_file_name(),
+                    e.get_num_translation_units(),
+                    e.get_cost_lines(),
+                    e.get_cost_bytes(),
+                )
+            })
+            .collect();
+        assert_eq!(vec![("big.h", 1, 10, 45), ("small.h", 2, 2, 26)], costs);
+
+        Ok(())
+    }
+
+    #[test]
+    fn translation_unit_test() -> Result<(), Box<dyn Error>> {
         let files = create_sample_files()?;
+        let mut analyzer = DependencyAnalyzer::make(&files, false)?;
 
-        let debug = true;
-        let analyzer = DependencyAnalyzer::make(&files, debug)?;
-        let sorted_impacts = analyzer.get_sorted_impact();
+        assert!(analyzer.is_translation_unit("Source/main.cpp"));
+        assert!(analyzer.is_translation_unit("lib.c"));
+        assert!(!analyzer.is_translation_unit("Source/main.h"));
+        assert!(!analyzer.is_translation_unit("iostream"));
+        assert!(!analyzer.is_translation_unit("Source.cpp/main"));
 
-        let expected = [
-            ("foobar.h", 2),
-            ("iostream", 1),
-            ("blablah.h", 3),
-            ("main.cpp", 0),
-            ("leviathan.h", 0),
+        analyzer.set_source_extensions(
+            &SourceExtensions::default().with_translation_unit_extensions(vec![String::from("mm")]),
+        );
+        assert!(analyzer.is_translation_unit("Source/main.mm"));
+        assert!(!analyzer.is_translation_unit("Source/main.cpp"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn translation_unit_impact_test() -> Result<(), Box<dyn Error>> {
+        let files = vec![
+            File::make("a.cpp", "#include \"common.h\"")?,
+            File::make("b.cpp", "#include \"common.h\"\n#include \"b.h\"")?,
+            File::make("test.inl", "#include \"b.h\"")?,
+            File::make("common.h", "#include \"base.h\"")?,
+            File::make("b.h", "#include \"base.h\"")?,
+            File::make("base.h", "")?,
         ];
 
-        assert_eq!(5, sorted_impacts.len());
+        let mut analyzer = DependencyAnalyzer::make(&files, false)?;
+        let impacts: Vec<(&str, usize)> = analyzer
+            .get_sorted_translation_unit_impact(false)
+            .iter()
+            .map(|e| (e.get_file_name(), e.get_num_impacted_files()))
+            .collect();
+        assert_eq!(
+            vec![("base.h", 2), ("common.h", 2), ("b.h", 1), ("test.inl", 0)],
+            impacts
+        );
+        assert_eq!(
+            vec!["a.cpp", "b.cpp"],
+            analyzer.get_impacted_translation_units("base.h")
+        );
 
-        for e in expected.into_iter() {
-            assert!(sorted_impacts
+        analyzer.set_source_extensions(
+            &SourceExtensions::default()
+                .with_translation_unit_extensions(vec![String::from("cpp"), String::from("inl")]),
+        );
+        assert_eq!(
+            vec!["a.cpp", "b.cpp", "test.inl"],
+            analyzer.get_impacted_translation_units("base.h")
+        );
+        assert_eq!(
+            vec![("base.h", 3), ("b.h", 2), ("common.h", 2)],
+            analyzer
+                .get_sorted_translation_unit_impact(false)
                 .iter()
-                .any(|entry| entry.file_name == e.0 && entry.including_files_paths.len() == e.1));
-        }
+                .map(|e| (e.get_file_name(), e.get_num_impacted_files()))
+                .collect::<Vec<_>>()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn include_chains_test() -> Result<(), Box<dyn Error>> {
+        let files = vec![
+            File::make(
+                "Source/main.cpp",
+                "#include <vector>\n#include \"a.h\"\n#include \"c.h\"",
+            )?,
+            File::make("Source/a.h", "#pragma once\n\n#include \"b.h\"")?,
+            File::make("Source/b.h", "#include \"c.h\"")?,
+            File::make("Source/c.h", "#include <vector>")?,
+        ];
+
+        let analyzer = DependencyAnalyzer::make(&files, false)?;
+        let describe = |chain: &IncludeChain| -> Vec<(String, usize)> {
+            chain
+                .get_edges()
+                .iter()
+                .map(|e| (e.get_includer().to_string(), e.get_line()))
+                .collect()
+        };
+
+        let chains = analyzer.get_include_chains(
+            "Source/main.cpp",
+            "vector",
+            IncludeChainQuery::Shortest,
+        )?;
+        assert_eq!(1, chains.len());
+        assert_eq!(vec!["Source/main.cpp", "vector"], chains[0].get_files());
+        assert_eq!(
+            vec![(String::from("Source/main.cpp"), 1)],
+            describe(&chains[0])
+        );
+
+        let chains =
+            analyzer.get_include_chains("main.cpp", "c.h", IncludeChainQuery::KShortest(2))?;
+        assert_eq!(2, chains.len());
+        assert_eq!(vec!["Source/main.cpp", "Source/c.h"], chains[0].get_files());
+        assert_eq!(
+            vec!["Source/main.cpp", "Source/a.h", "Source/b.h", "Source/c.h"],
+            chains[1].get_files()
+        );
+        assert_eq!(
+            vec![
+                (String::from("Source/main.cpp"), 2),
+                (String::from("Source/a.h"), 3),
+                (String::from("Source/b.h"), 1)
+            ],
+            describe(&chains[1])
+        );
+
+        let chains = analyzer.get_include_chains(
+            "Source/main.cpp",
+            "vector",
+            IncludeChainQuery::AllSimple(10),
+        )?;
+        assert_eq!(3, chains.len());
+
+        assert!(analyzer
+            .get_include_chains("c.h", "a.h", IncludeChainQuery::Shortest)?
+            .is_empty());
+        assert!(analyzer
+            .get_include_chains("missing.h", "a.h", IncludeChainQuery::Shortest)
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn computed_includes_test() -> Result<(), Box<dyn Error>> {
+        let mut files = vec![
+            File::make("main.cpp", "#include \"platform.h\"\n#include \"foo.h\"")?,
+            File::make(
+                "platform.h",
+                "#define STRINGIFY_INNER(x) #x\n#define STRINGIFY(x) STRINGIFY_INNER(x)\n\
+#define PLATFORM_HEADER(name) STRINGIFY(PLATFORM/name)",
+            )?,
+            File::make(
+                "foo.h",
+                "#include PLATFORM_HEADER(foo_impl.h)\n#include UNKNOWN_HEADER",
+            )?,
+            File::make("Windows/foo_impl.h", "")?,
+        ];
+        let resolver = IncludeResolver::make(Path::new(""), &IncludeSettings::default())?;
+
+        // the macros of the translation unit, e.g., from a compilation database
+        let root_macros = HashMap::from([(
+            String::from("main.cpp"),
+            MacroTable::make(&[MacroFlag::parse_define("PLATFORM=Windows")]),
+        )]);
+        assert_eq!(
+            1,
+            DependencyAnalyzer::expand_computed_includes(
+                &mut files,
+                &resolver,
+                &MacroTable::default(),
+                &root_macros
+            )?
+        );
+
+        let analyzer = DependencyAnalyzer::make_with_resolver(&files, &resolver, false)?;
+        let edges = analyzer.get_include_edges("foo.h");
+        assert_eq!("Windows/foo_impl.h", edges[0].get_target());
+        assert!(edges[0].get_directive().is_computed());
+        assert!(!edges[1].is_resolved());
+        assert_eq!("UNKNOWN_HEADER", edges[1].get_spelling());
+        assert_eq!(
+            vec!["UNKNOWN_HEADER"],
+            analyzer
+                .get_unresolved_includes()
+                .iter()
+                .map(|e| e.get_spelling())
+                .collect::<Vec<_>>()
+        );
+
+        // the file defining B_HEADER is only reached once CONFIG_HEADER is expanded
+        let mut files = vec![
+            File::make("main.cpp", "#include \"a.h\"\n#include \"b.h\"")?,
+            File::make("a.h", "#include CONFIG_HEADER")?,
+            File::make("b.h", "#include B_HEADER")?,
+            File::make("cfg.h", "#define B_HEADER \"b_impl.h\"")?,
+            File::make("b_impl.h", "")?,
+        ];
+        let macros = MacroTable::make(&[MacroFlag::parse_define("CONFIG_HEADER=\"cfg.h\"")]);
+        assert_eq!(
+            2,
+            DependencyAnalyzer::expand_computed_includes(
+                &mut files,
+                &resolver,
+                &macros,
+                &HashMap::new()
+            )?
+        );
+        assert_eq!(
+            "b_impl.h",
+            files[2].get_include_directives()[0].get_spelling()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn include_next_and_probes_test() -> Result<(), Box<dyn Error>> {
+        let files = vec![
+            File::make(
+                "main.cpp",
+                "#if __has_include(<missing.h>) || __has_include(\"config.h\")\n#endif\n\
+                 #include \"wrapper/stdlib.h\"",
+            )?,
+            File::make("config.h", "")?,
+            File::make("wrapper/stdlib.h", "#include_next <stdlib.h>")?,
+            File::make("libc/stdlib.h", "")?,
+        ];
+
+        let analyzer = DependencyAnalyzer::make(&files, false)?;
+        let edges = analyzer.get_include_edges("wrapper/stdlib.h");
+        assert_eq!("libc/stdlib.h", edges[0].get_target());
+
+        // probes are neither unresolved includes nor edges of the include graph
+        assert!(analyzer.get_unresolved_includes().is_empty());
+        let probes: Vec<(&str, bool)> = analyzer
+            .get_include_probes()
+            .iter()
+            .map(|e| (e.get_spelling(), e.is_resolved()))
+            .collect();
+        assert_eq!(vec![("missing.h", false), ("config.h", true)], probes);
+        assert!(analyzer.get_inclusion_map()["config.h"].is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn include_lints_test() -> Result<(), Box<dyn Error>> {
+        let files = vec![
+            File::make(
+                "main.cpp",
+                "\
+#include \"a.h\"
+#ifdef _WIN32
+#include \"b.h\"
+#else
+#include \"b.h\"
+#endif
+#ifdef WITH_EDITOR
+#include \"a.h\"
+#endif
+#include \"other.cpp\"
+#include <vector>
+#include <vector>",
+            )?,
+            File::make("a.h", "#include \"a.h\"")?,
+            File::make("b.h", "")?,
+            File::make("other.cpp", "")?,
+        ];
+
+        let analyzer = DependencyAnalyzer::make(&files, false)?;
+
+        let duplicates: Vec<(usize, usize, &str)> = analyzer
+            .get_duplicate_includes()
+            .iter()
+            .map(|(first, second)| (first.get_line(), second.get_line(), second.get_target()))
+            .collect();
+        assert_eq!(vec![(1, 8, "a.h"), (11, 12, "vector")], duplicates);
+
+        let self_includes = analyzer.get_self_includes();
+        assert_eq!(1, self_includes.len());
+        assert_eq!("a.h", self_includes[0].get_includer());
+
+        let translation_unit_includes = analyzer.get_translation_unit_includes();
+        assert_eq!(1, translation_unit_includes.len());
+        assert_eq!(10, translation_unit_includes[0].get_line());
+
+        Ok(())
+    }
+
+    #[test]
+    fn include_guard_issues_test() -> Result<(), Box<dyn Error>> {
+        let files = vec![
+            File::make("main.cpp", "#include \"a.h\"\n#include \"b.h\"")?,
+            File::make("a.h", "#ifndef COMMON_H\n#define COMMON_H\n#endif")?,
+            File::make("b.h", "#ifndef COMMON_H\n#define COMMON_H\n#endif")?,
+            File::make("once.h", "#pragma once\nint once();")?,
+            File::make("none.h", "int none();")?,
+            File::make(
+                "partial.h",
+                "#include \"a.h\"\n#ifndef P_H\n#define P_H\n#endif",
+            )?,
+            File::make(
+                "partial_once.h",
+                "#pragma once\n#ifndef PO_H\n#define PO_H\n#endif\nint po();",
+            )?,
+        ];
+
+        let analyzer = DependencyAnalyzer::make(&files, false)?;
+        let issues = analyzer.get_include_guard_issues();
+        let paths = |files: &[&File]| -> Vec<String> {
+            files.iter().map(|f| f.get_path().to_string()).collect()
+        };
+
+        assert_eq!(vec!["none.h"], paths(issues.get_missing_guards()));
+        assert_eq!(vec!["partial.h"], paths(issues.get_partial_guards()));
+        assert_eq!(1, issues.get_colliding_guards().len());
+        assert_eq!(
+            "COMMON_H",
+            issues.get_colliding_guards()[0].get_guard_macro()
+        );
+        assert_eq!(
+            vec!["a.h", "b.h"],
+            issues.get_colliding_guards()[0].get_files()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn modules_test() -> Result<(), Box<dyn Error>> {
+        let files = vec![
+            File::make(
+                "foo.cppm",
+                "module;\n#include \"config.h\"\nexport module foo;\nexport import :part;",
+            )?,
+            File::make(
+                "foo-part.cppm",
+                "export module foo:part;\nimport \"part.h\";",
+            )?,
+            File::make("foo.cpp", "module foo;")?,
+            File::make("main.cpp", "import foo;\nimport std;")?,
+            File::make("config.h", "")?,
+            File::make("part.h", "")?,
+        ];
+
+        let analyzer = DependencyAnalyzer::make(&files, false)?;
+        let impacts: HashMap<&str, usize> = analyzer
+            .get_sorted_impact_counts(false)
+            .iter()
+            .map(|e| (e.get_file_name(), e.get_num_impacted_files()))
+            .collect();
+        assert_eq!(6, impacts["part.h"]);
+        assert_eq!(3, impacts["foo.cppm"]);
+        assert_eq!(2, impacts["module:foo"]);
+        assert_eq!(1, impacts["module:std"]);
+
+        let std_import = &analyzer.get_include_edges("main.cpp")[1];
+        assert_eq!(
+            &Resolution::Module {
+                node: String::from("module:std"),
+                declared: false
+            },
+            std_import.get_resolution()
+        );
+        assert!(analyzer.get_unresolved_includes().is_empty());
+
+        let chains =
+            analyzer.get_include_chains("main.cpp", "part.h", IncludeChainQuery::Shortest)?;
+        assert_eq!(
+            vec![
+                "main.cpp",
+                "module:foo",
+                "foo.cppm",
+                "module:foo:part",
+                "foo-part.cppm",
+                "part.h"
+            ],
+            chains[0].get_files()
+        );
+        assert_eq!(
+            vec![1, 3, 4, 1, 2],
+            chains[0]
+                .get_edges()
+                .iter()
+                .map(|e| e.get_line())
+                .collect::<Vec<_>>()
+        );
+
+        // conditional declarations are filtered like conditional includes
+        let files = vec![
+            File::make("foo.cppm", "#ifdef WITH_FOO\nexport module foo;\n#endif")?,
+            File::make("main.cpp", "import foo;")?,
+        ];
+        let resolver = IncludeResolver::make(Path::new(""), &IncludeSettings::default())?;
+        let impact = |filter: ConditionalEdges| -> Result<usize, Box<dyn Error>> {
+            let analyzer =
+                DependencyAnalyzer::make_with_edge_filter(&files, &resolver, filter, false)?;
+            Ok(analyzer
+                .get_sorted_impact_counts(false)
+                .iter()
+                .find(|e| e.get_file_name() == "foo.cppm")
+                .map_or(0, |e| e.get_num_impacted_files()))
+        };
+        assert_eq!(2, impact(ConditionalEdges::All)?);
+        assert_eq!(0, impact(ConditionalEdges::Always)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn conditional_edges_test() -> Result<(), Box<dyn Error>> {
+        let files = vec![
+            File::make(
+                "main.cpp",
+                "\
+#include \"common.h\"
+#ifdef _WIN32
+#include \"windows.h\"
+#endif
+#if 0
+#include \"legacy.h\"
+#endif",
+            )?,
+            File::make("common.h", "#ifndef COMMON_H\n#define COMMON_H\n#endif")?,
+            File::make("windows.h", "#include \"common.h\"")?,
+            File::make("legacy.h", "")?,
+        ];
+
+        let conditionalities: Vec<Conditionality> = DependencyAnalyzer::make(&files, false)?
+            .get_include_edges("main.cpp")
+            .iter()
+            .map(|e| e.get_conditionality())
+            .collect();
+        assert_eq!(
+            vec![
+                Conditionality::Always,
+                Conditionality::Conditional,
+                Conditionality::Dead
+            ],
+            conditionalities
+        );
+
+        let resolver = IncludeResolver::make(Path::new(""), &IncludeSettings::default())?;
+        let impacts = |filter: ConditionalEdges| -> Result<Vec<(String, usize)>, Box<dyn Error>> {
+            let analyzer =
+                DependencyAnalyzer::make_with_edge_filter(&files, &resolver, filter, false)?;
+            let mut impacts: Vec<(String, usize)> = analyzer
+                .get_sorted_impact_counts(false)
+                .iter()
+                .map(|e| (e.get_file_name().to_string(), e.get_num_impacted_files()))
+                .filter(|(_, n)| *n > 0)
+                .collect();
+            impacts.sort();
+            Ok(impacts)
+        };
+
+        assert_eq!(
+            vec![
+                (String::from("common.h"), 2),
+                (String::from("legacy.h"), 1),
+                (String::from("windows.h"), 1)
+            ],
+            impacts(ConditionalEdges::All)?
+        );
+        assert_eq!(
+            vec![
+                (String::from("common.h"), 2),
+                (String::from("windows.h"), 1)
+            ],
+            impacts(ConditionalEdges::Live)?
+        );
+        // windows.h still includes common.h, but main.cpp does not include windows.h anymore
+        assert_eq!(
+            vec![(String::from("common.h"), 2)],
+            impacts(ConditionalEdges::Always)?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn dfs_tree_visit_test() -> Result<(), Box<dyn Error>> {
+        let files = create_sample_files()?;
+        let analyzer = DependencyAnalyzer::make(&files, false)?;
+
+        let tree = analyzer.dfs_tree("blablah.h")?;
+        assert_eq!("blablah.h", tree.visit_order[0]);
+        assert_eq!("foobar.h", tree.visit_order[1]);
+        assert_eq!(
+            HashSet::from(["main.cpp", "leviathan.h"]),
+            tree.visit_order[2..].iter().cloned().collect()
+        );
+
+        let mut output = Vec::new();
+        tree.write_tree(&mut output, "blablah.h")?;
+        let output = String::from_utf8(output)?;
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(4, lines.len());
+        assert!(lines[0].contains("blablah.h"));
+        assert!(lines[1].contains("    foobar.h"));
+        assert!(lines[2].contains("        "));
+
+        Ok(())
+    }
+
+    #[test]
+    fn very_deep_include_chain_test() -> Result<(), Box<dyn Error>> {
+        // header_i includes header_(i-1), i.e., the first header impacts all the others
+        const CHAIN_LENGTH: usize = 100_000;
+        let files = (0..CHAIN_LENGTH)
+            .map(|i| {
+                let content = match i {
+                    0 => String::new(),
+                    _ => format!("#include \"header_{}.h\"", i - 1),
+                };
+                File::make(&format!("header_{}.h", i), &content)
+            })
+            .collect::<Result<Vec<File>, _>>()?;
+
+        let analyzer = DependencyAnalyzer::make(&files, false)?;
+
+        let impacts = analyzer.get_sorted_impact_counts(false);
+        assert_eq!(CHAIN_LENGTH, impacts.len());
+        assert_eq!("header_0.h", impacts[0].get_file_name());
+        assert_eq!(CHAIN_LENGTH - 1, impacts[0].get_num_impacted_files());
+        assert_eq!(0, impacts[CHAIN_LENGTH - 1].get_num_impacted_files());
+
+        let tree = analyzer.dfs_tree("header_0.h")?;
+        assert_eq!(CHAIN_LENGTH, tree.visit_order.len());
+        assert_eq!("header_99999.h", tree.visit_order[CHAIN_LENGTH - 1]);
+
+        let mut output = Vec::new();
+        tree.write_tree(&mut output, "header_0.h")?;
+        let output = String::from_utf8(output)?;
+        assert_eq!(CHAIN_LENGTH, output.lines().count());
+        assert!(output
+            .lines()
+            .last()
+            .is_some_and(|l| l.contains("[99999] header_99999.h")));
 
         Ok(())
     }
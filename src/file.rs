@@ -1,51 +1,784 @@
-use regex::Regex;
+use crate::lexer::{self, LogicalLine};
+use crate::preprocessor::{
+    self, Condition, ConditionalStack, Conditionality, IncludeGuard, MacroTable,
+};
+
+/**
+ * The include delimiter, which determines where the compiler looks for the included file.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IncludeKind {
+    /// #include "file.h"
+    Quoted,
+    /// #include <file.h>
+    Angled,
+    /// #include MACRO(file.h), whose macros could not be expanded into a file name.
+    Computed,
+    /// import foo; naming a C++20 module instead of a file.
+    Module,
+}
+
+/**
+ * The directive naming an included file or module, which determines how it is resolved and
+ * whether it is actually included.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IncludeMode {
+    /// #include
+    Include,
+    /// #include_next, searching the directories following the one of the including file.
+    IncludeNext,
+    /// __has_include in #if and #elif, which checks whether the file exists without including
+    /// it.
+    HasInclude,
+    /// __has_include_next in #if and #elif.
+    HasIncludeNext,
+    /// import <file.h>; importing a header unit.
+    ImportHeader,
+    /// import foo; or import :part; importing a C++20 module, also implied by module
+    /// implementation units (module foo;).
+    ImportModule,
+    /// export module foo; or module foo:part; declaring the module implemented by the file.
+    ModuleDeclaration,
+}
+
+/**
+ * An include directive, as written in the source file.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncludeDirective {
+    spelling: String,
+    kind: IncludeKind,
+    mode: IncludeMode,
+    line: usize,
+    column: usize,
+    text: String,
+    comment: Option<String>,
+    conditions: Vec<Condition>,
+
+    /**
+     * The macro expression of computed includes (e.g., "PLATFORM_HEADER(Foo.h)").
+     */
+    macro_expression: Option<String>,
+
+    /**
+     * The number of blank lines between the directive and the previous include directive, when
+     * nothing else separates them.
+     */
+    blank_lines_before: Option<usize>,
+
+    /**
+     * Whether some of those blank lines contain comments.
+     */
+    has_comments_before: bool,
+}
+
+impl IncludeDirective {
+    /**
+     * Builds a forced include (e.g., "-include file"), which has no location in the file.
+     */
+    fn make_forced(spelling: &str) -> Self {
+        IncludeDirective {
+            spelling: spelling.to_string(),
+            kind: IncludeKind::Quoted,
+            mode: IncludeMode::Include,
+            line: 0,
+            column: 0,
+            text: format!("-include {}", spelling),
+            comment: None,
+            conditions: Vec::new(),
+            macro_expression: None,
+            blank_lines_before: None,
+            has_comments_before: false,
+        }
+    }
+
+    /**
+     * Returns the included file name as written between the delimiters (e.g., "Public/foo.h"),
+     * after expanding the macros of computed includes. Computed includes which could not be
+     * expanded are spelled with their macro expression.
+     */
+    pub fn get_spelling(&self) -> &str {
+        &self.spelling
+    }
+
+    pub fn get_kind(&self) -> IncludeKind {
+        self.kind
+    }
+
+    pub fn get_mode(&self) -> IncludeMode {
+        self.mode
+    }
+
+    /**
+     * Returns whether the directive only checks that the file exists (e.g.,
+     * "#if __has_include(<optional>)"), instead of including it.
+     */
+    pub fn is_soft(&self) -> bool {
+        matches!(
+            self.mode,
+            IncludeMode::HasInclude | IncludeMode::HasIncludeNext
+        )
+    }
+
+    /**
+     * Returns whether the file is searched starting from the directory following the one where
+     * the including file has been found (e.g., "#include_next <stdlib.h>").
+     */
+    pub fn is_next(&self) -> bool {
+        matches!(
+            self.mode,
+            IncludeMode::IncludeNext | IncludeMode::HasIncludeNext
+        )
+    }
+
+    /**
+     * Returns the 1-based line of the directive, 0 for forced includes.
+     */
+    pub fn get_line(&self) -> usize {
+        self.line
+    }
+
+    /**
+     * Returns the 1-based column of the '#' of the directive, 0 for forced includes. For
+     * __has_include, the column of the '#' of the enclosing #if or #elif.
+     */
+    pub fn get_column(&self) -> usize {
+        self.column
+    }
+
+    /**
+     * Returns the directive as written, without the surrounding whitespace, comments and line
+     * continuations (e.g., "#include <vector>"). For __has_include, the probe only (e.g.,
+     * "__has_include(<optional>)").
+     */
+    pub fn get_text(&self) -> &str {
+        &self.text
+    }
+
+    /**
+     * Returns the text of the trailing comment, if any.
+     */
+    pub fn get_comment(&self) -> Option<&str> {
+        self.comment.as_deref()
+    }
+
+    /**
+     * Returns the conditional branches enclosing the directive, from the outermost one, include
+     * guards excluded (e.g., "#ifdef WITH_EDITOR").
+     */
+    pub fn get_conditions(&self) -> &[Condition] {
+        &self.conditions
+    }
+
+    /**
+     * Returns whether the directive is always compiled, only under some conditions, or never
+     * (e.g., under "#if 0").
+     */
+    pub fn get_conditionality(&self) -> Conditionality {
+        Conditionality::of(&self.conditions)
+    }
+
+    /**
+     * Returns the macro expression of computed includes (e.g., "CONFIG_HEADER" for
+     * "#include CONFIG_HEADER"), None for includes of a file name.
+     */
+    pub fn get_macro_expression(&self) -> Option<&str> {
+        self.macro_expression.as_deref()
+    }
+
+    /**
+     * Returns the number of blank lines (comments included) between the include and the
+     * previous include directive, None when other lines separate them or when the include is the
+     * first one, i.e., when the include starts a block of includes.
+     */
+    pub fn get_blank_lines_before(&self) -> Option<usize> {
+        self.blank_lines_before
+    }
+
+    /**
+     * Returns whether comment lines separate the include from the previous include directive of
+     * the same block (e.g., "// third party").
+     */
+    pub fn has_comments_before(&self) -> bool {
+        self.has_comments_before
+    }
+
+    pub fn is_computed(&self) -> bool {
+        self.macro_expression.is_some()
+    }
+
+    /**
+     * Expands the macro expression of computed includes not expanded yet, returning whether it
+     * has been expanded into a file name.
+     */
+    fn expand(&mut self, macros: &MacroTable) -> bool {
+        let Some(expression) = self.macro_expression.as_deref() else {
+            return false;
+        };
+        if self.kind != IncludeKind::Computed {
+            return false;
+        }
+
+        match File::parse_header_name(&preprocessor::expand_macros(expression, macros)) {
+            Some((kind, spelling, _)) => {
+                self.kind = kind;
+                self.spelling = spelling.to_string();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /**
+     * Returns whether the include is forced from the command line, instead of written in the
+     * file.
+     */
+    pub fn is_forced(&self) -> bool {
+        self.line == 0
+    }
+}
+
+/**
+ * The node name of a C++20 module in the include graph, e.g., "module:foo" or "module:foo:part".
+ */
+pub fn make_module_node(module_name: &str) -> String {
+    format!("module:{}", module_name)
+}
+
+/**
+ * The dependencies found in the content of a file.
+ */
+struct ParsedContent {
+    include_directives: Vec<IncludeDirective>,
+    module_declaration: Option<IncludeDirective>,
+    macro_definitions: MacroTable,
+    include_guard: IncludeGuard,
+}
 
 pub struct File {
-    name: String,
-    used_modules: Vec<String>,
+    path: String,
+    include_directives: Vec<IncludeDirective>,
+
+    /**
+     * The declaration of the C++20 module (or module partition) of the file, if it is a module
+     * interface unit or a partition.
+     */
+    module_declaration: Option<IncludeDirective>,
+
+    /**
+     * The macros defined by the file itself, which computed includes of other files may use.
+     */
+    macro_definitions: MacroTable,
+
+    /**
+     * The "#pragma once" and the guard macro protecting the file from multiple inclusion.
+     */
+    include_guard: IncludeGuard,
+
+    num_lines: usize,
+    num_bytes: usize,
 }
 
 impl File {
-    pub fn make(name: &str, file_content: &str) -> Result<File, &'static str> {
-        let used_modules = File::make_used_modules(file_content)?;
+    /**
+     * Parses a source file.
+     *
+     * - path: the file path, relative to the project root and using '/' as separator (e.g.,
+     *   "Source/Private/main.cpp")
+     * - file_content: the file content
+     */
+    pub fn make(path: &str, file_content: &str) -> Result<File, &'static str> {
+        Self::make_with_forced_includes(path, file_content, &[])
+    }
+
+    /**
+     * Parses a source file compiled with forced includes (e.g., "-include file"), which are
+     * handled as quoted includes preceding the first line of the file, i.e., at line 0.
+     */
+    pub fn make_with_forced_includes(
+        path: &str,
+        file_content: &str,
+        forced_includes: &[String],
+    ) -> Result<File, &'static str> {
+        Self::make_with_macros(path, file_content, forced_includes, None)
+    }
+
+    /**
+     * Parses a source file evaluating its conditional directives (e.g., "#ifdef WITH_EDITOR"),
+     * so that only the includes actually compiled are kept. Macros defined and undefined in the
+     * file are taken into account from where they appear.
+     *
+     * - macros: the macros defined before the first line of the file (e.g., "-D" flags)
+     */
+    pub fn make_preprocessed(
+        path: &str,
+        file_content: &str,
+        forced_includes: &[String],
+        macros: &MacroTable,
+    ) -> Result<File, &'static str> {
+        Self::make_with_macros(path, file_content, forced_includes, Some(macros))
+    }
+
+    fn make_with_macros(
+        path: &str,
+        file_content: &str,
+        forced_includes: &[String],
+        macros: Option<&MacroTable>,
+    ) -> Result<File, &'static str> {
+        let content = File::make_include_directives(file_content, macros)?;
+        let include_directives = forced_includes
+            .iter()
+            .map(|f| IncludeDirective::make_forced(f))
+            .chain(content.include_directives)
+            .collect();
 
         Ok(File {
-            name: String::from(name),
-            used_modules: used_modules,
+            path: String::from(path),
+            include_directives,
+            module_declaration: content.module_declaration,
+            macro_definitions: content.macro_definitions,
+            include_guard: content.include_guard,
+            num_lines: file_content.lines().count(),
+            num_bytes: file_content.len(),
         })
     }
 
+    /**
+     * Returns the path of the file relative to the project root, which is what identifies the
+     * file across the whole project.
+     */
+    pub fn get_path(&self) -> &str {
+        &self.path
+    }
+
+    /**
+     * Returns the file name only, without its directories.
+     */
     pub fn get_name(&self) -> &str {
-        &self.name
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+
+    /**
+     * Returns the include directives of the file, forced includes first, then in the order they
+     * appear in the file.
+     */
+    pub fn get_include_directives(&self) -> &[IncludeDirective] {
+        &self.include_directives
+    }
+
+    /**
+     * Returns the declaration of the C++20 module (or partition) of the file, spelled with the
+     * module node name (e.g., "module:foo" for "export module foo;"), if any.
+     */
+    pub fn get_module_declaration(&self) -> Option<&IncludeDirective> {
+        self.module_declaration.as_ref()
+    }
+
+    /**
+     * Returns the macros defined by the file, as left at its end (e.g., "#define CONFIG_HEADER
+     * \"cfg.h\"").
+     */
+    pub fn get_macro_definitions(&self) -> &MacroTable {
+        &self.macro_definitions
+    }
+
+    /**
+     * Returns the "#pragma once" and the guard macro of the file, if any.
+     */
+    pub fn get_include_guard(&self) -> &IncludeGuard {
+        &self.include_guard
+    }
+
+    /**
+     * Returns whether some computed includes could not be expanded into a file name.
+     */
+    pub fn has_unexpanded_includes(&self) -> bool {
+        self.include_directives
+            .iter()
+            .any(|d| d.get_kind() == IncludeKind::Computed)
+    }
+
+    /**
+     * Expands the computed includes not expanded yet with the given macros, followed by the ones
+     * defined by the file itself (e.g., the macros defined by the files including this one).
+     * Returns the number of expanded includes.
+     */
+    pub fn expand_computed_includes(&mut self, macros: &MacroTable) -> usize {
+        let mut macros = macros.clone();
+        macros.extend(&self.macro_definitions);
+
+        self.include_directives
+            .iter_mut()
+            .map(|d| d.expand(&macros))
+            .filter(|&expanded| expanded)
+            .count()
+    }
+
+    /**
+     * Returns the number of lines of the file content.
+     */
+    pub fn get_num_lines(&self) -> usize {
+        self.num_lines
+    }
+
+    /**
+     * Returns the size in bytes of the file content.
+     */
+    pub fn get_num_bytes(&self) -> usize {
+        self.num_bytes
+    }
+
+    /**
+     * Returns the include directives and module imports of the file content, with the conditions
+     * enclosing them, its module declaration, the macros defined by the file and its include
+     * guard. Without macros,
+     * the includes of all the conditional branches are returned, and computed includes are
+     * expanded with the macros defined by the file only.
+     */
+    fn make_include_directives(
+        file_content: &str,
+        macros: Option<&MacroTable>,
+    ) -> Result<ParsedContent, &'static str> {
+        let lines = lexer::split_logical_lines(file_content);
+
+        let evaluate = macros.is_some();
+        let mut macros = macros.cloned().unwrap_or_default();
+        let mut macro_definitions = MacroTable::default();
+        let mut conditionals = ConditionalStack::make();
+        let mut include_directives = Vec::new();
+        let mut module_declaration: Option<IncludeDirective> = None;
+
+        // the blank lines since the last include directive, if nothing else followed it, and
+        // whether they contain comments
+        let mut blank_lines: Option<usize> = None;
+        let mut has_comments = false;
+
+        // whether the next code line starts a declaration, i.e., the previous one ended with ';',
+        // '{' or '}', so that module declarations and imports are not mistaken for the text of
+        // other declarations (e.g., the arguments of a call spanning multiple lines)
+        let mut at_declaration_start = true;
+
+        for line in &lines {
+            if line.is_blank() {
+                blank_lines = blank_lines.map(|n| n + 1);
+                has_comments |= line.get_comment().is_some();
+                continue;
+            }
+            let blank_lines_before = blank_lines.take();
+            let has_comments_before =
+                std::mem::take(&mut has_comments) && blank_lines_before.is_some();
+
+            let Some((name, arguments)) = line.get_directive() else {
+                if evaluate && !conditionals.is_active() {
+                    continue;
+                }
+
+                let ends_declaration = line.get_text().trim_end().ends_with([';', '{', '}']);
+                if !std::mem::replace(&mut at_declaration_start, ends_declaration) {
+                    continue;
+                }
+
+                let module_name = module_declaration.as_ref().map(|d| d.get_spelling());
+                let Some(d) = Self::make_module_directive(line, module_name) else {
+                    continue;
+                };
+                let d = IncludeDirective {
+                    conditions: conditionals.get_conditions(),
+                    ..d
+                };
+                match d.get_mode() {
+                    IncludeMode::ModuleDeclaration => module_declaration = Some(d),
+                    _ => include_directives.push(d),
+                }
+                continue;
+            };
+
+            // __has_include probes are evaluated under the conditions enclosing the #if or #elif
+            let if_context =
+                (name == "if").then(|| (conditionals.get_conditions(), conditionals.is_active()));
+            let is_conditional = conditionals.process(name, arguments, line.get_line(), &macros);
+            let probe_context = match name {
+                "if" => if_context,
+                "elif" => {
+                    let mut conditions = conditionals.get_conditions();
+                    conditions.pop();
+                    Some((conditions, conditionals.is_enclosing_active()))
+                }
+                _ => None,
+            };
+            if let Some((conditions, _)) = probe_context.filter(|(_, active)| *active || !evaluate)
+            {
+                include_directives.extend(Self::make_include_probes(line, &macros).map(|d| {
+                    IncludeDirective {
+                        conditions: conditions.clone(),
+                        ..d
+                    }
+                }));
+            }
+
+            if is_conditional || evaluate && !conditionals.is_active() {
+                continue;
+            }
+
+            match name {
+                "define" => {
+                    macros.define_from_directive(arguments);
+                    macro_definitions.define_from_directive(arguments);
+                }
+                "undef" => {
+                    macros.undefine(arguments);
+                    macro_definitions.undefine(arguments);
+                }
+                "include" | "include_next" => {
+                    if let Some(d) = Self::make_include_directive(line, &macros) {
+                        include_directives.push(IncludeDirective {
+                            conditions: conditionals.get_conditions(),
+                            blank_lines_before,
+                            has_comments_before,
+                            ..d
+                        });
+                        blank_lines = Some(0);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(ParsedContent {
+            include_directives,
+            module_declaration,
+            macro_definitions,
+            include_guard: IncludeGuard::find(&lines),
+        })
+    }
+
+    /**
+     * Parses the C++20 module declaration or import starting the logical line, if any, e.g.,
+     * "export module foo;", "module foo:part;", "import foo;", "import :part;" or
+     * "import <vector>;". Modules are spelled with their node name (e.g., "module:foo"), and
+     * partitions of the current module are qualified by its name (e.g., "module:foo:part").
+     *
+     * - module_node: the node of the module declared by the file so far, if any
+     */
+    fn make_module_directive(
+        line: &LogicalLine,
+        module_node: Option<&str>,
+    ) -> Option<IncludeDirective> {
+        let text = line.get_text().trim();
+        let (exported, rest) = match Self::strip_keyword(text, "export") {
+            Some(rest) => (true, rest),
+            None => (false, text),
+        };
+        let (is_import, rest) = match Self::strip_keyword(rest, "module") {
+            Some(rest) => (false, rest),
+            None => (true, Self::strip_keyword(rest, "import")?),
+        };
+        let (arguments, _) = rest.split_once(';')?;
+        let arguments = arguments.trim();
+
+        let directive = IncludeDirective {
+            spelling: String::new(),
+            kind: IncludeKind::Module,
+            mode: IncludeMode::ImportModule,
+            line: line.get_line(),
+            column: line.get_column(),
+            text: text[..text.len() - rest.len() + arguments.len()].to_string() + ";",
+            comment: line.get_comment().map(|c| c.to_string()),
+            conditions: Vec::new(),
+            macro_expression: None,
+            blank_lines_before: None,
+            has_comments_before: false,
+        };
+
+        if is_import {
+            if let Some((kind, spelling, _)) = Self::parse_header_name(arguments) {
+                return Some(IncludeDirective {
+                    spelling: spelling.to_string(),
+                    kind,
+                    mode: IncludeMode::ImportHeader,
+                    ..directive
+                });
+            }
+        }
+
+        let name: String = arguments.split_whitespace().collect();
+        let is_partition = name.starts_with(':');
+        let is_valid_name = name.split(':').enumerate().all(|(index, part)| {
+            (index == 0 && part.is_empty() && is_import)
+                || part.split('.').all(|p| {
+                    p.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                        && p.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+                })
+        });
+        // the global module fragment ("module;") and the private one ("module :private;") do not
+        // name any module
+        if name.is_empty() || !is_valid_name || name.split(':').count() > 2 {
+            return None;
+        }
+
+        let (name, mode) = match (is_import, is_partition) {
+            (true, true) => {
+                let module_name = module_node
+                    .and_then(|m| m.strip_prefix("module:"))
+                    .and_then(|m| m.split(':').next())
+                    .unwrap_or_default();
+                (
+                    format!("{}{}", module_name, name),
+                    IncludeMode::ImportModule,
+                )
+            }
+            (true, false) => (name, IncludeMode::ImportModule),
+            // implementation units ("module foo;") implicitly import their module interface
+            (false, _) if !exported && !name.contains(':') => (name, IncludeMode::ImportModule),
+            (false, _) => (name, IncludeMode::ModuleDeclaration),
+        };
+
+        Some(IncludeDirective {
+            spelling: make_module_node(&name),
+            mode,
+            ..directive
+        })
+    }
+
+    /**
+     * Returns the text following the given keyword, trimmed, if the text starts with it.
+     */
+    fn strip_keyword<'t>(text: &'t str, keyword: &str) -> Option<&'t str> {
+        let rest = text.strip_prefix(keyword)?;
+        match rest.starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_') {
+            true => None,
+            false => Some(rest.trim_start()),
+        }
     }
 
-    pub fn get_used_modules(&self) -> &[String] {
-        &self.used_modules
+    /**
+     * Parses the include directive of the logical line, if any, e.g., "#include <vector>",
+     * "# include \"foo.h\"" or "#include_next <stdlib.h>".
+     */
+    fn make_include_directive(line: &LogicalLine, macros: &MacroTable) -> Option<IncludeDirective> {
+        let (name, arguments) = line.get_directive()?;
+        let mode = match name {
+            "include" => IncludeMode::Include,
+            "include_next" => IncludeMode::IncludeNext,
+            _ => return None,
+        };
+
+        // the directive text goes from the '#' up to the closing delimiter, if any
+        let text = line.get_text().trim();
+        let text = match Self::parse_header_name(arguments) {
+            Some((_, _, length)) => &text[..text.len() - arguments.len() + length],
+            None => text,
+        };
+
+        Self::make_header_directive(line, mode, text, arguments, macros)
     }
 
-    fn make_used_modules(file_content: &str) -> Result<Vec<String>, &'static str> {
-        let re = Regex::new(r#"^\s*#include\s*[<"](.*?)[>"](?:\s*//.*)?$"#)
-            .map_err(|_| "Error in regex creation")?;
+    /**
+     * Parses the __has_include and __has_include_next probes of the #if or #elif of the logical
+     * line, e.g., "#if __has_include(<optional>)".
+     */
+    fn make_include_probes<'l>(
+        line: &'l LogicalLine,
+        macros: &'l MacroTable,
+    ) -> impl Iterator<Item = IncludeDirective> + 'l {
+        let (_, arguments) = line.get_directive().unwrap_or_default();
 
-        let used_modules = file_content
-            .lines()
-            .filter(|row| !row.trim_start().starts_with("//"))
-            .filter(|row| !row.trim_start().starts_with("/*"))
-            .filter_map(|row| {
-                re.captures(row)
-                    .and_then(|captures| captures.get(1))
-                    .map(|m| m.as_str().to_string())
+        preprocessor::find_include_probes(arguments)
+            .into_iter()
+            .filter_map(move |probe| {
+                let mode = match probe.is_next() {
+                    true => IncludeMode::HasIncludeNext,
+                    false => IncludeMode::HasInclude,
+                };
+                Self::make_header_directive(
+                    line,
+                    mode,
+                    probe.get_text(),
+                    probe.get_argument(),
+                    macros,
+                )
             })
-            .collect();
+    }
 
-        Ok(used_modules)
+    /**
+     * Builds the directive naming a file with the given arguments, either a file name between
+     * delimiters or a macro expression (e.g., "PLATFORM_HEADER(Foo.h)").
+     */
+    fn make_header_directive(
+        line: &LogicalLine,
+        mode: IncludeMode,
+        text: &str,
+        arguments: &str,
+        macros: &MacroTable,
+    ) -> Option<IncludeDirective> {
+        if arguments.is_empty() {
+            return None;
+        }
+
+        let directive = IncludeDirective {
+            spelling: String::new(),
+            kind: IncludeKind::Computed,
+            mode,
+            line: line.get_line(),
+            column: line.get_column(),
+            text: text.to_string(),
+            comment: line.get_comment().map(|c| c.to_string()),
+            conditions: Vec::new(),
+            macro_expression: None,
+            blank_lines_before: None,
+            has_comments_before: false,
+        };
+
+        if let Some((kind, spelling, _)) = Self::parse_header_name(arguments) {
+            return Some(IncludeDirective {
+                spelling: spelling.to_string(),
+                kind,
+                ..directive
+            });
+        }
+
+        // computed include, e.g., "#include PLATFORM_HEADER(Foo.h)"
+        let mut directive = IncludeDirective {
+            spelling: arguments.to_string(),
+            macro_expression: Some(arguments.to_string()),
+            ..directive
+        };
+        directive.expand(macros);
+
+        Some(directive)
+    }
+
+    /**
+     * Parses the file name of an include, e.g., "\"foo.h\"" or "<vector>", returning its kind,
+     * the file name between the delimiters and the length of the name with its delimiters.
+     */
+    fn parse_header_name(text: &str) -> Option<(IncludeKind, &str, usize)> {
+        let (kind, closing) = match text.chars().next()? {
+            '"' => (IncludeKind::Quoted, '"'),
+            '<' => (IncludeKind::Angled, '>'),
+            _ => return None,
+        };
+        let length = text[1..].find(closing)?;
+
+        Some((kind, &text[1..1 + length], length + 2))
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::compilation_database::MacroFlag;
+    use crate::file::{IncludeKind, IncludeMode};
+    use crate::preprocessor::MacroTable;
     use std::error::Error;
 
+    fn spellings(f: &super::File) -> Vec<&str> {
+        f.get_include_directives()
+            .iter()
+            .map(|d| d.get_spelling())
+            .collect()
+    }
+
     #[test]
     fn cpp_file_parsing_test() -> Result<(), &'static str> {
         let file_name = "main.cpp";
@@ -56,6 +789,7 @@ mod tests {
 #include \"foobar.h\"
 //#include \"commented_out.h\"
 /*#include \"another_commented_out.h\"
+#include \"commented_out_block.h\" */
 
 int main(void) {
     printf(\"Hello world\");
@@ -64,9 +798,13 @@ int main(void) {
         )?;
 
         assert_eq!(file_name, f.get_name());
+        assert_eq!(vec!["iostream", "foobar.h"], spellings(&f));
         assert_eq!(
-            vec![String::from("iostream"), String::from("foobar.h")],
-            f.get_used_modules()
+            vec![super::IncludeKind::Angled, super::IncludeKind::Quoted],
+            f.get_include_directives()
+                .iter()
+                .map(|d| d.get_kind())
+                .collect::<Vec<_>>()
         );
 
         Ok(())
@@ -82,6 +820,7 @@ int main(void) {
 #include \"include_folder/foobar.h\"
 //#include \"commented_out.h\"
 /*#include \"another_commented_out.h\"
+#include \"commented_out_block.h\" */
 
 int main(void) {
     printf(\"Hello world\");
@@ -90,14 +829,420 @@ int main(void) {
         )?;
 
         assert_eq!(file_name, f.get_name());
+        assert_eq!(vec!["iostream", "include_folder/foobar.h"], spellings(&f));
+
+        Ok(())
+    }
+
+    #[test]
+    fn cpp_file_include_directives_test() -> Result<(), &'static str> {
+        let f = super::File::make(
+            "main.cpp",
+            "\
+// main file
+#include <vector>
+  #include   \"foobar.h\"  // for Foo
+",
+        )?;
+
+        let directives = f.get_include_directives();
+        assert_eq!(2, directives.len());
+
+        assert_eq!("vector", directives[0].get_spelling());
+        assert_eq!(super::IncludeKind::Angled, directives[0].get_kind());
+        assert_eq!(
+            (2, 1),
+            (directives[0].get_line(), directives[0].get_column())
+        );
+        assert_eq!("#include <vector>", directives[0].get_text());
+        assert_eq!(None, directives[0].get_comment());
+
+        assert_eq!("foobar.h", directives[1].get_spelling());
+        assert_eq!(super::IncludeKind::Quoted, directives[1].get_kind());
+        assert_eq!(
+            (3, 3),
+            (directives[1].get_line(), directives[1].get_column())
+        );
+        assert_eq!("#include   \"foobar.h\"", directives[1].get_text());
+        assert_eq!(Some("for Foo"), directives[1].get_comment());
+
+        Ok(())
+    }
+
+    #[test]
+    fn cpp_file_include_blocks_test() -> Result<(), &'static str> {
+        let f = super::File::make(
+            "main.cpp",
+            "\
+#include \"main.h\"
+
+// third party
+#include <zlib.h>
+#include <vector>
+int x;
+#include <string>
+#ifdef _WIN32
+#include <windows.h>
+#endif",
+        )?;
+
+        let blank_lines: Vec<Option<usize>> = f
+            .get_include_directives()
+            .iter()
+            .map(|d| d.get_blank_lines_before())
+            .collect();
+        assert_eq!(vec![None, Some(2), Some(0), None, None], blank_lines);
+
+        let has_comments: Vec<bool> = f
+            .get_include_directives()
+            .iter()
+            .map(|d| d.has_comments_before())
+            .collect();
+        assert_eq!(vec![false, true, false, false, false], has_comments);
+
+        Ok(())
+    }
+
+    #[test]
+    fn cpp_file_lexing_test() -> Result<(), &'static str> {
+        let f = super::File::make(
+            "main.cpp",
+            "\
+/* license
+#include \"in_block_comment.h\"
+*/
+# include <spaced.h>
+#\tinclude \\
+    \"continued.h\"
+const char* text = R\"(
+#include \"in_raw_string.h\"
+)\";
+const char* other = \"/* not a comment\";
+#include \"after_string.h\" /* trailing */
+#define INCLUDE_NOTHING // #include \"in_line_comment.h\"
+",
+        )?;
+
+        assert_eq!(
+            vec!["spaced.h", "continued.h", "after_string.h"],
+            spellings(&f)
+        );
+
+        let directives = f.get_include_directives();
+        assert_eq!(
+            (4, 1),
+            (directives[0].get_line(), directives[0].get_column())
+        );
+        assert_eq!("# include <spaced.h>", directives[0].get_text());
+        assert_eq!(5, directives[1].get_line());
+        assert_eq!(11, directives[2].get_line());
+        assert_eq!(Some("trailing"), directives[2].get_comment());
+
+        Ok(())
+    }
+
+    #[test]
+    fn cpp_file_conditional_includes_test() -> Result<(), Box<dyn Error>> {
+        let content = "\
+#include \"always.h\"
+#if 0
+#include \"never.h\"
+#endif
+#ifdef WITH_EDITOR
+#include \"editor.h\"
+#elif PLATFORM_LINUX && !defined(WITH_SERVER)
+#include \"linux_client.h\"
+#else
+#include \"other.h\"
+#endif
+#define LOCAL_FEATURE 1
+#if LOCAL_FEATURE
+#include \"local_feature.h\"
+#endif
+#undef LOCAL_FEATURE
+#ifndef LOCAL_FEATURE
+#include \"no_local_feature.h\"
+#endif
+";
+
+        // without macros, all the branches are taken
+        let f = super::File::make("main.cpp", content)?;
+        assert_eq!(7, f.get_include_directives().len());
+
+        let editor = MacroTable::make(&[MacroFlag::parse_define("WITH_EDITOR")]);
+        let f = super::File::make_preprocessed("main.cpp", content, &[], &editor)?;
+        assert_eq!(
+            vec![
+                "always.h",
+                "editor.h",
+                "local_feature.h",
+                "no_local_feature.h"
+            ],
+            spellings(&f)
+        );
+
+        let linux = MacroTable::make(&[MacroFlag::parse_define("PLATFORM_LINUX=1")]);
+        let f = super::File::make_preprocessed("main.cpp", content, &[], &linux)?;
+        assert_eq!("linux_client.h", spellings(&f)[1]);
+
+        let server = MacroTable::make(&[
+            MacroFlag::parse_define("PLATFORM_LINUX=1"),
+            MacroFlag::parse_define("WITH_SERVER"),
+        ]);
+        let f = super::File::make_preprocessed("main.cpp", content, &[], &server)?;
+        assert_eq!("other.h", spellings(&f)[1]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn cpp_file_computed_includes_test() -> Result<(), Box<dyn Error>> {
+        let mut f = super::File::make(
+            "main.cpp",
+            "\
+#define CONFIG_HEADER \"cfg.h\"
+#include CONFIG_HEADER
+#include PLATFORM_HEADER(Foo.h)
+#undef CONFIG_HEADER
+#include CONFIG_HEADER
+",
+        )?;
+
+        let directives = f.get_include_directives();
+        assert_eq!(
+            vec!["cfg.h", "PLATFORM_HEADER(Foo.h)", "CONFIG_HEADER"],
+            spellings(&f)
+        );
+        assert_eq!(IncludeKind::Quoted, directives[0].get_kind());
+        assert_eq!(Some("CONFIG_HEADER"), directives[0].get_macro_expression());
+        assert_eq!("#include CONFIG_HEADER", directives[0].get_text());
+        assert_eq!(IncludeKind::Computed, directives[1].get_kind());
+        assert!(f.has_unexpanded_includes());
+        assert!(f.get_macro_definitions().get("CONFIG_HEADER").is_none());
+
+        // macros defined by other files, e.g., the ones including this file
+        let mut macros = MacroTable::default();
+        macros.define_from_directive("STRINGIFY(x) #x");
+        macros.define_from_directive("PLATFORM_HEADER(name) STRINGIFY(Linux/Linux##name)");
+        assert_eq!(1, f.expand_computed_includes(&macros));
+        assert_eq!("Linux/LinuxFoo.h", spellings(&f)[1]);
+        assert_eq!(
+            IncludeKind::Quoted,
+            f.get_include_directives()[1].get_kind()
+        );
+        assert!(f.has_unexpanded_includes());
+
+        Ok(())
+    }
+
+    #[test]
+    fn cpp_file_include_next_and_probes_test() -> Result<(), Box<dyn Error>> {
+        let content = "\
+#include_next <stdlib.h>
+#ifdef WITH_PROBES
+#if __has_include(<optional>) && __has_include_next(\"config.h\")
+#include <optional>
+#elif __has_include(<experimental/optional>)
+#include <experimental/optional>
+#endif
+#endif
+";
+        let f = super::File::make("stdlib.h", content)?;
+        let modes: Vec<(&str, IncludeMode)> = f
+            .get_include_directives()
+            .iter()
+            .map(|d| (d.get_spelling(), d.get_mode()))
+            .collect();
+        assert_eq!(
+            vec![
+                ("stdlib.h", IncludeMode::IncludeNext),
+                ("optional", IncludeMode::HasInclude),
+                ("config.h", IncludeMode::HasIncludeNext),
+                ("optional", IncludeMode::Include),
+                ("experimental/optional", IncludeMode::HasInclude),
+                ("experimental/optional", IncludeMode::Include),
+            ],
+            modes
+        );
+
+        let directives = f.get_include_directives();
+        assert_eq!("#include_next <stdlib.h>", directives[0].get_text());
+        assert!(directives[0].is_next() && !directives[0].is_soft());
+        assert_eq!("__has_include(<optional>)", directives[1].get_text());
+        assert_eq!(3, directives[1].get_line());
+        assert!(directives[2].is_next() && directives[2].is_soft());
+
+        // probes are under the conditions enclosing their #if or #elif
+        let conditions = |d: &super::IncludeDirective| -> Vec<String> {
+            d.get_conditions()
+                .iter()
+                .map(|c| c.get_text().to_string())
+                .collect()
+        };
+        assert_eq!(vec!["#ifdef WITH_PROBES"], conditions(&directives[1]));
+        assert_eq!(vec!["#ifdef WITH_PROBES"], conditions(&directives[4]));
+
+        // probes of conditions which are not evaluated are dropped
+        let f = super::File::make_preprocessed("stdlib.h", content, &[], &MacroTable::default())?;
+        assert_eq!(1, f.get_include_directives().len());
+
+        // whether the probed files exist is unknown, so the fallback branch is kept as well
+        let with_probes = MacroTable::make(&[MacroFlag::parse_define("WITH_PROBES")]);
+        let f = super::File::make_preprocessed("stdlib.h", content, &[], &with_probes)?;
         assert_eq!(
             vec![
-                String::from("iostream"),
-                String::from("include_folder/foobar.h")
+                "stdlib.h",
+                "optional",
+                "config.h",
+                "optional",
+                "experimental/optional",
+                "experimental/optional"
             ],
-            f.get_used_modules()
+            spellings(&f)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn cpp_file_modules_test() -> Result<(), Box<dyn Error>> {
+        let f = super::File::make(
+            "foo.cppm",
+            "\
+module;
+#include \"config.h\"
+export module foo;
+export import :part;
+export import bar.baz;
+import <vector>;
+import \"local.h\";
+int import_count = 0;
+module :private;
+",
+        )?;
+
+        let declaration = f.get_module_declaration().ok_or("Missing declaration")?;
+        assert_eq!("module:foo", declaration.get_spelling());
+        assert_eq!("export module foo;", declaration.get_text());
+        assert_eq!(3, declaration.get_line());
+
+        let directives: Vec<(&str, IncludeKind, IncludeMode)> = f
+            .get_include_directives()
+            .iter()
+            .map(|d| (d.get_spelling(), d.get_kind(), d.get_mode()))
+            .collect();
+        assert_eq!(
+            vec![
+                ("config.h", IncludeKind::Quoted, IncludeMode::Include),
+                (
+                    "module:foo:part",
+                    IncludeKind::Module,
+                    IncludeMode::ImportModule
+                ),
+                (
+                    "module:bar.baz",
+                    IncludeKind::Module,
+                    IncludeMode::ImportModule
+                ),
+                ("vector", IncludeKind::Angled, IncludeMode::ImportHeader),
+                ("local.h", IncludeKind::Quoted, IncludeMode::ImportHeader),
+            ],
+            directives
+        );
+        assert_eq!(
+            "export import :part;",
+            f.get_include_directives()[1].get_text()
         );
 
+        // partitions declare their own module node, implementation units import their module
+        let f = super::File::make("foo-part.cppm", "export module foo:part;")?;
+        assert_eq!(
+            Some("module:foo:part"),
+            f.get_module_declaration().map(|d| d.get_spelling())
+        );
+        let f = super::File::make("foo.cpp", "module foo;\nimport :impl;")?;
+        assert!(f.get_module_declaration().is_none());
+        assert_eq!(vec!["module:foo", "module::impl"], spellings(&f));
+
+        // only declarations starting a line and following the end of the previous one are
+        // parsed, with the conditions enclosing them
+        let f = super::File::make(
+            "bar.cppm",
+            "\
+#ifdef WITH_BAR
+export module bar;
+#endif
+#if WITH_FOO
+import foo;
+#endif
+auto text = R\"(
+import raw;
+)\";
+#define IMPORT_MACRO \\
+    import macro;
+int value = compute(
+    import continued;
+int first; import same_line;
+",
+        )?;
+        let declaration = f.get_module_declaration().ok_or("Missing declaration")?;
+        assert_eq!(
+            vec!["#ifdef WITH_BAR"],
+            declaration
+                .get_conditions()
+                .iter()
+                .map(|c| c.get_text())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(vec!["module:foo"], spellings(&f));
+        assert_eq!(
+            crate::preprocessor::Conditionality::Conditional,
+            f.get_include_directives()[0].get_conditionality()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn cpp_file_path_test() -> Result<(), &'static str> {
+        let f = super::File::make("Source/Private/main.cpp", "#include \"foobar.h\"")?;
+
+        assert_eq!("Source/Private/main.cpp", f.get_path());
+        assert_eq!("main.cpp", f.get_name());
+
+        Ok(())
+    }
+
+    #[test]
+    fn cpp_file_size_test() -> Result<(), &'static str> {
+        let f = super::File::make("main.cpp", "#include \"foobar.h\"\n\nint main() {}\n")?;
+        assert_eq!(3, f.get_num_lines());
+        assert_eq!(35, f.get_num_bytes());
+
+        let f = super::File::make("empty.h", "")?;
+        assert_eq!(0, f.get_num_lines());
+        assert_eq!(0, f.get_num_bytes());
+
+        Ok(())
+    }
+
+    #[test]
+    fn cpp_file_forced_includes_test() -> Result<(), &'static str> {
+        let f = super::File::make_with_forced_includes(
+            "main.cpp",
+            "#include <iostream>",
+            &[String::from("/media/workspace/Prefix.h")],
+        )?;
+
+        assert_eq!(vec!["/media/workspace/Prefix.h", "iostream"], spellings(&f));
+
+        let forced = &f.get_include_directives()[0];
+        assert!(forced.is_forced());
+        assert_eq!(super::IncludeKind::Quoted, forced.get_kind());
+        assert_eq!(0, forced.get_line());
+        assert_eq!("-include /media/workspace/Prefix.h", forced.get_text());
+        assert!(!f.get_include_directives()[1].is_forced());
+
         Ok(())
     }
 }
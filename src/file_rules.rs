@@ -0,0 +1,249 @@
+use crate::project_config::Profile;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use regex::Regex;
+use std::error::Error;
+
+/**
+ * The prefix of the rules given as regexes instead of glob patterns.
+ */
+pub const REGEX_RULE_PREFIX: &str = "regex:";
+
+/**
+ * A set of rules matching paths relative to the project root, as in .gitignore files:
+ * - a glob pattern with a '/' is anchored to the project root (e.g., "Source/ThirdParty"), while
+ *   one without matches at any depth (e.g., "Intermediate" or "*.generated.h")
+ * - a regex, given as "regex:..." (e.g., "regex:Tests?/"), is searched in the path
+ *
+ * A rule matching a directory matches its whole subtree.
+ */
+#[derive(Debug, Clone)]
+struct PathRules {
+    rules: Vec<String>,
+    globs: GlobSet,
+    regexes: Vec<Regex>,
+
+    /**
+     * The literal directories each rule is anchored to (e.g., "Source/Runtime" for
+     * "Source/Runtime/Core*"), or None when some rule may match anywhere.
+     */
+    anchors: Option<Vec<String>>,
+}
+
+impl PathRules {
+    fn make(rules: Vec<String>) -> Result<PathRules, Box<dyn Error>> {
+        let mut globs = GlobSetBuilder::new();
+        let mut regexes = Vec::new();
+        let mut anchors = Some(Vec::new());
+
+        for rule in &rules {
+            match rule.strip_prefix(REGEX_RULE_PREFIX) {
+                Some(regex) => {
+                    regexes.push(Regex::new(regex)?);
+                    anchors = None;
+                }
+                None => {
+                    let pattern = rule.trim_matches('/');
+                    if pattern.is_empty() {
+                        return Err(format!("Empty glob pattern {}", rule).into());
+                    }
+
+                    let pattern = match rule.trim_end_matches('/').contains('/') {
+                        true => pattern.to_string(),
+                        false => format!("**/{}", pattern),
+                    };
+                    // the subtree of a matching directory matches as well
+                    for pattern in [pattern.clone(), format!("{}/**", pattern)] {
+                        globs.add(GlobBuilder::new(&pattern).literal_separator(true).build()?);
+                    }
+
+                    let anchor: Vec<&str> = pattern
+                        .split('/')
+                        .take_while(|c| !c.contains(['*', '?', '[', '{']))
+                        .collect();
+                    match (anchor.is_empty(), anchors.as_mut()) {
+                        (false, Some(anchors)) => anchors.push(anchor.join("/")),
+                        _ => anchors = None,
+                    }
+                }
+            }
+        }
+
+        Ok(PathRules {
+            rules,
+            globs: globs.build()?,
+            regexes,
+            anchors,
+        })
+    }
+
+    fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    fn is_match(&self, path: &str) -> bool {
+        self.globs.is_match(path)
+            || Self::get_ancestors(path).any(|p| self.regexes.iter().any(|r| r.is_match(p)))
+    }
+
+    /**
+     * Returns whether some file under the directory may match, i.e., the directory is on the way
+     * to the directory some rule is anchored to, or under it.
+     */
+    fn may_match_under(&self, directory: &str) -> bool {
+        match &self.anchors {
+            Some(anchors) => {
+                directory.is_empty()
+                    || anchors.iter().any(|a| {
+                        Self::is_same_or_under(a, directory) || Self::is_same_or_under(directory, a)
+                    })
+            }
+            None => true,
+        }
+    }
+
+    fn is_same_or_under(path: &str, directory: &str) -> bool {
+        path.strip_prefix(directory)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    }
+
+    /**
+     * Returns the path followed by its ancestor directories, e.g., "a/b/c.h", "a/b" and "a".
+     */
+    fn get_ancestors(path: &str) -> impl Iterator<Item = &str> {
+        std::iter::once(path).chain(path.rmatch_indices('/').map(move |(i, _)| &path[..i]))
+    }
+}
+
+/**
+ * The rules selecting the project files to scan, matched against their paths relative to the
+ * project root, using '/' as separator:
+ * - include: when not empty, only the subtrees matching one of them are scanned
+ * - exclude: the subtrees matching one of them are not scanned, even when included
+ */
+#[derive(Debug, Clone)]
+pub struct FileRules {
+    include: PathRules,
+    exclude: PathRules,
+}
+
+impl FileRules {
+    /**
+     * Builds the rules from glob patterns and "regex:" prefixed regexes, e.g., "Intermediate",
+     * "Source/ThirdParty" or "regex:/Tests?/".
+     */
+    pub fn make(include: Vec<String>, exclude: Vec<String>) -> Result<FileRules, Box<dyn Error>> {
+        Ok(FileRules {
+            include: PathRules::make(include)?,
+            exclude: PathRules::make(exclude)?,
+        })
+    }
+
+    /**
+     * Checks that a single rule is a valid glob pattern or regex.
+     */
+    pub fn check_rule(rule: &str) -> Result<(), Box<dyn Error>> {
+        PathRules::make(vec![rule.to_string()]).map(|_| ())
+    }
+
+    pub fn get_include_rules(&self) -> &[String] {
+        &self.include.rules
+    }
+
+    pub fn get_exclude_rules(&self) -> &[String] {
+        &self.exclude.rules
+    }
+
+    /**
+     * Returns whether the file or directory is excluded, and so its whole subtree.
+     */
+    pub fn is_excluded(&self, path: &str) -> bool {
+        self.exclude.is_match(path)
+    }
+
+    /**
+     * Returns whether the directory is walked, i.e., it is not excluded and it may contain
+     * included files.
+     */
+    pub fn accepts_directory(&self, path: &str) -> bool {
+        !self.is_excluded(path) && (self.include.is_empty() || self.include.may_match_under(path))
+    }
+
+    /**
+     * Returns whether the file is scanned, i.e., included and not excluded.
+     */
+    pub fn accepts_file(&self, path: &str) -> bool {
+        !self.is_excluded(path) && (self.include.is_empty() || self.include.is_match(path))
+    }
+}
+
+impl Default for FileRules {
+    /**
+     * Returns the rules of the default profile.
+     */
+    fn default() -> Self {
+        FileRules::make(Vec::new(), Profile::default().get_exclude_rules())
+            .expect("Invalid rules of the default profile")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_rules(include: &[&str], exclude: &[&str]) -> FileRules {
+        let to_strings = |rules: &[&str]| rules.iter().map(|r| r.to_string()).collect();
+        FileRules::make(to_strings(include), to_strings(exclude)).expect("Invalid rules")
+    }
+
+    #[test]
+    fn glob_rules_test() {
+        let rules = make_rules(&[], &["Intermediate", "/Plugins", "Source/*.generated.h"]);
+
+        assert!(rules.is_excluded("Intermediate"));
+        assert!(rules.is_excluded("Game/Intermediate/Build/main.cpp"));
+        assert!(rules.is_excluded("Plugins/Foo/foo.h"));
+        assert!(rules.is_excluded("Source/foo.generated.h"));
+
+        // rules are anchored to path components
+        assert!(!rules.is_excluded("Game/IntermediateTools/main.cpp"));
+        assert!(!rules.is_excluded("Game/Plugins/foo.h"));
+        assert!(!rules.is_excluded("MyPluginsHelper/foo.h"));
+        assert!(!rules.is_excluded("Source/Public/foo.generated.h"));
+    }
+
+    #[test]
+    fn regex_rules_test() {
+        let rules = make_rules(&[], &["regex:(^|/)Tests?$", "regex:_test\\.cpp$"]);
+
+        assert!(rules.is_excluded("Source/Test/main.cpp"));
+        assert!(rules.is_excluded("Tests/main.cpp"));
+        assert!(rules.is_excluded("Source/foo_test.cpp"));
+        assert!(!rules.is_excluded("Source/Testing/main.cpp"));
+
+        assert!(FileRules::check_rule("regex:(").is_err());
+        assert!(FileRules::check_rule("Source/[").is_err());
+        assert!(FileRules::check_rule("/").is_err());
+    }
+
+    #[test]
+    fn include_rules_test() {
+        let rules = make_rules(&["Source/Runtime", "*.inl"], &["ThirdParty"]);
+
+        assert!(rules.accepts_file("Source/Runtime/Core/core.h"));
+        assert!(rules.accepts_file("Tools/tool.inl"));
+        assert!(!rules.accepts_file("Source/Editor/editor.h"));
+        assert!(!rules.accepts_file("Source/Runtime/ThirdParty/zlib.h"));
+
+        // unanchored rules may match anywhere
+        assert!(rules.accepts_directory("Source/Editor"));
+
+        let rules = make_rules(&["Source/Runtime/**/*.h"], &[]);
+        assert!(rules.accepts_directory(""));
+        assert!(rules.accepts_directory("Source"));
+        assert!(rules.accepts_directory("Source/Runtime/Core"));
+        assert!(!rules.accepts_directory("Source/Editor"));
+        assert!(!rules.accepts_directory("Sources"));
+        assert!(rules.accepts_file("Source/Runtime/Core/core.h"));
+        assert!(!rules.accepts_file("Source/Runtime/Core/core.cpp"));
+    }
+}
@@ -0,0 +1,1043 @@
+use rayon::prelude::*;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
+
+/**
+ * Number of target components whose reachability is computed at once. Reachability is computed
+ * chunk by chunk to bound the memory usage to (components x CHUNK_BITS) bits.
+ */
+const CHUNK_BITS: usize = 1024;
+const CHUNK_WORDS: usize = CHUNK_BITS / 64;
+
+/**
+ * The direction edges are followed when computing reachability.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// From a file to the files it includes.
+    Includes,
+    /// From a file to the files including it.
+    Includers,
+}
+
+/**
+ * The include graph with compact integer node ids, suited for whole-graph algorithms.
+ * Node ids are assigned following the lexicographic order of the node names, so that the
+ * algorithms results are deterministic.
+ */
+#[derive(Debug)]
+pub struct IncludeGraph<'a> {
+    nodes: Vec<&'a str>,
+    ids: HashMap<&'a str, usize>,
+
+    /**
+     * For each node, the nodes it directly includes, sorted by id.
+     */
+    includes: Vec<Vec<usize>>,
+
+    /**
+     * For each node, the nodes directly including it, sorted by id.
+     */
+    includers: Vec<Vec<usize>>,
+}
+
+impl<'a> IncludeGraph<'a> {
+    /**
+     * Builds the graph from the inclusion map, i.e., for each file the set of files directly
+     * including it.
+     */
+    pub fn make(modules_inclusion: &HashMap<&'a str, HashSet<&'a str>>) -> IncludeGraph<'a> {
+        let mut nodes: Vec<&'a str> = modules_inclusion.keys().cloned().collect();
+        nodes.sort();
+
+        let ids: HashMap<&'a str, usize> = nodes.iter().enumerate().map(|(i, &n)| (n, i)).collect();
+
+        let mut includes = vec![Vec::new(); nodes.len()];
+        let mut includers = vec![Vec::new(); nodes.len()];
+        for (included, including_files) in modules_inclusion {
+            let included_id = ids[included];
+            for including in including_files {
+                let including_id = ids[including];
+                includes[including_id].push(included_id);
+                includers[included_id].push(including_id);
+            }
+        }
+
+        for adjacency in includes.iter_mut().chain(includers.iter_mut()) {
+            adjacency.sort_unstable();
+        }
+
+        IncludeGraph {
+            nodes,
+            ids,
+            includes,
+            includers,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn get_node(&self, id: usize) -> &'a str {
+        self.nodes[id]
+    }
+
+    pub fn get_id(&self, node: &str) -> Option<usize> {
+        self.ids.get(node).copied()
+    }
+
+    pub fn get_includes(&self, id: usize) -> &[usize] {
+        &self.includes[id]
+    }
+
+    pub fn get_includers(&self, id: usize) -> &[usize] {
+        &self.includers[id]
+    }
+
+    /**
+     * Returns the nodes reachable from the given ones following the given direction, the given
+     * ones included, sorted by id.
+     */
+    pub fn reachable(&self, from: &[usize], direction: Direction) -> Vec<usize> {
+        let mut visited = vec![false; self.nodes.len()];
+        let mut stack = from.to_vec();
+        while let Some(node) = stack.pop() {
+            if std::mem::replace(&mut visited[node], true) {
+                continue;
+            }
+
+            let neighbors = match direction {
+                Direction::Includes => &self.includes[node],
+                Direction::Includers => &self.includers[node],
+            };
+            stack.extend(neighbors.iter().filter(|&&n| !visited[n]));
+        }
+
+        (0..self.nodes.len()).filter(|&n| visited[n]).collect()
+    }
+
+    /**
+     * Returns the strongly connected components of the graph, following the include direction,
+     * computed with an iterative version of Tarjan's algorithm.
+     * Components are returned in reverse topological order, i.e., a component is listed before
+     * any component including it. Node ids inside each component are sorted.
+     */
+    pub fn strongly_connected_components(&self) -> Vec<Vec<usize>> {
+        const UNVISITED: usize = usize::MAX;
+
+        let n = self.nodes.len();
+        let mut index = vec![UNVISITED; n];
+        let mut lowlink = vec![0; n];
+        let mut on_stack = vec![false; n];
+        let mut stack = Vec::new();
+        let mut components = Vec::new();
+        let mut next_index = 0;
+
+        // explicit call stack of (node, position of the next include to visit)
+        let mut call_stack: Vec<(usize, usize)> = Vec::new();
+
+        for root in 0..n {
+            if index[root] != UNVISITED {
+                continue;
+            }
+
+            index[root] = next_index;
+            lowlink[root] = next_index;
+            next_index += 1;
+            stack.push(root);
+            on_stack[root] = true;
+            call_stack.push((root, 0));
+
+            while let Some(&(v, position)) = call_stack.last() {
+                if let Some(&w) = self.includes[v].get(position) {
+                    if let Some(top) = call_stack.last_mut() {
+                        top.1 += 1;
+                    }
+
+                    if index[w] == UNVISITED {
+                        index[w] = next_index;
+                        lowlink[w] = next_index;
+                        next_index += 1;
+                        stack.push(w);
+                        on_stack[w] = true;
+                        call_stack.push((w, 0));
+                    } else if on_stack[w] {
+                        lowlink[v] = lowlink[v].min(index[w]);
+                    }
+                    continue;
+                }
+
+                call_stack.pop();
+                if let Some(&(parent, _)) = call_stack.last() {
+                    lowlink[parent] = lowlink[parent].min(lowlink[v]);
+                }
+
+                if lowlink[v] == index[v] {
+                    let mut component = Vec::new();
+                    while let Some(w) = stack.pop() {
+                        on_stack[w] = false;
+                        component.push(w);
+                        if w == v {
+                            break;
+                        }
+                    }
+                    component.sort_unstable();
+                    components.push(component);
+                }
+            }
+        }
+
+        components
+    }
+
+    /**
+     * Returns, for each node, the sum of the weights of the other nodes reachable from it
+     * following the given direction.
+     *
+     * Strongly connected components are collapsed first, so that reachability is propagated over
+     * a DAG in topological order, as bitsets over the components. When parallel is true, the
+     * computation is distributed over the rayon thread pool.
+     *
+     * - direction: whether to follow includes or includers
+     * - weights: the weight of each node, by node id (e.g., 1 to count the reachable nodes)
+     * - parallel: whether to distribute the computation over multiple threads
+     */
+    pub fn reachable_weight_sums(
+        &self,
+        direction: Direction,
+        weights: &[u64],
+        parallel: bool,
+    ) -> Vec<u64> {
+        assert_eq!(self.len(), weights.len());
+
+        let components = self.strongly_connected_components();
+        let num_components = components.len();
+
+        // Tarjan's order lists included components first. Processing positions are assigned so
+        // that every component is processed after the components it reaches.
+        let position_of = |index: usize| match direction {
+            Direction::Includes => index,
+            Direction::Includers => num_components - 1 - index,
+        };
+
+        let mut component_positions = vec![0; self.len()];
+        for (index, component) in components.iter().enumerate() {
+            for &node in component {
+                component_positions[node] = position_of(index);
+            }
+        }
+
+        let mut successors = vec![Vec::new(); num_components];
+        let mut component_weights = vec![0u64; num_components];
+        for (index, component) in components.iter().enumerate() {
+            let position = position_of(index);
+            for &node in component {
+                component_weights[position] += weights[node];
+
+                let adjacency = match direction {
+                    Direction::Includes => &self.includes[node],
+                    Direction::Includers => &self.includers[node],
+                };
+                successors[position].extend(
+                    adjacency
+                        .iter()
+                        .map(|&n| component_positions[n])
+                        .filter(|&p| p != position),
+                );
+            }
+            successors[position].sort_unstable();
+            successors[position].dedup();
+        }
+
+        let weight_planes = WeightPlanes::make(&component_weights);
+        let num_chunks = num_components.div_ceil(CHUNK_BITS);
+        let add = |mut a: Vec<u64>, b: Vec<u64>| {
+            a.iter_mut().zip(b).for_each(|(a, b)| *a += b);
+            a
+        };
+
+        let totals = if parallel {
+            (0..num_chunks)
+                .into_par_iter()
+                .map(|chunk| Self::chunk_weight_sums(chunk, &successors, &weight_planes))
+                .reduce(|| vec![0; num_components], add)
+        } else {
+            (0..num_chunks)
+                .map(|chunk| Self::chunk_weight_sums(chunk, &successors, &weight_planes))
+                .fold(vec![0; num_components], add)
+        };
+
+        // the other nodes of the same component are reachable as well
+        (0..self.len())
+            .map(|node| {
+                let position = component_positions[node];
+                totals[position] + component_weights[position] - weights[node]
+            })
+            .collect()
+    }
+
+    /**
+     * Returns, for each component position, the sum of the weights of the components of the
+     * given chunk which are reachable from it.
+     */
+    fn chunk_weight_sums(
+        chunk: usize,
+        successors: &[Vec<usize>],
+        weight_planes: &WeightPlanes,
+    ) -> Vec<u64> {
+        let num_components = successors.len();
+        let low = chunk * CHUNK_BITS;
+        let high = (low + CHUNK_BITS).min(num_components);
+
+        // components before the chunk cannot reach it, being processed first
+        let mut rows = vec![0u64; (num_components - low) * CHUNK_WORDS];
+        let mut sums = vec![0u64; num_components];
+
+        for position in low..num_components {
+            let (processed, current) = rows.split_at_mut((position - low) * CHUNK_WORDS);
+            let row = &mut current[..CHUNK_WORDS];
+
+            for &successor in successors[position].iter().filter(|&&s| s >= low) {
+                let offset = successor - low;
+                if successor < high {
+                    row[offset / 64] |= 1 << (offset % 64);
+                }
+
+                let successor_row = &processed[offset * CHUNK_WORDS..(offset + 1) * CHUNK_WORDS];
+                row.iter_mut().zip(successor_row).for_each(|(r, s)| *r |= s);
+            }
+
+            sums[position] = weight_planes.weighted_count(chunk, row);
+        }
+
+        sums
+    }
+
+    /**
+     * Returns, for each of the given target nodes, the marked nodes reachable from the roots
+     * reaching it, sorted by id. Roots are the components not included by any other component
+     * (e.g., translation units), so these are the marked nodes compiled together with the target.
+     *
+     * Strongly connected components are collapsed first, so that the marked components reachable
+     * from each component are propagated once over the DAG, included components first, and then
+     * pushed from the roots down to the components they reach. Both are bitsets over the marked
+     * components, computed chunk by chunk.
+     */
+    pub fn reachable_from_roots(
+        &self,
+        targets: &[usize],
+        is_marked: impl Fn(usize) -> bool,
+    ) -> Vec<Vec<usize>> {
+        let components = self.strongly_connected_components();
+        let num_components = components.len();
+
+        let mut component_of = vec![0; self.len()];
+        for (index, component) in components.iter().enumerate() {
+            for &node in component {
+                component_of[node] = index;
+            }
+        }
+
+        // Tarjan's order lists included components first, so successors come before
+        let mut successors = vec![Vec::new(); num_components];
+        let mut is_root = vec![true; num_components];
+        for (index, component) in components.iter().enumerate() {
+            for &node in component {
+                for &included in &self.includes[node] {
+                    let included = component_of[included];
+                    if included != index {
+                        successors[index].push(included);
+                        is_root[included] = false;
+                    }
+                }
+            }
+            successors[index].sort_unstable();
+            successors[index].dedup();
+        }
+
+        let marked: Vec<usize> = (0..num_components)
+            .filter(|&c| components[c].iter().any(|&n| is_marked(n)))
+            .collect();
+
+        let mut reachable = vec![Vec::new(); targets.len()];
+        for (chunk, chunk_components) in marked.chunks(CHUNK_BITS).enumerate() {
+            let mut bits = vec![None; num_components];
+            for (bit, &component) in chunk_components.iter().enumerate() {
+                bits[component] = Some(bit);
+            }
+
+            // the marked components reachable from each component
+            let mut down = vec![0u64; num_components * CHUNK_WORDS];
+            for component in 0..num_components {
+                let (processed, current) = down.split_at_mut(component * CHUNK_WORDS);
+                let row = &mut current[..CHUNK_WORDS];
+                if let Some(bit) = bits[component] {
+                    row[bit / 64] |= 1 << (bit % 64);
+                }
+                for &successor in &successors[component] {
+                    let successor_row =
+                        &processed[successor * CHUNK_WORDS..(successor + 1) * CHUNK_WORDS];
+                    row.iter_mut().zip(successor_row).for_each(|(r, s)| *r |= s);
+                }
+            }
+
+            // the marked components reachable from the roots reaching each component, including
+            // components first
+            let mut up = vec![0u64; num_components * CHUNK_WORDS];
+            for component in (0..num_components).rev() {
+                let (included, current) = up.split_at_mut(component * CHUNK_WORDS);
+                let row = &mut current[..CHUNK_WORDS];
+                if is_root[component] {
+                    let down_row = &down[component * CHUNK_WORDS..(component + 1) * CHUNK_WORDS];
+                    row.iter_mut().zip(down_row).for_each(|(r, d)| *r |= d);
+                }
+                for &successor in &successors[component] {
+                    let successor_row =
+                        &mut included[successor * CHUNK_WORDS..(successor + 1) * CHUNK_WORDS];
+                    successor_row
+                        .iter_mut()
+                        .zip(&*row)
+                        .for_each(|(s, r)| *s |= r);
+                }
+            }
+
+            for (nodes, &target) in reachable.iter_mut().zip(targets) {
+                let start = component_of[target] * CHUNK_WORDS;
+                for (word, &value) in up[start..start + CHUNK_WORDS].iter().enumerate() {
+                    let set_bits = (0..64).filter(|bit| value & (1 << bit) != 0);
+                    for bit in set_bits {
+                        let component = marked[chunk * CHUNK_BITS + word * 64 + bit];
+                        nodes.extend(components[component].iter().filter(|&&n| is_marked(n)));
+                    }
+                }
+            }
+        }
+
+        for nodes in &mut reachable {
+            nodes.sort_unstable();
+        }
+
+        reachable
+    }
+
+    /**
+     * Returns the shortest include path from one node to another, both included, visiting only
+     * the allowed nodes. When from and to are the same node, the shortest cycle through it is
+     * returned.
+     */
+    pub fn shortest_path(
+        &self,
+        from: usize,
+        to: usize,
+        is_allowed: impl Fn(usize) -> bool,
+    ) -> Option<Vec<usize>> {
+        self.shortest_path_with_edges(from, to, is_allowed, |_, _| true)
+    }
+
+    /**
+     * Returns up to k shortest include paths without repeated nodes from one node to another, in
+     * increasing length (ties sorted by node ids), computed with Yen's algorithm. When from and
+     * to are the same node, only the shortest cycle through it is returned.
+     */
+    pub fn k_shortest_paths(&self, from: usize, to: usize, k: usize) -> Vec<Vec<usize>> {
+        let Some(shortest) = self.shortest_path(from, to, |_| true) else {
+            return Vec::new();
+        };
+        if k == 0 {
+            return Vec::new();
+        }
+        if from == to {
+            return vec![shortest];
+        }
+
+        let mut paths = vec![shortest];
+        let mut candidates: BTreeSet<(usize, Vec<usize>)> = BTreeSet::new();
+
+        while paths.len() < k {
+            let previous = &paths[paths.len() - 1];
+
+            // deviate from the previous path at each of its nodes, avoiding the edges already
+            // taken from the same root and the nodes of the root itself
+            for spur_index in 0..previous.len() - 1 {
+                let root = &previous[..=spur_index];
+                let removed_edges: HashSet<(usize, usize)> = paths
+                    .iter()
+                    .filter(|p| p.len() > spur_index + 1 && p[..=spur_index] == *root)
+                    .map(|p| (p[spur_index], p[spur_index + 1]))
+                    .collect();
+                let removed_nodes: HashSet<usize> = root[..spur_index].iter().cloned().collect();
+
+                let spur_path = self.shortest_path_with_edges(
+                    root[spur_index],
+                    to,
+                    |n| !removed_nodes.contains(&n),
+                    |a, b| !removed_edges.contains(&(a, b)),
+                );
+
+                if let Some(spur_path) = spur_path {
+                    let mut path = root[..spur_index].to_vec();
+                    path.extend(spur_path);
+                    if !paths.contains(&path) {
+                        candidates.insert((path.len(), path));
+                    }
+                }
+            }
+
+            match candidates.pop_first() {
+                Some((_, path)) => paths.push(path),
+                None => break,
+            }
+        }
+
+        paths
+    }
+
+    /**
+     * Returns up to limit include paths without repeated nodes from one node to another, in
+     * depth-first order following the node ids. When from and to are the same node, the cycles
+     * through it are returned.
+     */
+    pub fn simple_paths(&self, from: usize, to: usize, limit: usize) -> Vec<Vec<usize>> {
+        // only the nodes reaching the destination are worth exploring
+        let mut reaching = vec![false; self.len()];
+        reaching[to] = true;
+        let mut queue = VecDeque::from([to]);
+        while let Some(current) = queue.pop_front() {
+            for &includer in &self.includers[current] {
+                if !reaching[includer] {
+                    reaching[includer] = true;
+                    queue.push_back(includer);
+                }
+            }
+        }
+
+        let mut paths = Vec::new();
+        if !reaching[from] {
+            return paths;
+        }
+
+        let mut on_path = vec![false; self.len()];
+        on_path[from] = true;
+        let mut path = vec![from];
+        // explicit stack of the position of the next include to follow from each path node
+        let mut positions = vec![0];
+
+        while let Some(position) = positions.last_mut() {
+            if paths.len() >= limit {
+                break;
+            }
+
+            let current = path[path.len() - 1];
+            let next = self.includes[current][*position..]
+                .iter()
+                .position(|&n| n == to || (reaching[n] && !on_path[n]))
+                .map(|offset| *position + offset);
+
+            match next {
+                Some(index) => {
+                    *position = index + 1;
+                    let next = self.includes[current][index];
+
+                    if next == to {
+                        let mut found = path.clone();
+                        found.push(to);
+                        paths.push(found);
+                    } else {
+                        on_path[next] = true;
+                        path.push(next);
+                        positions.push(0);
+                    }
+                }
+                None => {
+                    positions.pop();
+                    if let Some(last) = path.pop() {
+                        on_path[last] = false;
+                    }
+                }
+            }
+        }
+
+        paths
+    }
+
+    /**
+     * Returns the shortest include path from one node to another, both included, visiting only
+     * the allowed nodes and following only the allowed edges.
+     */
+    fn shortest_path_with_edges(
+        &self,
+        from: usize,
+        to: usize,
+        is_allowed: impl Fn(usize) -> bool,
+        is_allowed_edge: impl Fn(usize, usize) -> bool,
+    ) -> Option<Vec<usize>> {
+        let mut parents: HashMap<usize, usize> = HashMap::new();
+        let mut queue = VecDeque::from([from]);
+        let mut visited = HashSet::from([from]);
+
+        while let Some(current) = queue.pop_front() {
+            for &next in &self.includes[current] {
+                if !is_allowed_edge(current, next) {
+                    continue;
+                }
+
+                if next == to {
+                    let mut path = vec![to, current];
+                    let mut node = current;
+                    while let Some(&parent) = parents.get(&node) {
+                        path.push(parent);
+                        node = parent;
+                    }
+                    path.reverse();
+                    return Some(path);
+                }
+
+                if is_allowed(next) && visited.insert(next) {
+                    parents.insert(next, current);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/**
+ * The component weights decomposed in binary planes, i.e., for each bit of the weights, the
+ * bitset of the components whose weight has that bit set. This allows to sum the weights of a
+ * set of components with a few popcounts.
+ */
+struct WeightPlanes {
+    planes: Vec<(u32, Vec<u64>)>,
+}
+
+impl WeightPlanes {
+    fn make(weights: &[u64]) -> WeightPlanes {
+        let num_words = weights.len().div_ceil(CHUNK_BITS) * CHUNK_WORDS;
+        let max_weight = weights.iter().max().copied().unwrap_or(0);
+
+        let planes = (0..u64::BITS - max_weight.leading_zeros())
+            .map(|bit| {
+                let mut plane = vec![0u64; num_words];
+                for (index, weight) in weights.iter().enumerate() {
+                    if weight & (1 << bit) != 0 {
+                        plane[index / 64] |= 1 << (index % 64);
+                    }
+                }
+                (bit, plane)
+            })
+            .collect();
+
+        WeightPlanes { planes }
+    }
+
+    /**
+     * Returns the sum of the weights of the components set in the given chunk row.
+     */
+    fn weighted_count(&self, chunk: usize, row: &[u64]) -> u64 {
+        let words = chunk * CHUNK_WORDS..(chunk + 1) * CHUNK_WORDS;
+
+        self.planes
+            .iter()
+            .map(|(bit, plane)| {
+                let count: u32 = row
+                    .iter()
+                    .zip(&plane[words.clone()])
+                    .map(|(r, p)| (r & p).count_ones())
+                    .sum();
+                (count as u64) << bit
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /**
+     * Builds a graph from (includer, included) pairs.
+     */
+    fn make_graph<'a>(edges: &[(&'a str, &'a str)]) -> IncludeGraph<'a> {
+        let mut modules_inclusion: HashMap<&'a str, HashSet<&'a str>> = HashMap::new();
+        for &(includer, included) in edges {
+            modules_inclusion.entry(includer).or_default();
+            modules_inclusion
+                .entry(included)
+                .or_default()
+                .insert(includer);
+        }
+
+        IncludeGraph::make(&modules_inclusion)
+    }
+
+    fn names<'a>(graph: &IncludeGraph<'a>, ids: &[usize]) -> Vec<&'a str> {
+        ids.iter().map(|&id| graph.get_node(id)).collect()
+    }
+
+    #[test]
+    fn graph_construction_test() {
+        let graph = make_graph(&[("main.cpp", "b.h"), ("main.cpp", "a.h"), ("b.h", "a.h")]);
+
+        assert_eq!(3, graph.len());
+        let main = graph.get_id("main.cpp").unwrap();
+        let a = graph.get_id("a.h").unwrap();
+        assert_eq!(vec!["a.h", "b.h"], names(&graph, graph.get_includes(main)));
+        assert_eq!(
+            vec!["b.h", "main.cpp"],
+            names(&graph, graph.get_includers(a))
+        );
+    }
+
+    #[test]
+    fn strongly_connected_components_test() {
+        let graph = make_graph(&[
+            ("main.cpp", "a.h"),
+            ("a.h", "b.h"),
+            ("b.h", "c.h"),
+            ("c.h", "a.h"),
+            ("c.h", "d.h"),
+            ("d.h", "d.h"),
+        ]);
+
+        let components: Vec<Vec<&str>> = graph
+            .strongly_connected_components()
+            .iter()
+            .map(|c| names(&graph, c))
+            .collect();
+
+        // reverse topological order: included components first
+        assert_eq!(
+            vec![vec!["d.h"], vec!["a.h", "b.h", "c.h"], vec!["main.cpp"]],
+            components
+        );
+    }
+
+    #[test]
+    fn reachable_test() {
+        let graph = make_graph(&[
+            ("main.cpp", "a.h"),
+            ("a.h", "b.h"),
+            ("other.cpp", "b.h"),
+            ("other.cpp", "c.h"),
+        ]);
+        let id = |name: &str| graph.get_id(name).unwrap();
+
+        assert_eq!(
+            vec!["a.h", "b.h", "main.cpp"],
+            names(
+                &graph,
+                &graph.reachable(&[id("main.cpp")], Direction::Includes)
+            )
+        );
+        assert_eq!(
+            vec!["a.h", "b.h", "main.cpp", "other.cpp"],
+            names(&graph, &graph.reachable(&[id("b.h")], Direction::Includers))
+        );
+        assert_eq!(
+            vec!["c.h"],
+            names(&graph, &graph.reachable(&[id("c.h")], Direction::Includes))
+        );
+    }
+
+    #[test]
+    fn k_shortest_paths_test() {
+        let graph = make_graph(&[
+            ("main.cpp", "a.h"),
+            ("main.cpp", "b.h"),
+            ("main.cpp", "c.h"),
+            ("a.h", "b.h"),
+            ("a.h", "x.h"),
+            ("b.h", "x.h"),
+            ("c.h", "d.h"),
+            ("d.h", "x.h"),
+        ]);
+        let id = |n| graph.get_id(n).unwrap();
+        let paths = |k| -> Vec<Vec<&str>> {
+            graph
+                .k_shortest_paths(id("main.cpp"), id("x.h"), k)
+                .iter()
+                .map(|p| names(&graph, p))
+                .collect()
+        };
+
+        assert_eq!(vec![vec!["main.cpp", "a.h", "x.h"]], paths(1));
+        assert_eq!(
+            vec![
+                vec!["main.cpp", "a.h", "x.h"],
+                vec!["main.cpp", "b.h", "x.h"],
+                vec!["main.cpp", "a.h", "b.h", "x.h"],
+            ],
+            paths(3)
+        );
+        assert_eq!(4, paths(10).len());
+        assert!(paths(0).is_empty());
+        assert!(graph
+            .k_shortest_paths(id("x.h"), id("main.cpp"), 3)
+            .is_empty());
+    }
+
+    #[test]
+    fn simple_paths_test() {
+        let graph = make_graph(&[
+            ("main.cpp", "a.h"),
+            ("main.cpp", "b.h"),
+            ("main.cpp", "c.h"),
+            ("a.h", "b.h"),
+            ("a.h", "x.h"),
+            ("b.h", "x.h"),
+            ("b.h", "a.h"),
+            ("c.h", "d.h"),
+            ("d.h", "x.h"),
+        ]);
+        let id = |n| graph.get_id(n).unwrap();
+        let paths = |limit| -> Vec<Vec<&str>> {
+            graph
+                .simple_paths(id("main.cpp"), id("x.h"), limit)
+                .iter()
+                .map(|p| names(&graph, p))
+                .collect()
+        };
+
+        assert_eq!(
+            vec![
+                vec!["main.cpp", "a.h", "b.h", "x.h"],
+                vec!["main.cpp", "a.h", "x.h"],
+                vec!["main.cpp", "b.h", "a.h", "x.h"],
+                vec!["main.cpp", "b.h", "x.h"],
+                vec!["main.cpp", "c.h", "d.h", "x.h"],
+            ],
+            paths(100)
+        );
+        assert_eq!(2, paths(2).len());
+
+        let cycles = graph.simple_paths(id("a.h"), id("a.h"), 100);
+        assert_eq!(
+            vec![vec!["a.h", "b.h", "a.h"]],
+            cycles.iter().map(|p| names(&graph, p)).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn shortest_path_test() {
+        let graph = make_graph(&[
+            ("main.cpp", "a.h"),
+            ("a.h", "b.h"),
+            ("b.h", "c.h"),
+            ("main.cpp", "c.h"),
+            ("c.h", "a.h"),
+        ]);
+        let id = |n| graph.get_id(n).unwrap();
+
+        let path = graph.shortest_path(id("main.cpp"), id("b.h"), |_| true);
+        assert_eq!(
+            vec!["main.cpp", "a.h", "b.h"],
+            names(&graph, &path.unwrap())
+        );
+
+        let cycle = graph.shortest_path(id("a.h"), id("a.h"), |_| true);
+        assert_eq!(
+            vec!["a.h", "b.h", "c.h", "a.h"],
+            names(&graph, &cycle.unwrap())
+        );
+
+        assert!(graph
+            .shortest_path(id("a.h"), id("main.cpp"), |_| true)
+            .is_none());
+        assert!(graph
+            .shortest_path(id("main.cpp"), id("b.h"), |n| n != id("a.h"))
+            .is_none());
+    }
+
+    /**
+     * Reference implementation: the weights of the nodes visited by a plain traversal.
+     */
+    fn reference_weight_sums(
+        graph: &IncludeGraph,
+        direction: Direction,
+        weights: &[u64],
+    ) -> Vec<u64> {
+        (0..graph.len())
+            .map(|start| {
+                let mut visited = vec![false; graph.len()];
+                visited[start] = true;
+                let mut stack = vec![start];
+                while let Some(node) = stack.pop() {
+                    let adjacency = match direction {
+                        Direction::Includes => graph.get_includes(node),
+                        Direction::Includers => graph.get_includers(node),
+                    };
+                    for &next in adjacency {
+                        if !visited[next] {
+                            visited[next] = true;
+                            stack.push(next);
+                        }
+                    }
+                }
+                (0..graph.len())
+                    .filter(|&n| visited[n] && n != start)
+                    .map(|n| weights[n])
+                    .sum()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reachable_weight_sums_test() {
+        let graph = make_graph(&[
+            ("main.cpp", "a.h"),
+            ("a.h", "b.h"),
+            ("b.h", "a.h"),
+            ("b.h", "c.h"),
+            ("other.cpp", "c.h"),
+            ("c.h", "c.h"),
+        ]);
+        let id = |n| graph.get_id(n).unwrap();
+        let ones = vec![1; graph.len()];
+
+        let includers = graph.reachable_weight_sums(Direction::Includers, &ones, false);
+        assert_eq!(4, includers[id("c.h")]);
+        assert_eq!(2, includers[id("a.h")]);
+        assert_eq!(0, includers[id("main.cpp")]);
+
+        let includes = graph.reachable_weight_sums(Direction::Includes, &ones, false);
+        assert_eq!(3, includes[id("main.cpp")]);
+        assert_eq!(2, includes[id("a.h")]);
+        assert_eq!(0, includes[id("c.h")]);
+    }
+
+    #[test]
+    fn reachable_weight_sums_matches_reference_test() {
+        // pseudo-random graph, large enough to span multiple chunks and with plenty of cycles
+        let names: Vec<String> = (0..1500).map(|i| format!("file{:04}.h", i)).collect();
+        let mut seed: u64 = 42;
+        let mut next_random = || {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) as usize
+        };
+        let mut edges = Vec::new();
+        for (i, name) in names.iter().enumerate() {
+            for _ in 0..2 {
+                // mostly towards lower indices, with some back edges creating cycles
+                let target = if next_random() % 50 == 0 {
+                    next_random() % names.len()
+                } else {
+                    next_random() % (i + 1)
+                };
+                edges.push((name.as_str(), names[target].as_str()));
+            }
+        }
+        let graph = make_graph(&edges);
+        let components = graph.strongly_connected_components();
+        assert!(components.len() > CHUNK_BITS);
+        assert!(components.iter().any(|c| c.len() > 1));
+
+        let weights: Vec<u64> = (0..graph.len())
+            .map(|_| (next_random() % 5000) as u64)
+            .collect();
+
+        for direction in [Direction::Includes, Direction::Includers] {
+            let expected = reference_weight_sums(&graph, direction, &weights);
+            assert_eq!(
+                expected,
+                graph.reachable_weight_sums(direction, &weights, false)
+            );
+            assert_eq!(
+                expected,
+                graph.reachable_weight_sums(direction, &weights, true)
+            );
+        }
+    }
+
+    #[test]
+    fn reachable_from_roots_test() {
+        let graph = make_graph(&[
+            ("main.cpp", "platform.h"),
+            ("main.cpp", "foo.h"),
+            ("foo.h", "bar.h"),
+            ("bar.h", "foo.h"),
+            ("other.cpp", "bar.h"),
+            ("tool.cpp", "tool.h"),
+        ]);
+        let id = |n| graph.get_id(n).unwrap();
+        let is_marked = |n| graph.get_node(n).ends_with(".h") || graph.get_node(n) == "other.cpp";
+
+        let reachable =
+            graph.reachable_from_roots(&[id("foo.h"), id("tool.h"), id("other.cpp")], is_marked);
+
+        // foo.h is reached from main.cpp, and from other.cpp through the cycle
+        assert_eq!(
+            vec!["bar.h", "foo.h", "other.cpp", "platform.h"],
+            names(&graph, &reachable[0])
+        );
+        assert_eq!(vec!["tool.h"], names(&graph, &reachable[1]));
+        assert_eq!(
+            vec!["bar.h", "foo.h", "other.cpp"],
+            names(&graph, &reachable[2])
+        );
+    }
+
+    #[test]
+    fn reachable_from_roots_matches_reference_test() {
+        // pseudo-random graph, large enough to span multiple chunks and with plenty of cycles
+        let names: Vec<String> = (0..3000).map(|i| format!("file{:04}.h", i)).collect();
+        let mut seed: u64 = 7;
+        let mut next_random = || {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) as usize
+        };
+        let mut edges = Vec::new();
+        for (i, name) in names.iter().enumerate().skip(1) {
+            // mostly towards lower indices, with some back edges creating cycles
+            let target = if next_random() % 50 == 0 {
+                next_random() % names.len()
+            } else {
+                next_random() % i
+            };
+            edges.push((name.as_str(), names[target].as_str()));
+        }
+        let graph = make_graph(&edges);
+        let is_marked = |n: usize| n.is_multiple_of(2);
+        assert!((0..graph.len()).filter(|&n| is_marked(n)).count() > CHUNK_BITS);
+
+        let components = graph.strongly_connected_components();
+        let mut is_root = vec![false; graph.len()];
+        for component in &components {
+            let has_includers = component
+                .iter()
+                .flat_map(|&n| graph.get_includers(n))
+                .any(|n| !component.contains(n));
+            for &node in component {
+                is_root[node] = !has_includers;
+            }
+        }
+
+        let targets: Vec<usize> = (0..graph.len()).step_by(97).collect();
+        let reachable = graph.reachable_from_roots(&targets, is_marked);
+        for (&target, nodes) in targets.iter().zip(&reachable) {
+            let roots: Vec<usize> = graph
+                .reachable(&[target], Direction::Includers)
+                .into_iter()
+                .filter(|&n| is_root[n])
+                .collect();
+            let expected: Vec<usize> = graph
+                .reachable(&roots, Direction::Includes)
+                .into_iter()
+                .filter(|&n| is_marked(n))
+                .collect();
+            assert_eq!(&expected, nodes);
+        }
+    }
+}
@@ -0,0 +1,515 @@
+use crate::file::{File, IncludeDirective, IncludeKind, IncludeMode};
+use regex::Regex;
+use std::error::Error;
+
+/**
+ * The default include groups, in order: quoted and angled includes. Each group is matched against
+ * the include name with its delimiters (e.g., "\"foo.h\"" or "<vector>"); telling third-party
+ * headers from the standard library ones (e.g., <QString> from <string>) takes custom groups.
+ */
+pub const DEFAULT_INCLUDE_GROUPS: [(&str, &str); 2] = [("project", r#"^""#), ("system", "^<")];
+
+/**
+ * A group of includes, e.g., the Qt headers.
+ */
+#[derive(Debug, Clone)]
+struct IncludeGroup {
+    name: String,
+    pattern: Regex,
+}
+
+/**
+ * The ordering policy of the blocks of includes, i.e., the include directives following each
+ * other with only blank lines and comments between them:
+ * - groups: the include groups, in order; includes matching no group come after all of them
+ * - own_header_first: whether the own header of a file (e.g., "foo.h" for "foo.cpp") comes
+ *   before all the groups
+ * - sorting: whether the includes of each group are sorted alphabetically, ignoring case
+ * - blank_lines: whether groups are separated by blank lines, and only them
+ */
+#[derive(Debug, Clone)]
+pub struct IncludeOrderPolicy {
+    groups: Vec<IncludeGroup>,
+    own_header_first: bool,
+    sorting: bool,
+    blank_lines: bool,
+}
+
+impl IncludeOrderPolicy {
+    /**
+     * Builds the policy with the given include groups, in order.
+     *
+     * - groups: the name and the regex of each group (e.g., ("Qt", "^<Q"))
+     */
+    pub fn make(groups: &[(&str, &str)]) -> Result<IncludeOrderPolicy, Box<dyn Error>> {
+        let groups = groups
+            .iter()
+            .map(|&(name, pattern)| {
+                Regex::new(pattern)
+                    .map(|pattern| IncludeGroup {
+                        name: name.to_string(),
+                        pattern,
+                    })
+                    .map_err(|e| format!("Invalid regex of include group {}: {}", name, e))
+            })
+            .collect::<Result<Vec<IncludeGroup>, String>>()?;
+
+        Ok(IncludeOrderPolicy {
+            groups,
+            own_header_first: true,
+            sorting: true,
+            blank_lines: true,
+        })
+    }
+
+    pub fn with_own_header_first(mut self, own_header_first: bool) -> Self {
+        self.own_header_first = own_header_first;
+        self
+    }
+
+    pub fn with_sorting(mut self, sorting: bool) -> Self {
+        self.sorting = sorting;
+        self
+    }
+
+    pub fn with_blank_lines(mut self, blank_lines: bool) -> Self {
+        self.blank_lines = blank_lines;
+        self
+    }
+
+    /**
+     * Checks the include blocks of a file, returning the violations of the policy, sorted by
+     * line, and the fixed version of each block with violations.
+     */
+    pub fn check(&self, file: &File) -> IncludeOrderReport {
+        let mut report = IncludeOrderReport::default();
+
+        for block in Self::make_include_blocks(file) {
+            let num_violations = report.violations.len();
+            let groups: Vec<usize> = block.iter().map(|d| self.get_group(file, d)).collect();
+
+            // the include of the latest group so far, which later groups must not precede; blank
+            // lines are checked only after includes in the right group
+            let mut latest = 0;
+            for index in 1..block.len() {
+                let (directive, group) = (block[index], groups[index]);
+                let (previous, previous_group) = (block[index - 1], groups[index - 1]);
+
+                let violation = if group < groups[latest] {
+                    Some(format!(
+                        "{} ({}) should come before {} ({}) at line {}",
+                        directive.get_text(),
+                        self.get_group_name(group),
+                        block[latest].get_text(),
+                        self.get_group_name(groups[latest]),
+                        block[latest].get_line()
+                    ))
+                } else if self.sorting
+                    && group == previous_group
+                    && Self::sorting_key(directive) < Self::sorting_key(previous)
+                {
+                    Some(format!(
+                        "{} should come before {} at line {}",
+                        directive.get_text(),
+                        previous.get_text(),
+                        previous.get_line()
+                    ))
+                } else if previous_group != groups[latest] {
+                    None
+                } else if self.blank_lines
+                    && group != previous_group
+                    && directive.get_blank_lines_before() == Some(0)
+                {
+                    Some(format!(
+                        "missing blank line between the {} and the {} includes",
+                        self.get_group_name(previous_group),
+                        self.get_group_name(group)
+                    ))
+                } else if self.blank_lines
+                    && group == previous_group
+                    && directive.get_blank_lines_before() > Some(0)
+                {
+                    Some(format!(
+                        "blank line between {} includes",
+                        self.get_group_name(group)
+                    ))
+                } else {
+                    None
+                };
+
+                if let Some(message) = violation {
+                    report.violations.push(OrderViolation {
+                        line: directive.get_line(),
+                        message,
+                    });
+                }
+                if group > groups[latest] {
+                    latest = index;
+                }
+            }
+
+            // fixing blocks with comments between or after includes would drop, misplace or
+            // rewrite them
+            let has_comments = block.iter().skip(1).any(|d| d.has_comments_before())
+                || block.iter().any(|d| d.get_comment().is_some());
+            if report.violations.len() > num_violations && !has_comments {
+                report
+                    .fixed_blocks
+                    .push(self.make_fixed_block(&block, &groups));
+            }
+        }
+
+        report
+    }
+
+    /**
+     * Returns the blocks of includes of a file which can be reordered, i.e., the includes of
+     * file names written in the file.
+     */
+    fn make_include_blocks(file: &File) -> Vec<Vec<&IncludeDirective>> {
+        let mut blocks: Vec<Vec<&IncludeDirective>> = Vec::new();
+        let mut is_block_open = false;
+
+        for d in file.get_include_directives() {
+            let is_orderable = !d.is_forced()
+                && matches!(
+                    d.get_mode(),
+                    IncludeMode::Include | IncludeMode::IncludeNext
+                )
+                && matches!(d.get_kind(), IncludeKind::Quoted | IncludeKind::Angled)
+                && !d.is_computed();
+            if !is_orderable {
+                is_block_open = false;
+                continue;
+            }
+
+            match blocks.last_mut() {
+                Some(block) if is_block_open && d.get_blank_lines_before().is_some() => {
+                    block.push(d)
+                }
+                _ => blocks.push(vec![d]),
+            }
+            is_block_open = true;
+        }
+
+        blocks
+    }
+
+    /**
+     * Returns the index of the group of an include: 0 for the own header, if first, then the
+     * groups in order, followed by the includes matching no group.
+     */
+    fn get_group(&self, file: &File, directive: &IncludeDirective) -> usize {
+        if self.own_header_first && Self::is_own_header(file, directive) {
+            return 0;
+        }
+
+        let name = match directive.get_kind() {
+            IncludeKind::Angled => format!("<{}>", directive.get_spelling()),
+            _ => format!("\"{}\"", directive.get_spelling()),
+        };
+        let index = self
+            .groups
+            .iter()
+            .position(|g| g.pattern.is_match(&name))
+            .unwrap_or(self.groups.len());
+
+        index + 1
+    }
+
+    fn get_group_name(&self, group: usize) -> &str {
+        match group {
+            0 => "own header",
+            _ => self.groups.get(group - 1).map_or("other", |g| &g.name),
+        }
+    }
+
+    /**
+     * Returns whether the include is the own header of the file, i.e., a quoted include with the
+     * same name but a different extension (e.g., "Public/foo.h" for "Private/foo.cpp").
+     */
+    fn is_own_header(file: &File, directive: &IncludeDirective) -> bool {
+        let name = directive
+            .get_spelling()
+            .rsplit('/')
+            .next()
+            .unwrap_or_default();
+        match (name.rsplit_once('.'), file.get_name().rsplit_once('.')) {
+            (Some((stem, extension)), Some((file_stem, file_extension))) => {
+                directive.get_kind() == IncludeKind::Quoted
+                    && stem == file_stem
+                    && extension != file_extension
+            }
+            _ => false,
+        }
+    }
+
+    fn sorting_key(directive: &IncludeDirective) -> (String, &str) {
+        let spelling = directive.get_spelling();
+        (spelling.to_lowercase(), spelling)
+    }
+
+    /**
+     * Returns the block with its includes ordered by group and, if sorting, by name, with a
+     * blank line between groups, if required.
+     */
+    fn make_fixed_block(&self, block: &[&IncludeDirective], groups: &[usize]) -> FixedBlock {
+        let mut includes: Vec<(usize, &IncludeDirective)> =
+            groups.iter().cloned().zip(block.iter().cloned()).collect();
+        includes.sort_by(|(a_group, a), (b_group, b)| {
+            a_group.cmp(b_group).then_with(|| match self.sorting {
+                true => Self::sorting_key(a).cmp(&Self::sorting_key(b)),
+                false => a.get_line().cmp(&b.get_line()),
+            })
+        });
+
+        let mut lines = Vec::new();
+        for (index, (group, d)) in includes.iter().enumerate() {
+            if self.blank_lines && index > 0 && includes[index - 1].0 != *group {
+                lines.push(String::new());
+            }
+            lines.push(d.get_text().to_string());
+        }
+
+        FixedBlock {
+            first_line: block.first().map_or(0, |d| d.get_line()),
+            last_line: block.last().map_or(0, |d| d.get_line()),
+            lines,
+        }
+    }
+}
+
+impl Default for IncludeOrderPolicy {
+    fn default() -> Self {
+        IncludeOrderPolicy::make(&DEFAULT_INCLUDE_GROUPS).expect("Invalid default include groups")
+    }
+}
+
+/**
+ * The violations of the include ordering policy found in a file.
+ */
+#[derive(Debug, Default)]
+pub struct IncludeOrderReport {
+    violations: Vec<OrderViolation>,
+    fixed_blocks: Vec<FixedBlock>,
+}
+
+impl IncludeOrderReport {
+    pub fn get_violations(&self) -> &[OrderViolation] {
+        &self.violations
+    }
+
+    /**
+     * Returns the fixed version of each include block with violations, except for the blocks
+     * with comments, between their includes or after them.
+     */
+    pub fn get_fixed_blocks(&self) -> &[FixedBlock] {
+        &self.fixed_blocks
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderViolation {
+    line: usize,
+    message: String,
+}
+
+impl OrderViolation {
+    /**
+     * Returns the 1-based line of the include violating the policy.
+     */
+    pub fn get_line(&self) -> usize {
+        self.line
+    }
+
+    /**
+     * Returns the description of the violation (e.g., "missing blank line between the project and
+     * the system includes").
+     */
+    pub fn get_message(&self) -> &str {
+        &self.message
+    }
+}
+
+/**
+ * A block of includes ordered according to the policy, replacing the lines from the first to the
+ * last include of the original block.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixedBlock {
+    first_line: usize,
+    last_line: usize,
+    lines: Vec<String>,
+}
+
+impl FixedBlock {
+    pub fn get_first_line(&self) -> usize {
+        self.first_line
+    }
+
+    pub fn get_last_line(&self) -> usize {
+        self.last_line
+    }
+
+    pub fn get_lines(&self) -> &[String] {
+        &self.lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+
+    fn violation_lines(policy: &IncludeOrderPolicy, file: &File) -> Vec<usize> {
+        policy
+            .check(file)
+            .get_violations()
+            .iter()
+            .map(|v| v.get_line())
+            .collect()
+    }
+
+    #[test]
+    fn ordered_includes_test() -> Result<(), Box<dyn Error>> {
+        let file = File::make(
+            "Private/foo.cpp",
+            "\
+#include \"Public/foo.h\"
+
+#include \"bar.h\"
+#include \"Baz.h\"
+
+#include <boost/any.hpp>
+#include <Eigen/Dense>
+#include <QString>
+#include <string>
+#include <vector>
+#include <zlib.h>
+
+int x;
+#include \"late.h\"",
+        )?;
+
+        let report = IncludeOrderPolicy::default().check(&file);
+        assert!(report.get_violations().is_empty());
+        assert!(report.get_fixed_blocks().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn include_order_violations_test() -> Result<(), Box<dyn Error>> {
+        let file = File::make(
+            "foo.cpp",
+            "\
+#include <vector>
+#include \"foo.h\"
+
+#include \"b.h\"
+
+#include \"a.h\"
+#include <string>
+#ifdef _WIN32
+#include <windows.h>
+#endif",
+        )?;
+
+        let policy = IncludeOrderPolicy::default();
+        let report = policy.check(&file);
+        let violations: Vec<(usize, &str)> = report
+            .get_violations()
+            .iter()
+            .map(|v| (v.get_line(), v.get_message()))
+            .collect();
+        assert_eq!(
+            vec![
+                (
+                    2,
+                    "#include \"foo.h\" (own header) should come before #include <vector> (system) at line 1"
+                ),
+                (
+                    4,
+                    "#include \"b.h\" (project) should come before #include <vector> (system) at line 1"
+                ),
+                (
+                    6,
+                    "#include \"a.h\" (project) should come before #include <vector> (system) at line 1"
+                ),
+            ],
+            violations
+        );
+
+        assert_eq!(1, report.get_fixed_blocks().len());
+        let fixed = &report.get_fixed_blocks()[0];
+        assert_eq!((1, 7), (fixed.get_first_line(), fixed.get_last_line()));
+        assert_eq!(
+            vec![
+                "#include \"foo.h\"",
+                "",
+                "#include \"a.h\"",
+                "#include \"b.h\"",
+                "",
+                "#include <string>",
+                "#include <vector>",
+            ],
+            fixed.get_lines()
+        );
+
+        // sorting and separation
+        let file = File::make(
+            "main.cpp",
+            "#include \"b.h\"\n\n#include \"a.h\"\n#include <vector>",
+        )?;
+        assert_eq!(vec![3, 4], violation_lines(&policy, &file));
+
+        let policy = IncludeOrderPolicy::default()
+            .with_sorting(false)
+            .with_blank_lines(false);
+        assert!(violation_lines(&policy, &file).is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn commented_block_test() -> Result<(), Box<dyn Error>> {
+        let file = File::make(
+            "main.cpp",
+            "#include <vector>\n// keep this note\n#include \"a.h\"\nint x;\n#include \"c.h\"\n#include \"b.h\"\nint y;\n#include \"e.h\" /* note */\n#include \"d.h\"",
+        )?;
+
+        // the violations are reported, but only the block without comments is fixed
+        let report = IncludeOrderPolicy::default().check(&file);
+        let lines: Vec<usize> = report
+            .get_violations()
+            .iter()
+            .map(|v| v.get_line())
+            .collect();
+        assert_eq!(vec![3, 6, 9], lines);
+        assert_eq!(1, report.get_fixed_blocks().len());
+        assert_eq!(5, report.get_fixed_blocks()[0].get_first_line());
+
+        Ok(())
+    }
+
+    #[test]
+    fn custom_include_groups_test() -> Result<(), Box<dyn Error>> {
+        let file = File::make(
+            "main.cpp",
+            "#include <QWidget>\n\n#include <boost/any.hpp>\n\n#include \"main.h\"",
+        )?;
+
+        let policy = IncludeOrderPolicy::make(&[("Qt", "^<Q"), ("boost", "^<boost/")])?;
+        assert_eq!(vec![5], violation_lines(&policy, &file));
+
+        let policy = IncludeOrderPolicy::make(&[("boost", "^<boost/"), ("Qt", "^<Q")])?;
+        assert_eq!(vec![3, 5], violation_lines(&policy, &file));
+
+        let policy = policy.with_own_header_first(false);
+        assert_eq!(vec![3], violation_lines(&policy, &file));
+
+        assert!(IncludeOrderPolicy::make(&[("broken", "(")]).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,687 @@
+use crate::file::{IncludeDirective, IncludeKind};
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/**
+ * The include search directories, in the same flavours a compiler accepts them.
+ * - quote_dirs: searched only by quoted includes (e.g., "-iquote")
+ * - user_dirs: searched by both quoted and angled includes (e.g., "-I")
+ * - system_dirs: searched after the user directories (e.g., "-isystem")
+ *
+ * Relative directories are relative to the project root.
+ */
+#[derive(Debug, Clone)]
+pub struct IncludeSettings {
+    quote_dirs: Vec<String>,
+    user_dirs: Vec<String>,
+    system_dirs: Vec<String>,
+    suffix_fallback: bool,
+}
+
+impl IncludeSettings {
+    /**
+     * - suffix_fallback: when true, includes not found through the search directories are matched
+     *   against the project files ending with the include spelling, which is useful when the
+     *   actual include directories are not known.
+     */
+    pub fn make(
+        quote_dirs: Vec<String>,
+        user_dirs: Vec<String>,
+        system_dirs: Vec<String>,
+        suffix_fallback: bool,
+    ) -> Self {
+        IncludeSettings {
+            quote_dirs,
+            user_dirs,
+            system_dirs,
+            suffix_fallback,
+        }
+    }
+
+    pub fn get_quote_dirs(&self) -> &[String] {
+        &self.quote_dirs
+    }
+
+    pub fn get_user_dirs(&self) -> &[String] {
+        &self.user_dirs
+    }
+
+    pub fn get_system_dirs(&self) -> &[String] {
+        &self.system_dirs
+    }
+
+    pub fn is_suffix_fallback_enabled(&self) -> bool {
+        self.suffix_fallback
+    }
+}
+
+impl Default for IncludeSettings {
+    fn default() -> Self {
+        IncludeSettings::make(Vec::new(), Vec::new(), Vec::new(), true)
+    }
+}
+
+/**
+ * Set of files belonging to the project, identified by their normalized path relative to the
+ * project root.
+ */
+pub trait ProjectFiles {
+    fn contains_file(&self, path: &str) -> bool;
+}
+
+impl ProjectFiles for HashSet<&str> {
+    fn contains_file(&self, path: &str) -> bool {
+        self.contains(path)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchDirectoryKind {
+    Quote,
+    User,
+    System,
+}
+
+#[derive(Debug)]
+enum SearchLocation {
+    /// A directory inside the project, as a normalized path relative to the project root.
+    Project(String),
+    /// A directory outside the project.
+    External(PathBuf),
+}
+
+#[derive(Debug)]
+pub struct SearchDirectory {
+    location: SearchLocation,
+    kind: SearchDirectoryKind,
+}
+
+impl SearchDirectory {
+    pub fn get_kind(&self) -> SearchDirectoryKind {
+        self.kind
+    }
+
+    /**
+     * Returns the project-relative path of the directory, if it is inside the project.
+     */
+    pub fn get_project_path(&self) -> Option<&str> {
+        match &self.location {
+            SearchLocation::Project(path) => Some(path),
+            SearchLocation::External(_) => None,
+        }
+    }
+}
+
+/**
+ * How an include has been resolved.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Resolution {
+    /// A project file, identified by its normalized path relative to the project root.
+    Project { path: String, origin: Origin },
+    /// A file outside the project, found in the search directory with the given index, if any
+    /// (i.e., None for includes spelled with an absolute path).
+    External {
+        path: PathBuf,
+        directory: Option<usize>,
+    },
+    /// A C++20 module, identified by its node name (e.g., "module:foo"), declared by some
+    /// project file or not (e.g., "import std;").
+    Module { node: String, declared: bool },
+    /// The included file has not been found.
+    Unresolved,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Origin {
+    /// Found relative to the directory of the including file.
+    IncluderDirectory,
+    /// Found in the search directory with the given index.
+    SearchDirectory(usize),
+    /// Spelled with an absolute path.
+    AbsolutePath,
+    /// Not found by the compiler search rules, but matched against the project files ending with
+    /// the include spelling.
+    SuffixMatch,
+    /// The module interface unit (or partition) declaring the module.
+    ModuleDeclaration,
+}
+
+/**
+ * Resolves include directives to concrete files, mimicking the compiler search rules:
+ * - quoted includes are searched relative to the including file first, then in the quote, user
+ *   and system directories, in this order;
+ * - angled includes are searched in the user and system directories only.
+ */
+#[derive(Debug)]
+pub struct IncludeResolver {
+    project_root: PathBuf,
+    search_directories: Vec<SearchDirectory>,
+    suffix_fallback: bool,
+
+    /**
+     * Cache of the existence checks of files outside the project, shared by the threads
+     * resolving includes.
+     */
+    external_files: Mutex<HashMap<PathBuf, bool>>,
+}
+
+impl IncludeResolver {
+    pub fn make(
+        project_root: &Path,
+        settings: &IncludeSettings,
+    ) -> Result<IncludeResolver, Box<dyn Error>> {
+        let project_root = project_root
+            .canonicalize()
+            .unwrap_or_else(|_| project_root.to_path_buf());
+
+        let directories = [
+            (&settings.quote_dirs, SearchDirectoryKind::Quote),
+            (&settings.user_dirs, SearchDirectoryKind::User),
+            (&settings.system_dirs, SearchDirectoryKind::System),
+        ];
+
+        let mut search_directories = Vec::new();
+        for (dirs, kind) in directories {
+            for dir in dirs {
+                search_directories.push(SearchDirectory {
+                    location: Self::make_search_location(&project_root, dir)?,
+                    kind,
+                });
+            }
+        }
+
+        Ok(IncludeResolver {
+            project_root,
+            search_directories,
+            suffix_fallback: settings.suffix_fallback,
+            external_files: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn get_search_directories(&self) -> &[SearchDirectory] {
+        &self.search_directories
+    }
+
+    pub fn is_suffix_fallback_enabled(&self) -> bool {
+        self.suffix_fallback
+    }
+
+    /**
+     * Resolves an include directive found in the given file, searching the directories following
+     * the one of the including file for #include_next and __has_include_next.
+     */
+    pub fn resolve_directive(
+        &self,
+        includer: &str,
+        directive: &IncludeDirective,
+        project_files: &impl ProjectFiles,
+    ) -> Resolution {
+        let spelling = directive.get_spelling();
+        match directive.is_next() {
+            true => self.resolve_next(includer, spelling, directive.get_kind(), project_files),
+            false => self.resolve(includer, spelling, directive.get_kind(), project_files),
+        }
+    }
+
+    /**
+     * Resolves an include directive.
+     *
+     * - includer: the project-relative path of the including file
+     * - spelling: the file name as written in the include directive
+     * - kind: whether the include is quoted or angled
+     * - project_files: the files belonging to the project
+     */
+    pub fn resolve(
+        &self,
+        includer: &str,
+        spelling: &str,
+        kind: IncludeKind,
+        project_files: &impl ProjectFiles,
+    ) -> Resolution {
+        self.resolve_from(includer, spelling, kind, project_files, None)
+    }
+
+    /**
+     * Resolves an #include_next directive, searching only the directories following the one
+     * where the including file has been found, as compilers do. When the including file has not
+     * been found in any search directory, all of them are searched, but not the directory of the
+     * including file.
+     */
+    pub fn resolve_next(
+        &self,
+        includer: &str,
+        spelling: &str,
+        kind: IncludeKind,
+        project_files: &impl ProjectFiles,
+    ) -> Resolution {
+        let includer_directory = self
+            .search_directories
+            .iter()
+            .position(|d| {
+                d.get_project_path().is_some_and(|dir| {
+                    dir.is_empty()
+                        || includer
+                            .strip_prefix(dir)
+                            .is_some_and(|r| r.starts_with('/'))
+                })
+            })
+            .map_or(0, |index| index + 1);
+
+        self.resolve_from(
+            includer,
+            spelling,
+            kind,
+            project_files,
+            Some(includer_directory),
+        )
+    }
+
+    /**
+     * Resolves an include directive, searching the directory of the includer and then all the
+     * search directories, or only the search directories from the given index.
+     */
+    fn resolve_from(
+        &self,
+        includer: &str,
+        spelling: &str,
+        kind: IncludeKind,
+        project_files: &impl ProjectFiles,
+        first_directory: Option<usize>,
+    ) -> Resolution {
+        // neither unexpanded macros nor module names are files to look for
+        if kind == IncludeKind::Computed || kind == IncludeKind::Module {
+            return Resolution::Unresolved;
+        }
+
+        if Path::new(spelling).is_absolute() {
+            return self.resolve_absolute(spelling, project_files);
+        }
+
+        if kind == IncludeKind::Quoted && first_directory.is_none() {
+            let candidate = join_paths(extract_directory_from_path(includer), spelling);
+            if project_files.contains_file(&candidate) {
+                return Resolution::Project {
+                    path: candidate,
+                    origin: Origin::IncluderDirectory,
+                };
+            }
+        }
+
+        let first_directory = first_directory.unwrap_or(0);
+        for (index, directory) in self
+            .search_directories
+            .iter()
+            .enumerate()
+            .skip(first_directory)
+        {
+            if kind == IncludeKind::Angled && directory.kind == SearchDirectoryKind::Quote {
+                continue;
+            }
+
+            match &directory.location {
+                SearchLocation::Project(dir) => {
+                    let candidate = join_paths(dir, spelling);
+                    if project_files.contains_file(&candidate) {
+                        return Resolution::Project {
+                            path: candidate,
+                            origin: Origin::SearchDirectory(index),
+                        };
+                    }
+                }
+                SearchLocation::External(dir) => {
+                    let candidate = dir.join(spelling);
+                    if self.is_external_file(&candidate) {
+                        return Resolution::External {
+                            path: candidate,
+                            directory: Some(index),
+                        };
+                    }
+                }
+            }
+        }
+
+        Resolution::Unresolved
+    }
+
+    /**
+     * Returns the path relative to the project root of the given absolute path, if it is inside
+     * the project.
+     */
+    pub fn make_project_path(&self, path: &Path) -> Option<String> {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        path.strip_prefix(&self.project_root)
+            .ok()
+            .and_then(|relative| relative.to_str())
+            .map(|relative| normalize_path(&relative.replace('\\', "/")))
+    }
+
+    fn resolve_absolute(&self, spelling: &str, project_files: &impl ProjectFiles) -> Resolution {
+        let path = Path::new(spelling);
+        if let Some(relative) = self.make_project_path(path) {
+            if project_files.contains_file(&relative) {
+                return Resolution::Project {
+                    path: relative,
+                    origin: Origin::AbsolutePath,
+                };
+            }
+        }
+
+        if self.is_external_file(path) {
+            return Resolution::External {
+                path: path.to_path_buf(),
+                directory: None,
+            };
+        }
+
+        Resolution::Unresolved
+    }
+
+    fn is_external_file(&self, path: &Path) -> bool {
+        *self
+            .external_files
+            .lock()
+            .unwrap()
+            .entry(path.to_path_buf())
+            .or_insert_with(|| path.is_file())
+    }
+
+    fn make_search_location(
+        project_root: &Path,
+        dir: &str,
+    ) -> Result<SearchLocation, Box<dyn Error>> {
+        let dir_path = Path::new(dir);
+        if dir_path.is_relative() {
+            let normalized = normalize_path(&dir.replace('\\', "/"));
+            if normalized.starts_with("..") {
+                return Ok(SearchLocation::External(project_root.join(dir_path)));
+            }
+            return Ok(SearchLocation::Project(normalized));
+        }
+
+        let dir_path = dir_path
+            .canonicalize()
+            .unwrap_or_else(|_| dir_path.to_path_buf());
+        match dir_path.strip_prefix(project_root) {
+            Ok(relative) => {
+                let relative = relative
+                    .to_str()
+                    .ok_or(format!("Invalid include directory {}", dir))?;
+                Ok(SearchLocation::Project(normalize_path(
+                    &relative.replace('\\', "/"),
+                )))
+            }
+            Err(_) => Ok(SearchLocation::External(dir_path)),
+        }
+    }
+}
+
+/**
+ * Returns the directory part of a '/' separated path, or an empty string for files in the
+ * project root.
+ */
+pub fn extract_directory_from_path(path: &str) -> &str {
+    match path.rfind('/') {
+        Some(index) => &path[..index],
+        None => "",
+    }
+}
+
+/**
+ * Joins a '/' separated directory and relative path, normalizing the result.
+ */
+pub fn join_paths(directory: &str, path: &str) -> String {
+    normalize_path(&format!("{}/{}", directory, path))
+}
+
+/**
+ * Removes "." and ".." components from a '/' separated path. Leading ".." components that would
+ * escape the project root are kept, so that the path does not match any project file.
+ */
+pub fn normalize_path(path: &str) -> String {
+    let mut components: Vec<&str> = Vec::new();
+    for component in path.split('/') {
+        match component {
+            "" | "." => {}
+            ".." => match components.last() {
+                Some(&last) if last != ".." => {
+                    components.pop();
+                }
+                _ => components.push(component),
+            },
+            _ => components.push(component),
+        }
+    }
+
+    components.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_project_files() -> HashSet<&'static str> {
+        HashSet::from([
+            "Source/Core/Public/core.h",
+            "Source/Core/Private/core.cpp",
+            "Source/Core/Private/core.h",
+            "Source/Render/Public/render.h",
+            "ThirdParty/fmt/include/fmt/format.h",
+        ])
+    }
+
+    fn make_resolver(settings: IncludeSettings) -> Result<IncludeResolver, Box<dyn Error>> {
+        IncludeResolver::make(Path::new("/media/workspace/Project"), &settings)
+    }
+
+    #[test]
+    fn quoted_include_prefers_includer_directory_test() -> Result<(), Box<dyn Error>> {
+        let resolver = make_resolver(IncludeSettings::make(
+            vec![],
+            vec![String::from("Source/Core/Public")],
+            vec![],
+            true,
+        ))?;
+
+        let resolution = resolver.resolve(
+            "Source/Core/Private/core.cpp",
+            "core.h",
+            IncludeKind::Quoted,
+            &make_project_files(),
+        );
+
+        assert_eq!(
+            Resolution::Project {
+                path: String::from("Source/Core/Private/core.h"),
+                origin: Origin::IncluderDirectory,
+            },
+            resolution
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn angled_include_skips_includer_directory_test() -> Result<(), Box<dyn Error>> {
+        let resolver = make_resolver(IncludeSettings::make(
+            vec![],
+            vec![String::from("Source/Core/Public")],
+            vec![],
+            true,
+        ))?;
+
+        let resolution = resolver.resolve(
+            "Source/Core/Private/core.cpp",
+            "core.h",
+            IncludeKind::Angled,
+            &make_project_files(),
+        );
+
+        assert_eq!(
+            Resolution::Project {
+                path: String::from("Source/Core/Public/core.h"),
+                origin: Origin::SearchDirectory(0),
+            },
+            resolution
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn include_next_test() -> Result<(), Box<dyn Error>> {
+        let resolver = make_resolver(IncludeSettings::make(
+            vec![],
+            vec![
+                String::from("Source/Core/Private"),
+                String::from("Source/Core/Public"),
+            ],
+            vec![],
+            true,
+        ))?;
+        let project_files = make_project_files();
+
+        // the wrapper header in Private includes the one with the same name in Public
+        assert_eq!(
+            Resolution::Project {
+                path: String::from("Source/Core/Public/core.h"),
+                origin: Origin::SearchDirectory(1),
+            },
+            resolver.resolve_next(
+                "Source/Core/Private/core.h",
+                "core.h",
+                IncludeKind::Quoted,
+                &project_files
+            )
+        );
+        assert_eq!(
+            Resolution::Unresolved,
+            resolver.resolve_next(
+                "Source/Core/Public/core.h",
+                "core.h",
+                IncludeKind::Angled,
+                &project_files
+            )
+        );
+
+        // files not found in a search directory search all of them, but not their own directory
+        assert_eq!(
+            Resolution::Project {
+                path: String::from("Source/Core/Private/core.h"),
+                origin: Origin::SearchDirectory(0),
+            },
+            resolver.resolve_next(
+                "Source/Render/Public/render.h",
+                "core.h",
+                IncludeKind::Quoted,
+                &project_files
+            )
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn search_directories_order_test() -> Result<(), Box<dyn Error>> {
+        let resolver = make_resolver(IncludeSettings::make(
+            vec![String::from("Source/Render/Public")],
+            vec![String::from("Source/Core/Public")],
+            vec![String::from("ThirdParty/fmt/include")],
+            true,
+        ))?;
+        let project_files = make_project_files();
+
+        // quote directories are not searched by angled includes
+        assert_eq!(
+            Resolution::Unresolved,
+            resolver.resolve(
+                "Source/Core/Private/core.cpp",
+                "render.h",
+                IncludeKind::Angled,
+                &project_files
+            )
+        );
+        assert_eq!(
+            Resolution::Project {
+                path: String::from("Source/Render/Public/render.h"),
+                origin: Origin::SearchDirectory(0),
+            },
+            resolver.resolve(
+                "Source/Core/Private/core.cpp",
+                "render.h",
+                IncludeKind::Quoted,
+                &project_files
+            )
+        );
+        assert_eq!(
+            Resolution::Project {
+                path: String::from("ThirdParty/fmt/include/fmt/format.h"),
+                origin: Origin::SearchDirectory(2),
+            },
+            resolver.resolve(
+                "Source/Core/Private/core.cpp",
+                "fmt/format.h",
+                IncludeKind::Angled,
+                &project_files
+            )
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn absolute_search_directory_inside_project_test() -> Result<(), Box<dyn Error>> {
+        let resolver = make_resolver(IncludeSettings::make(
+            vec![],
+            vec![String::from("/media/workspace/Project/Source/Core/Public")],
+            vec![],
+            true,
+        ))?;
+
+        assert_eq!(
+            Some("Source/Core/Public"),
+            resolver.get_search_directories()[0].get_project_path()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn external_search_directory_test() -> Result<(), Box<dyn Error>> {
+        let temp_dir = tempdir::TempDir::new("scar_include_resolver_test")?;
+        std::fs::write(temp_dir.path().join("vector"), "")?;
+
+        let resolver = make_resolver(IncludeSettings::make(
+            vec![],
+            vec![],
+            vec![temp_dir.path().to_str().unwrap().to_string()],
+            true,
+        ))?;
+        let project_files = make_project_files();
+
+        match resolver.resolve("main.cpp", "vector", IncludeKind::Angled, &project_files) {
+            Resolution::External { path, directory } => {
+                assert_eq!(Some(0), directory);
+                assert!(path.ends_with("vector"));
+            }
+            other => panic!("Unexpected resolution {:?}", other),
+        }
+        assert_eq!(
+            Resolution::Unresolved,
+            resolver.resolve("main.cpp", "iostream", IncludeKind::Angled, &project_files)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn normalize_path_test() {
+        assert_eq!("a/c.h", normalize_path("a/b/../c.h"));
+        assert_eq!("a/c.h", normalize_path("./a/./c.h"));
+        assert_eq!("../c.h", normalize_path("a/../../c.h"));
+        assert_eq!("c.h", normalize_path("/c.h"));
+    }
+}
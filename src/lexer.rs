@@ -0,0 +1,359 @@
+/**
+ * Prefixes of raw string literals, e.g., R"(...)" or u8R"delimiter(...)delimiter".
+ */
+const RAW_STRING_PREFIXES: [&str; 5] = ["R", "u8R", "uR", "UR", "LR"];
+
+/**
+ * A line of source code as seen by the preprocessor: physical lines joined by backslash-newline
+ * continuations or by block comments spanning multiple lines, with comments replaced by a single
+ * space. String and character literals are kept as they are.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogicalLine {
+    text: String,
+    line: usize,
+    column: usize,
+    comment: Option<String>,
+}
+
+impl LogicalLine {
+    fn make(line: usize) -> Self {
+        LogicalLine {
+            text: String::new(),
+            line,
+            column: 0,
+            comment: None,
+        }
+    }
+
+    /**
+     * Returns the text of the line, with continuations removed and comments replaced by a space.
+     */
+    pub fn get_text(&self) -> &str {
+        &self.text
+    }
+
+    /**
+     * Returns the 1-based physical line of the first token of the line, or of the line start
+     * when the line is blank.
+     */
+    pub fn get_line(&self) -> usize {
+        self.line
+    }
+
+    /**
+     * Returns the 1-based column of the first token of the line in its physical line, 0 when the
+     * line is blank.
+     */
+    pub fn get_column(&self) -> usize {
+        self.column
+    }
+
+    /**
+     * Returns the text of the last comment of the line, without its delimiters and trimmed, if
+     * any.
+     */
+    pub fn get_comment(&self) -> Option<&str> {
+        self.comment.as_deref()
+    }
+
+    /**
+     * Returns whether the line contains no tokens, i.e., only whitespace and comments.
+     */
+    pub fn is_blank(&self) -> bool {
+        self.column == 0
+    }
+
+    /**
+     * Returns the name and the trimmed arguments of the preprocessor directive of the line, if
+     * any (e.g., ("include", "<vector>") for "# include <vector>").
+     */
+    pub fn get_directive(&self) -> Option<(&str, &str)> {
+        let rest = self.text.trim().strip_prefix('#')?.trim_start();
+        let name_length = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+
+        Some((&rest[..name_length], rest[name_length..].trim()))
+    }
+}
+
+/**
+ * Splits the source code into logical lines, the way the preprocessor does before handling
+ * directives: backslash-newline continuations are removed, comments are replaced by a space
+ * (block comments possibly joining several physical lines) and string, character and raw string
+ * literals are skipped, so that comment delimiters inside them are ignored.
+ */
+pub fn split_logical_lines(content: &str) -> Vec<LogicalLine> {
+    Lexer::make(content).split()
+}
+
+struct Lexer {
+    chars: Vec<char>,
+    position: usize,
+    line: usize,
+    column: usize,
+}
+
+impl Lexer {
+    fn make(content: &str) -> Self {
+        Lexer {
+            chars: content.chars().filter(|&c| c != '\r').collect(),
+            position: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+
+    fn split(mut self) -> Vec<LogicalLine> {
+        let mut lines = Vec::new();
+        let mut current = LogicalLine::make(self.line);
+
+        // the identifier or number being read, to recognise raw string prefixes and digit
+        // separators
+        let mut token = String::new();
+
+        while let Some(c) = self.next_spliced() {
+            match c {
+                '\n' => {
+                    lines.push(std::mem::replace(
+                        &mut current,
+                        LogicalLine::make(self.line),
+                    ));
+                    token.clear();
+                    continue;
+                }
+                '/' if self.peek_spliced() == Some('/') => {
+                    self.next_spliced();
+                    let mut comment = String::new();
+                    while let Some(c) = self.peek_spliced() {
+                        if c == '\n' {
+                            break;
+                        }
+                        comment.push(c);
+                        self.next_spliced();
+                    }
+                    current.text.push(' ');
+                    current.comment = Some(comment.trim().to_string());
+                }
+                '/' if self.peek_spliced() == Some('*') => {
+                    self.next_spliced();
+                    let mut comment = String::new();
+                    while let Some(c) = self.next_spliced() {
+                        if c == '*' && self.peek_spliced() == Some('/') {
+                            self.next_spliced();
+                            break;
+                        }
+                        comment.push(c);
+                    }
+                    current.text.push(' ');
+                    current.comment = Some(comment.trim().to_string());
+                }
+                '"' if RAW_STRING_PREFIXES.contains(&token.as_str()) => {
+                    current.text.push(c);
+                    self.read_raw_string(&mut current.text);
+                }
+                '"' | '\'' if !(c == '\'' && Self::is_number(&token)) => {
+                    let (line, column) = (self.line, self.column - 1);
+                    self.mark_token(&mut current, line, column);
+                    current.text.push(c);
+                    self.read_literal(c, &mut current.text);
+                }
+                c if c.is_whitespace() => {
+                    current.text.push(c);
+                }
+                c => {
+                    let (line, column) = (self.line, self.column - 1);
+                    self.mark_token(&mut current, line, column);
+                    current.text.push(c);
+                }
+            }
+
+            if c.is_ascii_alphanumeric()
+                || c == '_'
+                || (c == '.' || c == '\'') && Self::is_number(&token)
+            {
+                token.push(c);
+            } else {
+                token.clear();
+            }
+        }
+
+        if !current.text.is_empty() || current.comment.is_some() {
+            lines.push(current);
+        }
+
+        lines
+    }
+
+    /**
+     * Records the location of the first token of the line, where the last read character is.
+     */
+    fn mark_token(&self, current: &mut LogicalLine, line: usize, column: usize) {
+        if current.is_blank() {
+            current.line = line;
+            current.column = column;
+        }
+    }
+
+    fn is_number(token: &str) -> bool {
+        token.starts_with(|c: char| c.is_ascii_digit())
+    }
+
+    /**
+     * Reads a string or character literal up to its closing quote, or up to the end of the line
+     * when it is not closed.
+     */
+    fn read_literal(&mut self, quote: char, text: &mut String) {
+        while let Some(c) = self.peek_spliced() {
+            if c == '\n' {
+                return;
+            }
+
+            self.next_spliced();
+            text.push(c);
+
+            if c == '\\' {
+                if let Some(escaped) = self.peek_spliced().filter(|&e| e != '\n') {
+                    self.next_spliced();
+                    text.push(escaped);
+                }
+            } else if c == quote {
+                return;
+            }
+        }
+    }
+
+    /**
+     * Reads a raw string literal, from its delimiter up to its closing quote. Continuations are
+     * not removed inside raw strings, and newlines do not end the logical line.
+     */
+    fn read_raw_string(&mut self, text: &mut String) {
+        let mut delimiter = String::new();
+        while let Some(c) = self.next() {
+            text.push(c);
+            if c == '(' {
+                break;
+            }
+            delimiter.push(c);
+        }
+
+        let closing: Vec<char> = format!("){}\"", delimiter).chars().collect();
+        let mut matched = 0;
+        while let Some(c) = self.next() {
+            text.push(if c == '\n' { ' ' } else { c });
+
+            matched = match (c == closing[matched], c == closing[0]) {
+                (true, _) => matched + 1,
+                (false, true) => 1,
+                (false, false) => 0,
+            };
+            if matched == closing.len() {
+                return;
+            }
+        }
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = *self.chars.get(self.position)?;
+        self.position += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+
+        Some(c)
+    }
+
+    /**
+     * Skips the backslash-newline continuations at the current position.
+     */
+    fn skip_continuations(&mut self) {
+        while self.chars.get(self.position) == Some(&'\\')
+            && self.chars.get(self.position + 1) == Some(&'\n')
+        {
+            self.next();
+            self.next();
+        }
+    }
+
+    fn next_spliced(&mut self) -> Option<char> {
+        self.skip_continuations();
+        self.next()
+    }
+
+    fn peek_spliced(&mut self) -> Option<char> {
+        self.skip_continuations();
+        self.chars.get(self.position).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(content: &str) -> Vec<String> {
+        split_logical_lines(content)
+            .iter()
+            .map(|l| l.get_text().trim().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn comments_test() {
+        assert_eq!(
+            vec!["int a;", "", "int b;", "int   c;"],
+            texts("int a; // first\n/* block\n#include \"a.h\"\n*/\nint b;\nint /* inline */ c;")
+        );
+
+        let lines = split_logical_lines("/* first\n second */  #include <a.h> // trailing\n");
+        assert_eq!(1, lines.len());
+        assert_eq!((2, 13), (lines[0].get_line(), lines[0].get_column()));
+        assert_eq!(Some(("include", "<a.h>")), lines[0].get_directive());
+        assert_eq!(Some("trailing"), lines[0].get_comment());
+    }
+
+    #[test]
+    fn continuations_test() {
+        let lines = split_logical_lines(
+            "#inc\\\nlude \\\n  \"a.h\"\nint a; // comment \\\n#include \"b.h\"\n",
+        );
+        assert_eq!(2, lines.len());
+        assert_eq!(Some(("include", "\"a.h\"")), lines[0].get_directive());
+        assert_eq!(None, lines[1].get_directive());
+        assert_eq!(Some("comment #include \"b.h\""), lines[1].get_comment());
+
+        assert_eq!(vec!["ab", "c"], texts("a\\\r\nb\r\nc\r\n"));
+    }
+
+    #[test]
+    fn literals_test() {
+        assert_eq!(
+            vec![
+                "const char* a = \"/* not a comment\";",
+                "char b = '\"';",
+                "int c = 1'000;",
+                "auto d = \"\\\" // still a string\";",
+            ],
+            texts(
+                "const char* a = \"/* not a comment\";\nchar b = '\"';\nint c = 1'000; // x\n\
+auto d = \"\\\" // still a string\";"
+            )
+        );
+    }
+
+    #[test]
+    fn raw_strings_test() {
+        let lines = split_logical_lines(
+            "auto a = R\"(\n#include \"a.h\" /* )\";\nauto b = u8R\"x(\n)\"\n)x\";\n#include \"b.h\"\n",
+        );
+
+        assert_eq!(3, lines.len());
+        assert_eq!(None, lines[0].get_directive());
+        assert_eq!(None, lines[1].get_directive());
+        assert_eq!(Some(("include", "\"b.h\"")), lines[2].get_directive());
+        assert_eq!(6, lines[2].get_line());
+    }
+}
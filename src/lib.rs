@@ -1,9 +1,27 @@
+use dependency_analyzer::IncludeChainQuery;
+use include_order::IncludeOrderPolicy;
 use std::error::Error;
+use use_cases::CompileCostUseCase;
+use use_cases::IncludeChainUseCase;
+use use_cases::IncludeCyclesUseCase;
+use use_cases::IncludeGuardsUseCase;
+use use_cases::IncludeLintUseCase;
+use use_cases::IncludeOrderUseCase;
+use use_cases::ScanSettings;
 use use_cases::TopNUseCase;
 
+pub mod compilation_database;
 pub mod dependency_analyzer;
 pub mod file;
+pub mod file_rules;
+pub mod include_graph;
+pub mod include_order;
+pub mod include_resolver;
+pub mod lexer;
+pub mod preprocessor;
+pub mod project_config;
 pub mod project_scanner;
+pub mod source_extensions;
 pub mod use_cases;
 
 #[derive(Debug)]
@@ -11,54 +29,185 @@ pub struct Config<'a> {
     project_path: &'a str,
     mode: ScarMode,
     debug: bool,
+    scan_settings: ScanSettings,
 }
 
 #[derive(Debug)]
 enum ScarMode {
     TopNAnalisys(usize),
     TopNImpactAnalysis(usize),
+    TopNTranslationUnitImpactAnalysis(usize),
+    TopNIncludeWeightAnalysis(usize),
+    CompileCostAnalysis(usize),
+    IncludeCyclesAnalysis(usize),
+    IncludeGuardsAnalysis(usize),
+    IncludeLintAnalysis(usize),
+    IncludeOrderAnalysis(usize, IncludeOrderPolicy, bool),
+    IncludeChainAnalysis(String, String, IncludeChainQuery),
+}
+
+/**
+ * The analysis modes selected from the command line. When more than one is selected, the first
+ * one in declaration order is used.
+ */
+#[derive(Debug, Default)]
+pub struct ModeFlags {
+    pub is_topn: bool,
+    pub is_impact: bool,
+    pub is_tu_impact: bool,
+    pub is_weight: bool,
+    pub is_cost: bool,
+    pub is_cycles: bool,
+    pub is_guards: bool,
+    pub is_lint: bool,
+
+    /**
+     * The include ordering policy to check, if any, and whether to print the fixed include
+     * blocks.
+     */
+    pub order: Option<IncludeOrderPolicy>,
+    pub order_fix: bool,
+
+    /**
+     * The including and included files whose include chains are searched, if any.
+     */
+    pub why: Option<(String, String)>,
+    pub why_query: IncludeChainQuery,
 }
 
 impl<'a> Config<'a> {
     pub fn build(
         path: &'a str,
-        is_topn: bool,
-        is_impact: bool,
+        mode_flags: ModeFlags,
         output_size: usize,
         debug: bool,
+        scan_settings: ScanSettings,
     ) -> Result<Config<'a>, Box<dyn Error>> {
-        if is_topn {
-            return Ok(Config {
-                project_path: path,
-                mode: ScarMode::TopNAnalisys(output_size),
-                debug,
-            });
-        }
-
-        if is_impact {
-            return Ok(Config {
-                project_path: path,
-                mode: ScarMode::TopNImpactAnalysis(output_size),
-                debug,
-            });
-        }
+        let mode = if mode_flags.is_topn {
+            ScarMode::TopNAnalisys(output_size)
+        } else if mode_flags.is_impact {
+            ScarMode::TopNImpactAnalysis(output_size)
+        } else if mode_flags.is_tu_impact {
+            ScarMode::TopNTranslationUnitImpactAnalysis(output_size)
+        } else if mode_flags.is_weight {
+            ScarMode::TopNIncludeWeightAnalysis(output_size)
+        } else if mode_flags.is_cost {
+            ScarMode::CompileCostAnalysis(output_size)
+        } else if mode_flags.is_cycles {
+            ScarMode::IncludeCyclesAnalysis(output_size)
+        } else if mode_flags.is_guards {
+            ScarMode::IncludeGuardsAnalysis(output_size)
+        } else if mode_flags.is_lint {
+            ScarMode::IncludeLintAnalysis(output_size)
+        } else if let Some(policy) = mode_flags.order {
+            ScarMode::IncludeOrderAnalysis(output_size, policy, mode_flags.order_fix)
+        } else if let Some((from, to)) = mode_flags.why {
+            ScarMode::IncludeChainAnalysis(from, to, mode_flags.why_query)
+        } else {
+            return Err("Invalid input mode.".into());
+        };
 
-        Err("Invalid input mode.".into())
+        Ok(Config {
+            project_path: path,
+            mode,
+            debug,
+            scan_settings,
+        })
     }
 }
 
 pub fn run(config: Config) -> Result<(), Box<dyn Error>> {
     match config.mode {
         ScarMode::TopNAnalisys(output_size) => {
-            let use_case_config =
-                use_cases::Config::make(config.project_path, output_size, config.debug);
+            let use_case_config = use_cases::Config::make_with_scan_settings(
+                config.project_path,
+                output_size,
+                config.debug,
+                config.scan_settings,
+            );
             TopNUseCase::do_sorted_topn_inclusions(use_case_config)?;
         }
         ScarMode::TopNImpactAnalysis(output_size) => {
-            let use_case_config =
-                use_cases::Config::make(config.project_path, output_size, config.debug);
+            let use_case_config = use_cases::Config::make_with_scan_settings(
+                config.project_path,
+                output_size,
+                config.debug,
+                config.scan_settings,
+            );
             TopNUseCase::do_sorted_topn_impact(use_case_config)?;
         }
+        ScarMode::TopNTranslationUnitImpactAnalysis(output_size) => {
+            let use_case_config = use_cases::Config::make_with_scan_settings(
+                config.project_path,
+                output_size,
+                config.debug,
+                config.scan_settings,
+            );
+            TopNUseCase::do_sorted_topn_translation_unit_impact(use_case_config)?;
+        }
+        ScarMode::TopNIncludeWeightAnalysis(output_size) => {
+            let use_case_config = use_cases::Config::make_with_scan_settings(
+                config.project_path,
+                output_size,
+                config.debug,
+                config.scan_settings,
+            );
+            TopNUseCase::do_sorted_topn_include_weight(use_case_config)?;
+        }
+        ScarMode::CompileCostAnalysis(output_size) => {
+            let use_case_config = use_cases::Config::make_with_scan_settings(
+                config.project_path,
+                output_size,
+                config.debug,
+                config.scan_settings,
+            );
+            CompileCostUseCase::do_compile_cost(use_case_config)?;
+        }
+        ScarMode::IncludeCyclesAnalysis(output_size) => {
+            let use_case_config = use_cases::Config::make_with_scan_settings(
+                config.project_path,
+                output_size,
+                config.debug,
+                config.scan_settings,
+            );
+            IncludeCyclesUseCase::do_include_cycles(use_case_config)?;
+        }
+        ScarMode::IncludeGuardsAnalysis(output_size) => {
+            let use_case_config = use_cases::Config::make_with_scan_settings(
+                config.project_path,
+                output_size,
+                config.debug,
+                config.scan_settings,
+            );
+            IncludeGuardsUseCase::do_include_guards(use_case_config)?;
+        }
+        ScarMode::IncludeLintAnalysis(output_size) => {
+            let use_case_config = use_cases::Config::make_with_scan_settings(
+                config.project_path,
+                output_size,
+                config.debug,
+                config.scan_settings,
+            );
+            IncludeLintUseCase::do_include_lint(use_case_config)?;
+        }
+        ScarMode::IncludeOrderAnalysis(output_size, policy, fix) => {
+            let use_case_config = use_cases::Config::make_with_scan_settings(
+                config.project_path,
+                output_size,
+                config.debug,
+                config.scan_settings,
+            );
+            IncludeOrderUseCase::do_include_order(use_case_config, &policy, fix)?;
+        }
+        ScarMode::IncludeChainAnalysis(from, to, query) => {
+            let use_case_config = use_cases::Config::make_with_scan_settings(
+                config.project_path,
+                0,
+                config.debug,
+                config.scan_settings,
+            );
+            IncludeChainUseCase::do_include_chains(use_case_config, &from, &to, query)?;
+        }
     }
 
     Ok(())
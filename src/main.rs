@@ -1,5 +1,17 @@
 use clap::Parser;
+use scar::compilation_database::MacroFlag;
+use scar::dependency_analyzer::ConditionalEdges;
+use scar::file_rules::FileRules;
+use scar::include_order::IncludeOrderPolicy;
+use scar::project_config::ProjectConfig;
 use std::error;
+use std::path::{Path, PathBuf};
+
+/**
+ * The max number of results to report, when neither given on the command line nor in the
+ * configuration file.
+ */
+const DEFAULT_OUTPUT_SIZE: usize = 42;
 
 #[derive(Parser)]
 #[command(name = "scar")]
@@ -10,14 +22,108 @@ struct Args {
     #[arg(short = 'i', long = "topnimpact")]
     topn_impact_analyzer: bool,
 
+    #[arg(short = 'u', long = "topntuimpact")]
+    topn_tu_impact_analyzer: bool,
+
+    #[arg(short = 'w', long = "topnweight")]
+    topn_weight_analyzer: bool,
+
+    #[arg(long = "cost")]
+    cost_analyzer: bool,
+
+    #[arg(long = "cycles")]
+    cycles_analyzer: bool,
+
+    #[arg(long = "guards")]
+    guards_analyzer: bool,
+
+    #[arg(long = "lint")]
+    lint_analyzer: bool,
+
+    #[arg(long = "order")]
+    order_analyzer: bool,
+
+    #[arg(
+        long = "order-group",
+        value_name = "NAME=REGEX",
+        requires = "order_analyzer"
+    )]
+    order_groups: Vec<String>,
+
+    #[arg(long = "order-no-sort", requires = "order_analyzer")]
+    order_no_sort: bool,
+
+    #[arg(long = "order-no-blank-lines", requires = "order_analyzer")]
+    order_no_blank_lines: bool,
+
+    #[arg(long = "order-fix", requires = "order_analyzer")]
+    order_fix: bool,
+
+    #[arg(long = "why", num_args = 2, value_names = ["SOURCE", "HEADER"])]
+    why: Option<Vec<String>>,
+
+    #[arg(long = "why-k", requires = "why", conflicts_with = "why_all")]
+    why_k: Option<usize>,
+
+    #[arg(long = "why-all", requires = "why")]
+    why_all: Option<usize>,
+
     #[arg(short = 'p', long = "path")]
-    project_path: String,
+    project_path: Option<String>,
+
+    #[arg(short = 'c', long = "compile-commands")]
+    compile_commands: Option<String>,
 
-    #[arg(short = 'n', long = "num", default_value = "42")]
-    output_size: usize,
+    #[arg(long = "config")]
+    config: Option<String>,
+
+    #[arg(short = 'n', long = "num")]
+    output_size: Option<usize>,
 
     #[arg(short = 'd', long = "debug", default_value = "false")]
     debug: bool,
+
+    #[arg(short = 'j', long = "jobs")]
+    jobs: Option<usize>,
+
+    #[arg(short = 'I', long = "include-dir")]
+    include_dirs: Vec<String>,
+
+    #[arg(long = "iquote")]
+    quote_dirs: Vec<String>,
+
+    #[arg(long = "isystem")]
+    system_dirs: Vec<String>,
+
+    #[arg(long = "strict-includes", default_value = "false")]
+    strict_includes: bool,
+
+    #[arg(long = "include", value_name = "PATTERN")]
+    include_rules: Vec<String>,
+
+    #[arg(long = "exclude", value_name = "PATTERN")]
+    exclude_rules: Vec<String>,
+
+    #[arg(long = "use-ignore-files", default_value = "false")]
+    use_ignore_files: bool,
+
+    #[arg(long = "tu-ext")]
+    translation_unit_extensions: Vec<String>,
+
+    #[arg(long = "header-ext")]
+    header_extensions: Vec<String>,
+
+    #[arg(short = 'D', long = "define")]
+    defines: Vec<String>,
+
+    #[arg(short = 'U', long = "undefine")]
+    undefines: Vec<String>,
+
+    #[arg(long = "eval-conditionals", default_value = "false")]
+    eval_conditionals: bool,
+
+    #[arg(long = "conditional-edges", value_parser = ["all", "live", "always"])]
+    conditional_edges: Option<String>,
 }
 
 fn main() -> Result<(), Box<dyn error::Error>> {
@@ -25,14 +131,150 @@ fn main() -> Result<(), Box<dyn error::Error>> {
 
     let args = Args::parse();
 
+    // scanning and the analyses share the rayon thread pool, with one thread per CPU when 0
+    if let Some(jobs) = args.jobs {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs)
+            .build_global()?;
+    }
+
+    // without an explicit path, the project is the directory containing the compilation database
+    let project_path = match (&args.project_path, &args.compile_commands) {
+        (Some(path), _) => path.clone(),
+        (None, Some(compile_commands)) => match Path::new(compile_commands).parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_string_lossy().to_string(),
+            _ => String::from("."),
+        },
+        (None, None) => return Err("Either --path or --compile-commands is required.".into()),
+    };
+
+    // command line options are applied on top of the configuration file, if any
+    let project_config = match &args.config {
+        Some(config) => ProjectConfig::load(Path::new(config))?,
+        None => ProjectConfig::find(Path::new(&project_path))?.unwrap_or_default(),
+    };
+
+    let include_settings = scar::include_resolver::IncludeSettings::make(
+        [project_config.get_quote_dirs(), &args.quote_dirs].concat(),
+        [project_config.get_include_dirs(), &args.include_dirs].concat(),
+        [project_config.get_system_dirs(), &args.system_dirs].concat(),
+        !(args.strict_includes || project_config.is_strict_includes()),
+    );
+    let translation_unit_extensions = match args.translation_unit_extensions.is_empty() {
+        true => project_config.get_translation_unit_extensions().to_vec(),
+        false => args.translation_unit_extensions,
+    };
+    let header_extensions = match args.header_extensions.is_empty() {
+        true => project_config.get_header_extensions().to_vec(),
+        false => args.header_extensions,
+    };
+    let conditional_edges = match args.conditional_edges.as_deref() {
+        Some("all") => ConditionalEdges::All,
+        Some("live") => ConditionalEdges::Live,
+        Some("always") => ConditionalEdges::Always,
+        _ => project_config.get_conditional_edges().unwrap_or_default(),
+    };
+    let file_rules = FileRules::make(
+        [project_config.get_include_rules(), &args.include_rules].concat(),
+        [project_config.get_exclude_rules(), args.exclude_rules].concat(),
+    )?;
+    let mut scan_settings = scar::use_cases::ScanSettings::make(
+        include_settings,
+        args.compile_commands.map(PathBuf::from),
+    )
+    .with_translation_unit_extensions(translation_unit_extensions)
+    .with_header_extensions(header_extensions)
+    .with_conditional_edges(conditional_edges)
+    .with_file_rules(file_rules);
+    if args.use_ignore_files || project_config.is_use_ignore_files() {
+        scan_settings = scan_settings.with_ignore_files();
+    }
+
+    // giving any macro enables the evaluation of conditional directives
+    let defines = [project_config.get_defines(), &args.defines].concat();
+    let undefines = [project_config.get_undefines(), &args.undefines].concat();
+    if args.eval_conditionals
+        || project_config.is_eval_conditionals()
+        || !defines.is_empty()
+        || !undefines.is_empty()
+    {
+        let macros = defines
+            .iter()
+            .map(|d| MacroFlag::parse_define(d))
+            .chain(undefines.into_iter().map(MacroFlag::Undefine))
+            .collect();
+        scan_settings = scan_settings.with_conditional_evaluation(macros);
+    }
+
+    let order = match args.order_analyzer {
+        true => Some(make_include_order_policy(
+            match args.order_groups.is_empty() {
+                true => project_config.get_order_groups(),
+                false => &args.order_groups,
+            },
+            args.order_no_sort,
+            args.order_no_blank_lines,
+        )?),
+        false => None,
+    };
+
+    let mode_flags = scar::ModeFlags {
+        is_topn: args.topn_analyzer,
+        is_impact: args.topn_impact_analyzer,
+        is_tu_impact: args.topn_tu_impact_analyzer,
+        is_weight: args.topn_weight_analyzer,
+        is_cost: args.cost_analyzer,
+        is_cycles: args.cycles_analyzer,
+        is_guards: args.guards_analyzer,
+        is_lint: args.lint_analyzer,
+        order,
+        order_fix: args.order_fix,
+        why: args.why.map(|files| (files[0].clone(), files[1].clone())),
+        why_query: match (args.why_k, args.why_all) {
+            (Some(k), _) => scar::dependency_analyzer::IncludeChainQuery::KShortest(k),
+            (None, Some(limit)) => scar::dependency_analyzer::IncludeChainQuery::AllSimple(limit),
+            (None, None) => scar::dependency_analyzer::IncludeChainQuery::Shortest,
+        },
+    };
+
     let config = scar::Config::build(
-        &args.project_path,
-        args.topn_analyzer,
-        args.topn_impact_analyzer,
-        args.output_size,
-        args.debug,
+        &project_path,
+        mode_flags,
+        args.output_size
+            .or(project_config.get_output_size())
+            .unwrap_or(DEFAULT_OUTPUT_SIZE),
+        args.debug || project_config.is_debug(),
+        scan_settings,
     )?;
     scar::run(config)?;
 
     Ok(())
 }
+
+/**
+ * Builds the include ordering policy from the "NAME=REGEX" include groups, if any, or the default
+ * ones.
+ */
+fn make_include_order_policy(
+    groups: &[String],
+    no_sort: bool,
+    no_blank_lines: bool,
+) -> Result<IncludeOrderPolicy, Box<dyn error::Error>> {
+    let policy = match groups.is_empty() {
+        true => IncludeOrderPolicy::default(),
+        false => {
+            let groups = groups
+                .iter()
+                .map(|g| {
+                    g.split_once('=')
+                        .ok_or_else(|| format!("Invalid include group {}, expected NAME=REGEX", g))
+                })
+                .collect::<Result<Vec<(&str, &str)>, String>>()?;
+            IncludeOrderPolicy::make(&groups)?
+        }
+    };
+
+    Ok(policy
+        .with_sorting(!no_sort)
+        .with_blank_lines(!no_blank_lines))
+}
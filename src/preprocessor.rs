@@ -0,0 +1,1593 @@
+use crate::compilation_database::MacroFlag;
+use crate::lexer::LogicalLine;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::error::Error;
+
+/**
+ * A macro definition, e.g., "#define NAME value" or "#define NAME(a, b) value".
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Macro {
+    parameters: Option<Vec<String>>,
+    replacement: String,
+}
+
+impl Macro {
+    /**
+     * Returns the parameters of a function-like macro, None for object-like macros.
+     */
+    pub fn get_parameters(&self) -> Option<&[String]> {
+        self.parameters.as_deref()
+    }
+
+    pub fn get_replacement(&self) -> &str {
+        &self.replacement
+    }
+}
+
+/**
+ * The macros defined at some point of the preprocessing.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct MacroTable {
+    macros: HashMap<String, Macro>,
+}
+
+impl MacroTable {
+    /**
+     * Builds the table from command line flags, applied in order (e.g., "-DNAME=1 -UNAME" leaves
+     * NAME undefined). Macros defined without a value are defined as 1, as compilers do.
+     */
+    pub fn make(flags: &[MacroFlag]) -> MacroTable {
+        let mut table = MacroTable::default();
+        for flag in flags {
+            match flag {
+                MacroFlag::Define { name, value } => {
+                    let value = value.as_deref().unwrap_or("1");
+                    table.define_from_directive(&format!("{} {}", name, value));
+                }
+                MacroFlag::Undefine(name) => table.undefine(name),
+            }
+        }
+
+        table
+    }
+
+    pub fn define(&mut self, name: &str, parameters: Option<Vec<String>>, replacement: &str) {
+        self.macros.insert(
+            name.to_string(),
+            Macro {
+                parameters,
+                replacement: replacement.trim().to_string(),
+            },
+        );
+    }
+
+    /**
+     * Defines a macro from the arguments of a #define directive (e.g., "NAME value" or
+     * "NAME(a, b) value"). Returns the name of the defined macro, if the arguments are valid.
+     */
+    pub fn define_from_directive(&mut self, arguments: &str) -> Option<String> {
+        let arguments = arguments.trim_start();
+        let name_length = identifier_length(arguments);
+        if name_length == 0 {
+            return None;
+        }
+
+        let (name, rest) = arguments.split_at(name_length);
+
+        // function-like macros have the parameters list right after the name, without spaces
+        match rest.strip_prefix('(') {
+            Some(rest) => {
+                let (parameters, replacement) = rest.split_once(')')?;
+                let parameters = parameters
+                    .split(',')
+                    .map(|p| p.trim().to_string())
+                    .filter(|p| !p.is_empty())
+                    .collect();
+                self.define(name, Some(parameters), replacement);
+            }
+            None => self.define(name, None, rest),
+        }
+
+        Some(name.to_string())
+    }
+
+    pub fn undefine(&mut self, name: &str) {
+        self.macros.remove(name);
+    }
+
+    pub fn is_defined(&self, name: &str) -> bool {
+        self.macros.contains_key(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Macro> {
+        self.macros.get(name)
+    }
+
+    /**
+     * Defines all the macros of the given table, replacing the ones with the same name.
+     */
+    pub fn extend(&mut self, other: &MacroTable) {
+        self.macros
+            .extend(other.macros.iter().map(|(n, m)| (n.clone(), m.clone())));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.macros.is_empty()
+    }
+}
+
+/**
+ * A conditional branch enclosing some lines, e.g., "#ifdef WITH_EDITOR" or "#else" of
+ * "#if PLATFORM_WINDOWS".
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Condition {
+    text: String,
+    line: usize,
+    constant_value: Option<bool>,
+}
+
+impl Condition {
+    /**
+     * Returns the directive of the branch (e.g., "#ifdef WITH_EDITOR"). For #elif and #else
+     * branches, the opening directive follows in parentheses (e.g., "#else (#if PLATFORM_LINUX)").
+     */
+    pub fn get_text(&self) -> &str {
+        &self.text
+    }
+
+    /**
+     * Returns the 1-based line of the directive of the branch.
+     */
+    pub fn get_line(&self) -> usize {
+        self.line
+    }
+
+    /**
+     * Returns whether the branch is taken regardless of the defined macros (e.g., "#if 1" or
+     * "#else" of "#if 0"), or never taken (e.g., "#if 0"); None when it depends on macros.
+     */
+    pub fn get_constant_value(&self) -> Option<bool> {
+        self.constant_value
+    }
+}
+
+/**
+ * Whether some lines are compiled, depending on the conditions enclosing them.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Conditionality {
+    /// Compiled regardless of the defined macros.
+    Always,
+    /// Compiled only when some macros are defined (e.g., on some platforms).
+    Conditional,
+    /// Never compiled (e.g., under "#if 0").
+    Dead,
+}
+
+impl Conditionality {
+    /**
+     * Returns the conditionality of the lines enclosed by the given conditions.
+     */
+    pub fn of(conditions: &[Condition]) -> Conditionality {
+        if conditions.iter().any(|c| c.constant_value == Some(false)) {
+            Conditionality::Dead
+        } else if conditions.iter().all(|c| c.constant_value == Some(true)) {
+            Conditionality::Always
+        } else {
+            Conditionality::Conditional
+        }
+    }
+}
+
+/**
+ * A frame of the conditional stack, i.e., an #if/#ifdef/#ifndef group.
+ */
+#[derive(Debug, Clone)]
+struct ConditionalFrame {
+    parent_active: bool,
+    branch_taken: bool,
+    active: bool,
+
+    /**
+     * The opening directive of the group (e.g., "#ifdef WITH_EDITOR").
+     */
+    opening: String,
+
+    /**
+     * The current branch, and the constant values of the previous ones.
+     */
+    condition: Condition,
+    previous_constant_values: Vec<Option<bool>>,
+
+    /**
+     * Whether the group is an include guard, i.e., "#ifndef X" followed by "#define X" at the
+     * beginning of the file.
+     */
+    is_include_guard: bool,
+}
+
+/**
+ * The state of the conditional directives (#if, #ifdef, #ifndef, #elif, #else, #endif) while
+ * going through a file, telling whether the current lines are compiled and under which
+ * conditions.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct ConditionalStack {
+    frames: Vec<ConditionalFrame>,
+    num_directives: usize,
+
+    /**
+     * The macro of the "#ifndef" which opened the file, expected to be defined right after it
+     * when it is an include guard.
+     */
+    guard_candidate: Option<String>,
+}
+
+impl ConditionalStack {
+    pub fn make() -> ConditionalStack {
+        ConditionalStack::default()
+    }
+
+    /**
+     * Returns whether the current lines are compiled, i.e., all the enclosing conditions hold.
+     */
+    pub fn is_active(&self) -> bool {
+        self.frames.last().is_none_or(|f| f.active)
+    }
+
+    /**
+     * Returns whether the lines enclosing the current conditional group are compiled, i.e.,
+     * whether the conditions of its branches are evaluated at all.
+     */
+    pub fn is_enclosing_active(&self) -> bool {
+        self.frames.last().is_none_or(|f| f.parent_active)
+    }
+
+    /**
+     * Returns the conditions enclosing the current lines, from the outermost one, include guards
+     * excluded.
+     */
+    pub fn get_conditions(&self) -> Vec<Condition> {
+        self.frames
+            .iter()
+            .filter(|f| !f.is_include_guard)
+            .map(|f| f.condition.clone())
+            .collect()
+    }
+
+    /**
+     * Updates the state with the given directive. Conditions which cannot be evaluated are
+     * considered false, while the ones depending on include probes are unknown: their branch is
+     * compiled, and the following branches of the group are evaluated as if it was not taken.
+     * Returns whether the directive is a conditional directive.
+     *
+     * - name: the directive name (e.g., "ifdef")
+     * - arguments: the directive arguments (e.g., "WITH_EDITOR")
+     * - line: the 1-based line of the directive
+     * - macros: the macros defined at the directive
+     */
+    pub fn process(
+        &mut self,
+        name: &str,
+        arguments: &str,
+        line: usize,
+        macros: &MacroTable,
+    ) -> bool {
+        let is_first_directive = self.num_directives == 0;
+        self.num_directives += 1;
+
+        let guard_candidate = self.guard_candidate.take();
+        if name == "define" {
+            if let (Some(guard), [frame]) = (guard_candidate, self.frames.as_mut_slice()) {
+                frame.is_include_guard = first_identifier(arguments) == guard;
+            }
+            return false;
+        }
+
+        let condition = || match name {
+            "ifdef" | "elifdef" => Some(macros.is_defined(first_identifier(arguments))),
+            "ifndef" | "elifndef" => Some(!macros.is_defined(first_identifier(arguments))),
+            "else" => Some(true),
+            _ => evaluate_condition(arguments, macros).unwrap_or(Some(false)),
+        };
+        let text = format!("#{} {}", name, arguments).trim_end().to_string();
+
+        match name {
+            "if" | "ifdef" | "ifndef" => {
+                if is_first_directive {
+                    self.guard_candidate = guarded_macro(name, arguments).map(String::from);
+                }
+
+                let parent_active = self.is_active();
+                let condition = parent_active.then(condition).flatten();
+                let active = parent_active && condition != Some(false);
+                self.frames.push(ConditionalFrame {
+                    parent_active,
+                    branch_taken: condition == Some(true),
+                    active,
+                    condition: Condition {
+                        text: text.clone(),
+                        line,
+                        constant_value: Self::constant_value(name, arguments, &[]),
+                    },
+                    opening: text,
+                    previous_constant_values: Vec::new(),
+                    is_include_guard: false,
+                });
+            }
+            "elif" | "elifdef" | "elifndef" | "else" => {
+                if let Some(frame) = self.frames.last_mut() {
+                    let evaluated = frame.parent_active && !frame.branch_taken;
+                    let condition = evaluated.then(condition).flatten();
+                    frame.active = evaluated && condition != Some(false);
+                    frame.branch_taken |= condition == Some(true);
+
+                    frame
+                        .previous_constant_values
+                        .push(frame.condition.constant_value);
+                    frame.condition = Condition {
+                        text: format!("{} ({})", text, frame.opening),
+                        line,
+                        constant_value: Self::constant_value(
+                            name,
+                            arguments,
+                            &frame.previous_constant_values,
+                        ),
+                    };
+                    frame.is_include_guard = false;
+                }
+            }
+            "endif" => {
+                self.frames.pop();
+            }
+            _ => return false,
+        }
+
+        true
+    }
+
+    /**
+     * Returns the value of a branch condition when it does not depend on macros (e.g., "#if 0"),
+     * given the constant values of the previous branches of the same group.
+     */
+    fn constant_value(
+        name: &str,
+        arguments: &str,
+        previous_constant_values: &[Option<bool>],
+    ) -> Option<bool> {
+        // a branch following a taken one is never taken
+        if previous_constant_values.contains(&Some(true)) {
+            return Some(false);
+        }
+
+        let value = match name {
+            "else" => Some(true),
+            "if" | "elif" => {
+                let has_identifiers = tokenize(arguments)
+                    .map(|tokens| tokens.iter().any(|t| matches!(t, Token::Identifier(_))))
+                    .unwrap_or(true);
+                match has_identifiers {
+                    true => None,
+                    false => evaluate_condition(arguments, &MacroTable::default())
+                        .ok()
+                        .flatten(),
+                }
+            }
+            _ => None,
+        };
+
+        match value {
+            // taken only when no previous branch could be
+            Some(true) if previous_constant_values.iter().all(|&v| v == Some(false)) => Some(true),
+            Some(true) => None,
+            value => value,
+        }
+    }
+}
+
+/**
+ * How a header protects itself from being included more than once: "#pragma once", an include
+ * guard macro (i.e., "#ifndef X" followed by "#define X"), both or none.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IncludeGuard {
+    pragma_once_line: Option<usize>,
+    guard_macro: Option<String>,
+    guard_line: usize,
+    wraps_file: bool,
+}
+
+impl IncludeGuard {
+    /**
+     * Finds the include guard of the logical lines of a file. The guard macro is looked for in
+     * the first conditional group of the file, which wraps the file when nothing but blank lines
+     * and "#pragma once" precedes its opening directive, nothing follows its "#endif", and it has
+     * no "#elif" or "#else" branch.
+     */
+    pub fn find(lines: &[LogicalLine]) -> IncludeGuard {
+        let lines: Vec<&LogicalLine> = lines.iter().filter(|l| !l.is_blank()).collect();
+        let mut guard = IncludeGuard::default();
+
+        let mut depth = 0usize;
+        let mut opening = None;
+        let mut closing = None;
+        let mut has_branches = false;
+
+        for (index, line) in lines.iter().enumerate() {
+            let Some((name, arguments)) = line.get_directive() else {
+                continue;
+            };
+
+            match name {
+                "pragma" if first_identifier(arguments) == "once" => {
+                    guard.pragma_once_line.get_or_insert(line.get_line());
+                }
+                "if" | "ifdef" | "ifndef" => {
+                    if depth == 0 && opening.is_none() {
+                        opening = Some(index);
+
+                        let defined = lines.get(index + 1).and_then(|l| l.get_directive());
+                        if let (Some(guarded), Some(("define", definition))) =
+                            (guarded_macro(name, arguments), defined)
+                        {
+                            if first_identifier(definition) == guarded {
+                                guard.guard_macro = Some(guarded.to_string());
+                                guard.guard_line = line.get_line();
+                            }
+                        }
+                    }
+                    depth += 1;
+                }
+                "elif" | "elifdef" | "elifndef" | "else" => {
+                    has_branches |= depth == 1 && closing.is_none();
+                }
+                "endif" => {
+                    depth = depth.saturating_sub(1);
+                    if depth == 0 && opening.is_some() && closing.is_none() {
+                        closing = Some(index);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if let (Some(opening), Some(closing)) = (opening, closing) {
+            let is_pragma_once = |l: &LogicalLine| {
+                l.get_directive().is_some_and(|(name, arguments)| {
+                    name == "pragma" && first_identifier(arguments) == "once"
+                })
+            };
+            guard.wraps_file = guard.guard_macro.is_some()
+                && !has_branches
+                && closing == lines.len() - 1
+                && lines[..opening].iter().all(|l| is_pragma_once(l));
+        }
+
+        guard
+    }
+
+    /**
+     * Returns whether the file is protected neither by "#pragma once" nor by a guard macro.
+     */
+    pub fn is_missing(&self) -> bool {
+        self.pragma_once_line.is_none() && self.guard_macro.is_none()
+    }
+
+    /**
+     * Returns the 1-based line of the first "#pragma once" of the file, if any.
+     */
+    pub fn get_pragma_once_line(&self) -> Option<usize> {
+        self.pragma_once_line
+    }
+
+    /**
+     * Returns the guard macro of the file (e.g., "FOO_H" for "#ifndef FOO_H"), if any.
+     */
+    pub fn get_guard_macro(&self) -> Option<&str> {
+        self.guard_macro.as_deref()
+    }
+
+    /**
+     * Returns the 1-based line of the directive testing the guard macro, 0 without guard macro.
+     */
+    pub fn get_guard_line(&self) -> usize {
+        self.guard_line
+    }
+
+    /**
+     * Returns whether the guard macro protects the whole file, i.e., whether including the file
+     * again adds nothing.
+     */
+    pub fn wraps_file(&self) -> bool {
+        self.wraps_file
+    }
+}
+
+/**
+ * Returns the macro tested by an include guard directive, i.e., "X" for "#ifndef X" and
+ * "#if !defined(X)", if any.
+ */
+fn guarded_macro<'a>(name: &str, arguments: &'a str) -> Option<&'a str> {
+    let guarded = match name {
+        "ifndef" => arguments,
+        "if" => {
+            let argument = arguments.strip_prefix('!')?.trim_start();
+            let argument = argument.strip_prefix("defined")?.trim();
+            match argument.strip_prefix('(') {
+                Some(argument) => argument.strip_suffix(')')?.trim(),
+                None => argument,
+            }
+        }
+        _ => return None,
+    };
+
+    let identifier = first_identifier(guarded);
+    (!identifier.is_empty() && identifier.len() == guarded.trim().len()).then_some(identifier)
+}
+
+/**
+ * A __has_include or __has_include_next probe of a conditional expression, e.g.,
+ * "__has_include(<optional>)".
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncludeProbe<'e> {
+    text: &'e str,
+    argument: &'e str,
+    next: bool,
+}
+
+impl<'e> IncludeProbe<'e> {
+    /**
+     * Returns the whole probe, e.g., "__has_include(<optional>)".
+     */
+    pub fn get_text(&self) -> &'e str {
+        self.text
+    }
+
+    /**
+     * Returns the trimmed argument of the probe, e.g., "<optional>".
+     */
+    pub fn get_argument(&self) -> &'e str {
+        self.argument
+    }
+
+    /**
+     * Returns whether the probe is __has_include_next.
+     */
+    pub fn is_next(&self) -> bool {
+        self.next
+    }
+}
+
+/**
+ * Returns the __has_include and __has_include_next probes of a conditional expression, in order.
+ */
+pub fn find_include_probes(expression: &str) -> Vec<IncludeProbe<'_>> {
+    let mut probes = Vec::new();
+    let mut offset = 0;
+
+    while let Some(position) = expression[offset..].find("__has_include") {
+        let start = offset + position;
+        let name_length = identifier_length(&expression[start..]);
+        offset = start + name_length;
+
+        let name = &expression[start..offset];
+        let is_identifier_start =
+            !expression[..start].ends_with(|c: char| c.is_ascii_alphanumeric() || c == '_');
+        if !is_identifier_start || (name != "__has_include" && name != "__has_include_next") {
+            continue;
+        }
+
+        let Some(rest) = expression[offset..].trim_start().strip_prefix('(') else {
+            continue;
+        };
+
+        let Some(closing) = find_probe_closing(rest) else {
+            continue;
+        };
+
+        let rest_start = expression.len() - rest.len();
+        offset = rest_start + closing + 1;
+        probes.push(IncludeProbe {
+            text: &expression[start..offset],
+            argument: rest[..closing].trim(),
+            next: name == "__has_include_next",
+        });
+    }
+
+    probes
+}
+
+/**
+ * Returns the position of the parenthesis closing the argument of an include probe, skipping
+ * the file name between its delimiters, which may contain parentheses.
+ */
+fn find_probe_closing(argument: &str) -> Option<usize> {
+    let trimmed = argument.trim_start();
+    let name_start = argument.len() - trimmed.len();
+    let name_length = match trimmed.chars().next()? {
+        '<' => trimmed.find('>')?,
+        '"' => 1 + trimmed[1..].find('"')?,
+        _ => 0,
+    };
+
+    let name_end = name_start + name_length;
+    Some(name_end + argument[name_end..].find(')')?)
+}
+
+/**
+ * The max number of include probes of a conditional expression whose outcomes are all tried.
+ */
+const MAX_EVALUATED_PROBES: usize = 8;
+
+/**
+ * Evaluates the expression of an #if or #elif directive, e.g., "defined(WITH_EDITOR) && VERSION >
+ * 2". Macros are expanded, and identifiers which are not macros evaluate to 0 (true and false
+ * evaluate to 1 and 0). Arithmetic is performed on signed 64-bit integers.
+ * Since files are not looked for while parsing, include probes (e.g.,
+ * "__has_include(<optional>)") may evaluate to either 0 or 1: the result is None when it depends
+ * on them (e.g., "__has_include(<x>)"), and the value otherwise (e.g., "__has_include(<x>) || 1").
+ */
+pub fn evaluate_condition(
+    expression: &str,
+    macros: &MacroTable,
+) -> Result<Option<bool>, Box<dyn Error>> {
+    let probes = find_include_probes(expression);
+    if probes.len() > MAX_EVALUATED_PROBES {
+        return Ok(None);
+    }
+
+    let mut values = HashSet::new();
+    for found in 0..1u32 << probes.len() {
+        let expression =
+            probes
+                .iter()
+                .enumerate()
+                .fold(expression.to_string(), |e, (index, probe)| {
+                    let value = if found & (1 << index) != 0 { "1" } else { "0" };
+                    e.replacen(probe.get_text(), value, 1)
+                });
+        values.insert(evaluate_expression(&expression, macros)?);
+    }
+
+    Ok(match values.len() {
+        1 => values.into_iter().next(),
+        _ => None,
+    })
+}
+
+/**
+ * Evaluates a conditional expression without include probes.
+ */
+fn evaluate_expression(expression: &str, macros: &MacroTable) -> Result<bool, Box<dyn Error>> {
+    let tokens = tokenize(expression)?;
+    let tokens = expand(&tokens, macros, &mut HashSet::new())?;
+
+    let mut parser = ExpressionParser {
+        tokens: &tokens,
+        position: 0,
+    };
+    let value = parser.parse_conditional()?;
+    if parser.position != tokens.len() {
+        return Err(format!("Unexpected token in expression: {}", expression).into());
+    }
+
+    Ok(value != 0)
+}
+
+fn identifier_length(text: &str) -> usize {
+    text.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(text.len())
+}
+
+fn first_identifier(text: &str) -> &str {
+    let text = text.trim_start();
+    &text[..identifier_length(text)]
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Number(i64),
+    Identifier(String),
+    Operator(&'static str),
+}
+
+/**
+ * Operators of conditional expressions, longest first so that they are matched greedily.
+ */
+const OPERATORS: [&str; 24] = [
+    "<<", ">>", "<=", ">=", "==", "!=", "&&", "||", "(", ")", "+", "-", "*", "/", "%", "<", ">",
+    "&", "^", "|", "!", "~", "?", ":",
+];
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, Box<dyn Error>> {
+    let mut tokens = Vec::new();
+    let mut rest = expression.trim_start();
+
+    while let Some(c) = rest.chars().next() {
+        if c.is_ascii_digit() {
+            let length = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '\'' || c == '.'))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Number(parse_number(&rest[..length])?));
+            rest = &rest[length..];
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let length = identifier_length(rest);
+            tokens.push(Token::Identifier(rest[..length].to_string()));
+            rest = &rest[length..];
+        } else if c == '\'' {
+            let (value, length) = parse_character(rest)?;
+            tokens.push(Token::Number(value));
+            rest = &rest[length..];
+        } else if let Some(operator) = OPERATORS.iter().find(|o| rest.starts_with(*o)) {
+            tokens.push(Token::Operator(operator));
+            rest = &rest[operator.len()..];
+        } else {
+            return Err(format!("Invalid character in expression: {}", expression).into());
+        }
+
+        rest = rest.trim_start();
+    }
+
+    Ok(tokens)
+}
+
+/**
+ * Parses an integer literal, e.g., "42", "0x2A", "052", "0b101010", "42UL" or "1'000".
+ */
+fn parse_number(literal: &str) -> Result<i64, Box<dyn Error>> {
+    let digits: String = literal.chars().filter(|&c| c != '\'').collect();
+    let digits = digits.trim_end_matches(['u', 'U', 'l', 'L', 'z', 'Z']);
+
+    let (digits, radix) = if let Some(hex) = digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        (hex, 16)
+    } else if let Some(binary) = digits
+        .strip_prefix("0b")
+        .or_else(|| digits.strip_prefix("0B"))
+    {
+        (binary, 2)
+    } else if digits.len() > 1 && digits.starts_with('0') {
+        (&digits[1..], 8)
+    } else {
+        (digits, 10)
+    };
+
+    u64::from_str_radix(digits, radix)
+        .map(|value| value as i64)
+        .map_err(|_| format!("Invalid number in expression: {}", literal).into())
+}
+
+/**
+ * Parses a character literal at the start of the text, e.g., 'a' or '\n', returning its value
+ * and its length.
+ */
+fn parse_character(text: &str) -> Result<(i64, usize), Box<dyn Error>> {
+    let mut chars = text.char_indices().skip(1);
+    let value = match chars.next() {
+        Some((_, '\\')) => match chars.next() {
+            Some((_, 'n')) => '\n',
+            Some((_, 't')) => '\t',
+            Some((_, 'r')) => '\r',
+            Some((_, '0')) => '\0',
+            Some((_, c)) => c,
+            None => return Err("Unterminated character literal".into()),
+        },
+        Some((_, c)) => c,
+        None => return Err("Unterminated character literal".into()),
+    };
+
+    match chars.next() {
+        Some((index, '\'')) => Ok((value as i64, index + 1)),
+        _ => Err(format!("Invalid character literal: {}", text).into()),
+    }
+}
+
+/**
+ * Replaces defined() expressions and macros with their values. Identifiers which are not
+ * macros, and invocations of unknown function-like macros, evaluate to 0.
+ *
+ * - expanding: the macros being expanded, which are not expanded again
+ */
+fn expand(
+    tokens: &[Token],
+    macros: &MacroTable,
+    expanding: &mut HashSet<String>,
+) -> Result<Vec<Token>, Box<dyn Error>> {
+    let mut expanded = Vec::new();
+    let mut position = 0;
+
+    while position < tokens.len() {
+        let Token::Identifier(name) = &tokens[position] else {
+            expanded.push(tokens[position].clone());
+            position += 1;
+            continue;
+        };
+        position += 1;
+
+        match name.as_str() {
+            "defined" => {
+                let parenthesized = tokens.get(position) == Some(&Token::Operator("("));
+                let name_position = position + parenthesized as usize;
+                let Some(Token::Identifier(operand)) = tokens.get(name_position) else {
+                    return Err("Invalid defined() expression".into());
+                };
+                if parenthesized && tokens.get(name_position + 1) != Some(&Token::Operator(")")) {
+                    return Err("Invalid defined() expression".into());
+                }
+
+                expanded.push(Token::Number(macros.is_defined(operand) as i64));
+                position = name_position + 1 + parenthesized as usize;
+            }
+            "true" => expanded.push(Token::Number(1)),
+            "false" => expanded.push(Token::Number(0)),
+            _ => match macros.get(name) {
+                Some(m) if m.get_parameters().is_none() && !expanding.contains(name) => {
+                    expanding.insert(name.clone());
+                    let replacement = tokenize(m.get_replacement())?;
+                    expanded.extend(expand(&replacement, macros, expanding)?);
+                    expanding.remove(name);
+                }
+                _ => {
+                    // the arguments of function-like invocations are skipped as well
+                    if tokens.get(position) == Some(&Token::Operator("(")) {
+                        position = skip_parenthesized(tokens, position)?;
+                    }
+                    expanded.push(Token::Number(0));
+                }
+            },
+        }
+    }
+
+    Ok(expanded)
+}
+
+/**
+ * Returns the position following the parenthesized group starting at the given position.
+ */
+fn skip_parenthesized(tokens: &[Token], start: usize) -> Result<usize, Box<dyn Error>> {
+    let mut depth = 0;
+    for (position, token) in tokens.iter().enumerate().skip(start) {
+        match token {
+            Token::Operator("(") => depth += 1,
+            Token::Operator(")") => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(position + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Err("Unbalanced parentheses in expression".into())
+}
+
+/**
+ * Recursive descent parser of conditional expressions, following the C++ operators precedence.
+ */
+struct ExpressionParser<'t> {
+    tokens: &'t [Token],
+    position: usize,
+}
+
+impl ExpressionParser<'_> {
+    fn peek_operator(&self) -> Option<&'static str> {
+        match self.tokens.get(self.position) {
+            Some(Token::Operator(operator)) => Some(operator),
+            _ => None,
+        }
+    }
+
+    fn expect(&mut self, operator: &str) -> Result<(), Box<dyn Error>> {
+        if self.peek_operator() != Some(operator) {
+            return Err(format!("Expected {} in expression", operator).into());
+        }
+        self.position += 1;
+        Ok(())
+    }
+
+    fn parse_conditional(&mut self) -> Result<i64, Box<dyn Error>> {
+        let condition = self.parse_binary(0)?;
+        if self.peek_operator() != Some("?") {
+            return Ok(condition);
+        }
+
+        self.position += 1;
+        let when_true = self.parse_conditional()?;
+        self.expect(":")?;
+        let when_false = self.parse_conditional()?;
+
+        Ok(if condition != 0 {
+            when_true
+        } else {
+            when_false
+        })
+    }
+
+    /**
+     * Parses binary operators whose precedence is at least the given one, by precedence
+     * climbing.
+     */
+    fn parse_binary(&mut self, min_precedence: u8) -> Result<i64, Box<dyn Error>> {
+        let mut left = self.parse_unary()?;
+
+        while let Some(operator) = self.peek_operator() {
+            let Some(precedence) = Self::precedence(operator) else {
+                break;
+            };
+            if precedence < min_precedence {
+                break;
+            }
+
+            self.position += 1;
+            let right = self.parse_binary(precedence + 1)?;
+            left = Self::apply(operator, left, right)?;
+        }
+
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<i64, Box<dyn Error>> {
+        match self.tokens.get(self.position) {
+            Some(Token::Number(value)) => {
+                self.position += 1;
+                Ok(*value)
+            }
+            Some(Token::Operator("(")) => {
+                self.position += 1;
+                let value = self.parse_conditional()?;
+                self.expect(")")?;
+                Ok(value)
+            }
+            Some(Token::Operator(operator @ ("+" | "-" | "!" | "~"))) => {
+                self.position += 1;
+                let value = self.parse_unary()?;
+                Ok(match *operator {
+                    "+" => value,
+                    "-" => value.wrapping_neg(),
+                    "!" => (value == 0) as i64,
+                    _ => !value,
+                })
+            }
+            _ => Err("Expected a value in expression".into()),
+        }
+    }
+
+    fn precedence(operator: &str) -> Option<u8> {
+        let precedence = match operator {
+            "||" => 1,
+            "&&" => 2,
+            "|" => 3,
+            "^" => 4,
+            "&" => 5,
+            "==" | "!=" => 6,
+            "<" | ">" | "<=" | ">=" => 7,
+            "<<" | ">>" => 8,
+            "+" | "-" => 9,
+            "*" | "/" | "%" => 10,
+            _ => return None,
+        };
+
+        Some(precedence)
+    }
+
+    fn apply(operator: &str, left: i64, right: i64) -> Result<i64, Box<dyn Error>> {
+        let value = match operator {
+            "||" => (left != 0 || right != 0) as i64,
+            "&&" => (left != 0 && right != 0) as i64,
+            "|" => left | right,
+            "^" => left ^ right,
+            "&" => left & right,
+            "==" => (left == right) as i64,
+            "!=" => (left != right) as i64,
+            "<" => (left < right) as i64,
+            ">" => (left > right) as i64,
+            "<=" => (left <= right) as i64,
+            ">=" => (left >= right) as i64,
+            "<<" => left.wrapping_shl(right as u32),
+            ">>" => left.wrapping_shr(right as u32),
+            "+" => left.wrapping_add(right),
+            "-" => left.wrapping_sub(right),
+            "*" => left.wrapping_mul(right),
+            "/" | "%" if right == 0 => return Err("Division by zero in expression".into()),
+            "/" => left.wrapping_div(right),
+            "%" => left.wrapping_rem(right),
+            _ => return Err(format!("Unknown operator: {}", operator).into()),
+        };
+
+        Ok(value)
+    }
+}
+
+/**
+ * A preprocessing token of a macro expansion, e.g., an identifier, a string literal or a
+ * punctuator.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PreprocessingToken {
+    text: String,
+
+    /**
+     * Whether whitespace precedes the token, which is kept when stringifying and when joining
+     * the expanded tokens.
+     */
+    space_before: bool,
+}
+
+fn lex_preprocessing_tokens(text: &str) -> Vec<PreprocessingToken> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut space_before = false;
+    let mut position = 0;
+
+    while position < chars.len() {
+        let c = chars[position];
+        let start = position;
+        position += 1;
+
+        if c.is_whitespace() {
+            space_before = true;
+            continue;
+        }
+
+        let is_number_start =
+            c.is_ascii_digit() || c == '.' && chars.get(position).is_some_and(char::is_ascii_digit);
+        if c.is_ascii_alphabetic() || c == '_' {
+            while chars
+                .get(position)
+                .is_some_and(|c| c.is_ascii_alphanumeric() || *c == '_')
+            {
+                position += 1;
+            }
+        } else if is_number_start {
+            while let Some(&n) = chars.get(position) {
+                let is_exponent_sign =
+                    (n == '+' || n == '-') && matches!(chars[position - 1], 'e' | 'E' | 'p' | 'P');
+                if !(n.is_ascii_alphanumeric() || n == '_' || n == '.' || is_exponent_sign) {
+                    break;
+                }
+                position += 1;
+            }
+        } else if c == '"' || c == '\'' {
+            while let Some(&n) = chars.get(position) {
+                position += 1;
+                if n == '\\' {
+                    position += 1;
+                } else if n == c {
+                    break;
+                }
+            }
+            position = position.min(chars.len());
+        } else if c == '#' && chars.get(position) == Some(&'#') {
+            position += 1;
+        }
+
+        tokens.push(PreprocessingToken {
+            text: chars[start..position].iter().collect(),
+            space_before,
+        });
+        space_before = false;
+    }
+
+    tokens
+}
+
+fn join_preprocessing_tokens(tokens: &[PreprocessingToken]) -> String {
+    let mut text = String::new();
+    for (index, token) in tokens.iter().enumerate() {
+        if index > 0 && token.space_before {
+            text.push(' ');
+        }
+        text.push_str(&token.text);
+    }
+
+    text
+}
+
+/**
+ * Expands the macros of the given text (e.g., the arguments of a computed include such as
+ * "#include PLATFORM_HEADER(Foo.h)"), including function-like macros, stringification (#) and
+ * token pasting (##). The expanded tokens are separated by a space where whitespace preceded
+ * them.
+ */
+pub fn expand_macros(text: &str, macros: &MacroTable) -> String {
+    let expander = MacroExpander { macros };
+    let tokens = expander.expand(&lex_preprocessing_tokens(text), &HashSet::new());
+
+    join_preprocessing_tokens(&tokens)
+}
+
+struct MacroExpander<'m> {
+    macros: &'m MacroTable,
+}
+
+impl MacroExpander<'_> {
+    /**
+     * Expands the macros of the tokens, except the disabled ones, i.e., the macros being
+     * expanded, which are not expanded again in their own replacement.
+     */
+    fn expand(
+        &self,
+        tokens: &[PreprocessingToken],
+        disabled: &HashSet<String>,
+    ) -> Vec<PreprocessingToken> {
+        let mut output = Vec::new();
+        let mut position = 0;
+
+        while position < tokens.len() {
+            let token = &tokens[position];
+            position += 1;
+
+            let definition = match self.macros.get(&token.text) {
+                Some(definition) if !disabled.contains(&token.text) => definition,
+                _ => {
+                    output.push(token.clone());
+                    continue;
+                }
+            };
+
+            let arguments = match definition.get_parameters() {
+                None => Vec::new(),
+                Some(_) => match Self::collect_arguments(tokens, position) {
+                    Some((arguments, end)) => {
+                        position = end;
+                        arguments
+                    }
+                    // a function-like macro name not followed by its arguments is not expanded
+                    None => {
+                        output.push(token.clone());
+                        continue;
+                    }
+                },
+            };
+
+            let mut disabled = disabled.clone();
+            disabled.insert(token.text.clone());
+            let replacement = self.substitute(definition, arguments, &disabled);
+
+            let mut expanded = self.expand(&replacement, &disabled);
+            if let Some(first) = expanded.first_mut() {
+                first.space_before = token.space_before;
+            }
+            output.extend(expanded);
+        }
+
+        output
+    }
+
+    /**
+     * Collects the arguments of a function-like macro invocation, starting from the opening
+     * parenthesis. Returns the arguments and the position following the closing parenthesis.
+     */
+    fn collect_arguments(
+        tokens: &[PreprocessingToken],
+        start: usize,
+    ) -> Option<(Vec<Vec<PreprocessingToken>>, usize)> {
+        if tokens.get(start)?.text != "(" {
+            return None;
+        }
+
+        let mut arguments = vec![Vec::new()];
+        let mut depth = 0;
+        for (position, token) in tokens.iter().enumerate().skip(start + 1) {
+            match token.text.as_str() {
+                ")" if depth == 0 => return Some((arguments, position + 1)),
+                "," if depth == 0 => arguments.push(Vec::new()),
+                text => {
+                    match text {
+                        "(" => depth += 1,
+                        ")" => depth -= 1,
+                        _ => {}
+                    }
+                    arguments.last_mut()?.push(token.clone());
+                }
+            }
+        }
+
+        None
+    }
+
+    /**
+     * Returns the replacement of the macro with its parameters replaced by the arguments:
+     * stringified after '#', as written around '##', and expanded otherwise. Tokens around '##'
+     * are pasted together.
+     */
+    fn substitute(
+        &self,
+        definition: &Macro,
+        mut arguments: Vec<Vec<PreprocessingToken>>,
+        disabled: &HashSet<String>,
+    ) -> Vec<PreprocessingToken> {
+        let parameters = definition.get_parameters().unwrap_or_default();
+
+        // variadic arguments are all the trailing ones, commas included
+        if parameters.last().is_some_and(|p| p == "...") && arguments.len() > parameters.len() {
+            let variadic = arguments.split_off(parameters.len() - 1);
+            let comma = PreprocessingToken {
+                text: String::from(","),
+                space_before: false,
+            };
+            arguments.push(variadic.join(&comma));
+        }
+
+        let argument_of = |token: &PreprocessingToken| {
+            parameters
+                .iter()
+                .position(|p| *p == token.text || p == "..." && token.text == "__VA_ARGS__")
+                .map(|index| arguments.get(index).map_or(&[][..], |a| a.as_slice()))
+        };
+
+        let replacement = lex_preprocessing_tokens(definition.get_replacement());
+        let mut output: Vec<PreprocessingToken> = Vec::new();
+        let mut paste = false;
+        let mut position = 0;
+
+        while position < replacement.len() {
+            let token = &replacement[position];
+            let next = replacement.get(position + 1);
+            position += 1;
+
+            let tokens = match (token.text.as_str(), next.and_then(argument_of)) {
+                ("#", Some(argument)) if definition.get_parameters().is_some() => {
+                    position += 1;
+                    vec![PreprocessingToken {
+                        text: Self::stringify(argument),
+                        space_before: token.space_before,
+                    }]
+                }
+                ("##", _) => {
+                    paste = true;
+                    continue;
+                }
+                _ => match argument_of(token) {
+                    Some(argument) if paste || next.is_some_and(|n| n.text == "##") => {
+                        argument.to_vec()
+                    }
+                    Some(argument) => self.expand(argument, disabled),
+                    None => vec![token.clone()],
+                },
+            };
+
+            let mut tokens = tokens.into_iter();
+            if let Some(first) = tokens.next() {
+                match output.last_mut() {
+                    Some(last) if std::mem::take(&mut paste) => {
+                        let pasted = format!("{}{}", last.text, first.text);
+                        let space_before = last.space_before;
+                        output.pop();
+                        let mut pasted = lex_preprocessing_tokens(&pasted);
+                        if let Some(p) = pasted.first_mut() {
+                            p.space_before = space_before;
+                        }
+                        output.extend(pasted);
+                    }
+                    _ => output.push(PreprocessingToken {
+                        space_before: token.space_before,
+                        ..first
+                    }),
+                }
+            }
+            output.extend(tokens);
+            paste = false;
+        }
+
+        output
+    }
+
+    /**
+     * Returns the string literal of the tokens, e.g., "\"Foo.h\"" for the tokens of Foo.h.
+     */
+    fn stringify(tokens: &[PreprocessingToken]) -> String {
+        let text = join_preprocessing_tokens(tokens);
+        let escaped = text.replace('\\', "\\\\").replace('"', "\\\"");
+
+        format!("\"{}\"", escaped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evaluate(expression: &str, macros: &MacroTable) -> bool {
+        evaluate_condition(expression, macros).unwrap().unwrap()
+    }
+
+    #[test]
+    fn arithmetic_test() {
+        let macros = MacroTable::default();
+
+        assert!(evaluate("1", &macros));
+        assert!(!evaluate("0", &macros));
+        assert!(evaluate("1 + 2 * 3 == 7", &macros));
+        assert!(evaluate("(1 + 2) * 3 == 9", &macros));
+        assert!(evaluate("0x10 == 16 && 010 == 8 && 0b11 == 3", &macros));
+        assert!(evaluate("201703L >= 201402L", &macros));
+        assert!(evaluate("-1 < 0 && !0 && ~0 == -1", &macros));
+        assert!(evaluate(
+            "1 << 4 == 16 && 7 % 4 == 3 && 7 / 2 == 3",
+            &macros
+        ));
+        assert!(evaluate("0 ? 0 : 1 ? 2 : 0", &macros));
+        assert!(evaluate("'A' == 65 && 1'000 == 1000", &macros));
+        assert!(evaluate("true && !false", &macros));
+
+        assert!(evaluate_condition("1 / 0", &macros).is_err());
+        assert!(evaluate_condition("(1", &macros).is_err());
+        assert!(evaluate_condition("1 2", &macros).is_err());
+    }
+
+    #[test]
+    fn macros_test() {
+        let mut macros = MacroTable::make(&[
+            MacroFlag::parse_define("WITH_EDITOR"),
+            MacroFlag::parse_define("PLATFORM_WINDOWS=0"),
+            MacroFlag::parse_define("PLATFORM_LINUX=1"),
+            MacroFlag::parse_define("REMOVED=1"),
+            MacroFlag::Undefine(String::from("REMOVED")),
+        ]);
+        macros.define_from_directive("VERSION (MAJOR * 100 + MINOR)");
+        macros.define_from_directive("MAJOR 5");
+        macros.define_from_directive("MINOR 3");
+        macros.define_from_directive("SELF SELF + 1");
+        macros.define_from_directive("CHECK(x) x");
+
+        assert!(evaluate("WITH_EDITOR", &macros));
+        assert!(evaluate(
+            "defined(WITH_EDITOR) && defined PLATFORM_WINDOWS",
+            &macros
+        ));
+        assert!(!evaluate("PLATFORM_WINDOWS", &macros));
+        assert!(evaluate("PLATFORM_LINUX && !defined(REMOVED)", &macros));
+        assert!(!evaluate("UNKNOWN_MACRO", &macros));
+        assert!(evaluate("VERSION == 503", &macros));
+        assert!(evaluate("SELF == 1", &macros));
+        assert!(evaluate("defined(CHECK) && !CHECK(1)", &macros));
+
+        assert_eq!(
+            Some(&[String::from("x")][..]),
+            macros.get("CHECK").and_then(|m| m.get_parameters())
+        );
+    }
+
+    #[test]
+    fn conditional_stack_test() {
+        let macros = MacroTable::make(&[MacroFlag::parse_define("WITH_EDITOR")]);
+        let mut stack = ConditionalStack::make();
+        let mut process = |name: &str, arguments: &str| {
+            assert!(stack.process(name, arguments, 1, &macros));
+            stack.is_active()
+        };
+
+        assert!(process("ifdef", "WITH_EDITOR"));
+        assert!(!process("if", "0"));
+        assert!(!process("ifndef", "WITH_EDITOR"));
+        assert!(!process("else", ""));
+        assert!(!process("endif", ""));
+        assert!(process("elif", "1"));
+        assert!(!process("elif", "1"));
+        assert!(!process("else", ""));
+        assert!(process("endif", ""));
+        assert!(!process("ifndef", "WITH_EDITOR"));
+        assert!(process("elifdef", "WITH_EDITOR"));
+        assert!(!process("else", ""));
+        assert!(process("endif", ""));
+        assert!(process("endif", ""));
+
+        // branches depending on include probes are kept, together with their fallbacks
+        assert!(process("if", "__has_include(<optional>)"));
+        assert!(process("elif", "__has_include(<experimental/optional>)"));
+        assert!(process("else", ""));
+        assert!(process("endif", ""));
+        assert!(process("if", "__has_include(<optional>) || WITH_EDITOR"));
+        assert!(!process("else", ""));
+        assert!(process("endif", ""));
+
+        assert!(!stack.process("include", "<vector>", 1, &macros));
+        assert!(stack.is_active());
+    }
+
+    /**
+     * Returns the texts and the conditionality of the conditions at each "#include" of the
+     * content.
+     */
+    fn include_conditions(content: &str) -> Vec<(Vec<String>, Conditionality)> {
+        let macros = MacroTable::default();
+        let mut stack = ConditionalStack::make();
+        let mut conditions = Vec::new();
+
+        for (line, row) in content.lines().enumerate() {
+            let (name, arguments) = row
+                .trim_start_matches('#')
+                .split_once(' ')
+                .unwrap_or((row.trim_start_matches('#'), ""));
+
+            if name == "include" {
+                let current = stack.get_conditions();
+                conditions.push((
+                    current.iter().map(|c| c.get_text().to_string()).collect(),
+                    Conditionality::of(&current),
+                ));
+            } else {
+                stack.process(name, arguments, line + 1, &macros);
+            }
+        }
+
+        conditions
+    }
+
+    #[test]
+    fn conditions_test() {
+        let conditions = include_conditions(
+            "\
+#ifndef GUARD_H
+#define GUARD_H
+#include always.h
+#if 0
+#include dead.h
+#elif WITH_EDITOR
+#include editor.h
+#else
+#include other.h
+#endif
+#if 1
+#include always_too.h
+#else
+#include dead_too.h
+#endif
+#ifdef WITH_EDITOR
+#if 0
+#include dead_editor.h
+#endif
+#endif
+#endif",
+        );
+
+        let kinds: Vec<Conditionality> = conditions.iter().map(|c| c.1).collect();
+        assert_eq!(
+            vec![
+                Conditionality::Always,
+                Conditionality::Dead,
+                Conditionality::Conditional,
+                Conditionality::Conditional,
+                Conditionality::Always,
+                Conditionality::Dead,
+                Conditionality::Dead,
+            ],
+            kinds
+        );
+
+        // the include guard is not a condition
+        assert!(conditions[0].0.is_empty());
+        assert_eq!(vec!["#elif WITH_EDITOR (#if 0)"], conditions[2].0);
+        assert_eq!(vec!["#else (#if 0)"], conditions[3].0);
+        assert_eq!(vec!["#ifdef WITH_EDITOR", "#if 0"], conditions[6].0);
+    }
+
+    #[test]
+    fn non_guard_conditions_test() {
+        // an #ifndef not followed by the #define of its macro is not an include guard
+        let conditions = include_conditions("#ifndef NO_VECTOR\n#include vector\n#endif");
+        assert_eq!(vec!["#ifndef NO_VECTOR"], conditions[0].0);
+        assert_eq!(Conditionality::Conditional, conditions[0].1);
+    }
+
+    fn include_guard(content: &str) -> IncludeGuard {
+        IncludeGuard::find(&crate::lexer::split_logical_lines(content))
+    }
+
+    #[test]
+    fn include_guard_test() {
+        let guard = include_guard("// foo.h\n#ifndef FOO_H\n#define FOO_H\nint foo();\n#endif\n");
+        assert_eq!(Some("FOO_H"), guard.get_guard_macro());
+        assert_eq!(2, guard.get_guard_line());
+        assert!(guard.wraps_file());
+        assert!(!guard.is_missing());
+
+        let guard = include_guard("#pragma once\n#if !defined( FOO_H )\n#define FOO_H 1\n#endif");
+        assert_eq!(Some(1), guard.get_pragma_once_line());
+        assert_eq!(Some("FOO_H"), guard.get_guard_macro());
+        assert!(guard.wraps_file());
+
+        let guard = include_guard("#pragma once\nint foo();");
+        assert_eq!(None, guard.get_guard_macro());
+        assert!(!guard.is_missing());
+
+        // only the first conditional group of the file is a guard candidate
+        let guard = include_guard("#ifndef WITH_EDITOR\n#define WITH_EDITOR 0\n#endif\n#ifndef FOO_H\n#define FOO_H\n#endif");
+        assert_eq!(Some("WITH_EDITOR"), guard.get_guard_macro());
+        assert!(!guard.wraps_file());
+
+        let guard = include_guard("#ifdef FOO_H\n#define FOO_H\n#endif");
+        assert!(guard.is_missing());
+    }
+
+    #[test]
+    fn partial_include_guard_test() {
+        let partial = |content: &str| !include_guard(content).wraps_file();
+
+        assert!(partial(
+            "#include <vector>\n#ifndef FOO_H\n#define FOO_H\n#endif"
+        ));
+        assert!(partial("#ifndef FOO_H\n#define FOO_H\n#endif\nint foo();"));
+        assert!(partial(
+            "#ifndef FOO_H\n#define FOO_H\n#else\nint foo();\n#endif"
+        ));
+        assert!(partial("#ifndef FOO_H\n#define FOO_H\nint foo();"));
+        assert!(!partial(
+            "#ifndef FOO_H\n#define FOO_H\n#if 0\n#else\n#endif\n#endif /* FOO_H */"
+        ));
+    }
+
+    #[test]
+    fn include_probes_test() {
+        let probes = find_include_probes(
+            "__has_include(<optional>) && __has_include_next ( \"a(1).h\" ) || \
+             my__has_include(<x>) || __has_include(CONFIG_HEADER)",
+        );
+        let probes: Vec<(&str, &str, bool)> = probes
+            .iter()
+            .map(|p| (p.get_text(), p.get_argument(), p.is_next()))
+            .collect();
+        assert_eq!(
+            vec![
+                ("__has_include(<optional>)", "<optional>", false),
+                ("__has_include_next ( \"a(1).h\" )", "\"a(1).h\"", true),
+                ("__has_include(CONFIG_HEADER)", "CONFIG_HEADER", false),
+            ],
+            probes
+        );
+
+        // conditions depending on whether the files are found are unknown
+        let macros = MacroTable::default();
+        let evaluate = |expression| evaluate_condition(expression, &macros).unwrap();
+        assert_eq!(None, evaluate("__has_include(<optional>) && !defined(X)"));
+        assert_eq!(None, evaluate("!__has_include(\"a.h\")"));
+        assert_eq!(Some(true), evaluate("__has_include(<optional>) || 1"));
+        assert_eq!(
+            Some(false),
+            evaluate("__has_include(<a>) && __has_include(<b>) && X")
+        );
+        assert!(evaluate_condition("__has_include(<a>) +", &macros).is_err());
+    }
+
+    #[test]
+    fn macro_expansion_test() {
+        let mut macros =
+            MacroTable::make(&[MacroFlag::parse_define("PLATFORM_HEADER_NAME=Windows")]);
+        for definition in [
+            "CONFIG_HEADER \"cfg.h\"",
+            "SYSTEM_HEADER <sys/types.h>",
+            "PREPROCESSOR_TO_STRING(x) PREPROCESSOR_TO_STRING_INNER(x)",
+            "PREPROCESSOR_TO_STRING_INNER(x) #x",
+            "PREPROCESSOR_JOIN(x, y) PREPROCESSOR_JOIN_INNER(x, y)",
+            "PREPROCESSOR_JOIN_INNER(x, y) x##y",
+            "COMPILED_PLATFORM_HEADER(Suffix) \
+             PREPROCESSOR_TO_STRING(PREPROCESSOR_JOIN(PLATFORM_HEADER_NAME/PLATFORM_HEADER_NAME, Suffix))",
+            "FIRST(...) FIRST_INNER(__VA_ARGS__)",
+            "FIRST_INNER(a, ...) a",
+            "RECURSIVE RECURSIVE + 1",
+        ] {
+            macros.define_from_directive(definition);
+        }
+
+        assert_eq!("\"cfg.h\"", expand_macros("CONFIG_HEADER", &macros));
+        assert_eq!("<sys/types.h>", expand_macros("SYSTEM_HEADER", &macros));
+        assert_eq!(
+            "\"Windows/WindowsPlatformFoo.h\"",
+            expand_macros("COMPILED_PLATFORM_HEADER(PlatformFoo.h)", &macros)
+        );
+        assert_eq!("\"a.h\"", expand_macros("FIRST(\"a.h\", \"b.h\")", &macros));
+
+        // macros are not expanded in their own replacement, nor without their arguments
+        assert_eq!("RECURSIVE + 1", expand_macros("RECURSIVE", &macros));
+        assert_eq!("FIRST + UNKNOWN", expand_macros("FIRST + UNKNOWN", &macros));
+    }
+}
@@ -0,0 +1,439 @@
+use crate::dependency_analyzer::ConditionalEdges;
+use crate::file_rules::FileRules;
+use crate::include_order::IncludeOrderPolicy;
+use serde::Deserialize;
+use std::error::Error;
+use std::fs::read_to_string;
+use std::path::Path;
+
+/**
+ * The name of the configuration file looked for in the project root.
+ */
+pub const CONFIG_FILE_NAME: &str = "scar.toml";
+
+/**
+ * The exclusion rules of the "unreal" profile: the build outputs, plugins, test data and
+ * generated headers of Unreal Engine projects.
+ */
+pub const UNREAL_EXCLUDE_RULES: [&str; 6] = [
+    "Intermediate",
+    "Plugins",
+    "TestAutomationCore",
+    "Binaries",
+    "TestData",
+    "*.generated.h",
+];
+
+/**
+ * The built-in defaults the configuration file builds upon.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Profile {
+    /// Excludes the Unreal Engine build outputs, plugins and generated headers.
+    #[default]
+    Unreal,
+    /// Excludes nothing.
+    None,
+}
+
+impl Profile {
+    pub fn get_exclude_rules(&self) -> Vec<String> {
+        match self {
+            Profile::Unreal => UNREAL_EXCLUDE_RULES.iter().map(|r| r.to_string()).collect(),
+            Profile::None => Vec::new(),
+        }
+    }
+}
+
+/**
+ * The [files] section: which files are scanned, and which of them are translation units.
+ */
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FilesSection {
+    include: Vec<String>,
+    exclude: Vec<String>,
+    translation_unit_extensions: Vec<String>,
+    header_extensions: Vec<String>,
+    use_ignore_files: bool,
+}
+
+/**
+ * The [includes] section: how includes are resolved.
+ */
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct IncludesSection {
+    include_dirs: Vec<String>,
+    quote_dirs: Vec<String>,
+    system_dirs: Vec<String>,
+    strict: bool,
+}
+
+/**
+ * The [preprocessor] section: how conditional directives are handled.
+ */
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct PreprocessorSection {
+    defines: Vec<String>,
+    undefines: Vec<String>,
+    eval_conditionals: bool,
+    conditional_edges: Option<ConditionalEdges>,
+}
+
+/**
+ * The [output] section: how results are reported.
+ */
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct OutputSection {
+    num: Option<usize>,
+    debug: bool,
+}
+
+/**
+ * The [order] section: how includes are grouped by --order.
+ */
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct OrderSection {
+    groups: Vec<String>,
+}
+
+/**
+ * The project settings of a scar.toml file, e.g.:
+ *
+ * profile = "none"
+ *
+ * [files]
+ * exclude = ["ThirdParty"]
+ *
+ * [includes]
+ * include_dirs = ["Source/Public"]
+ *
+ * Command line options are applied on top of them. Without a configuration file, the "unreal"
+ * profile is used.
+ */
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProjectConfig {
+    profile: Profile,
+    files: FilesSection,
+    includes: IncludesSection,
+    preprocessor: PreprocessorSection,
+    output: OutputSection,
+    order: OrderSection,
+}
+
+impl ProjectConfig {
+    /**
+     * Loads the configuration file at the given path.
+     */
+    pub fn load(path: &Path) -> Result<ProjectConfig, Box<dyn Error>> {
+        let content = read_to_string(path)
+            .map_err(|e| format!("Cannot read configuration file {}: {}", path.display(), e))?;
+
+        Self::parse(&content)
+            .map_err(|e| format!("Invalid configuration file {}: {}", path.display(), e).into())
+    }
+
+    /**
+     * Loads the scar.toml file of the project root, if any.
+     */
+    pub fn find(project_path: &Path) -> Result<Option<ProjectConfig>, Box<dyn Error>> {
+        let path = project_path.join(CONFIG_FILE_NAME);
+        match path.is_file() {
+            true => Self::load(&path).map(Some),
+            false => Ok(None),
+        }
+    }
+
+    /**
+     * Parses the content of a configuration file. Errors name the offending key (e.g.,
+     * "files.exclude[1]").
+     */
+    pub fn parse(content: &str) -> Result<ProjectConfig, Box<dyn Error>> {
+        let config: ProjectConfig = toml::from_str(content)?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    /**
+     * Checks the values the file format cannot, returning the offending key with the problem.
+     */
+    fn validate(&self) -> Result<(), String> {
+        let lists = [
+            ("files.include", &self.files.include),
+            ("files.exclude", &self.files.exclude),
+            (
+                "files.translation_unit_extensions",
+                &self.files.translation_unit_extensions,
+            ),
+            ("files.header_extensions", &self.files.header_extensions),
+            ("includes.include_dirs", &self.includes.include_dirs),
+            ("includes.quote_dirs", &self.includes.quote_dirs),
+            ("includes.system_dirs", &self.includes.system_dirs),
+            ("preprocessor.defines", &self.preprocessor.defines),
+            ("preprocessor.undefines", &self.preprocessor.undefines),
+            ("order.groups", &self.order.groups),
+        ];
+        for (key, values) in lists {
+            if let Some(index) = values.iter().position(|v| v.trim().is_empty()) {
+                return Err(format!("{}[{}]: empty value", key, index));
+            }
+        }
+
+        let rules = [
+            ("files.include", &self.files.include),
+            ("files.exclude", &self.files.exclude),
+        ];
+        for (key, values) in rules {
+            for (index, rule) in values.iter().enumerate() {
+                FileRules::check_rule(rule).map_err(|e| format!("{}[{}]: {}", key, index, e))?;
+            }
+        }
+
+        let extensions = [
+            (
+                "files.translation_unit_extensions",
+                &self.files.translation_unit_extensions,
+            ),
+            ("files.header_extensions", &self.files.header_extensions),
+        ];
+        for (key, values) in extensions {
+            if let Some(index) = values.iter().position(|e| e.starts_with('.')) {
+                return Err(format!(
+                    "{}[{}]: extensions are given without the leading dot (e.g., \"cpp\")",
+                    key, index
+                ));
+            }
+        }
+
+        let macros = self
+            .preprocessor
+            .defines
+            .iter()
+            .map(|d| d.split_once('=').map_or(d.as_str(), |(name, _)| name))
+            .enumerate()
+            .map(|(index, name)| ("preprocessor.defines", index, name))
+            .chain(
+                self.preprocessor
+                    .undefines
+                    .iter()
+                    .enumerate()
+                    .map(|(index, name)| ("preprocessor.undefines", index, name.as_str())),
+            );
+        for (key, index, name) in macros {
+            let is_identifier = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !is_identifier {
+                return Err(format!("{}[{}]: invalid macro name {}", key, index, name));
+            }
+        }
+
+        for (index, group) in self.order.groups.iter().enumerate() {
+            let (name, pattern) = group
+                .split_once('=')
+                .ok_or_else(|| format!("order.groups[{}]: expected NAME=REGEX", index))?;
+            IncludeOrderPolicy::make(&[(name, pattern)])
+                .map_err(|e| format!("order.groups[{}]: {}", index, e))?;
+        }
+
+        if self.output.num == Some(0) {
+            return Err(String::from("output.num: must be greater than 0"));
+        }
+
+        Ok(())
+    }
+
+    pub fn get_profile(&self) -> Profile {
+        self.profile
+    }
+
+    /**
+     * Returns the rules of the subtrees to scan, if restricted.
+     */
+    pub fn get_include_rules(&self) -> &[String] {
+        &self.files.include
+    }
+
+    /**
+     * Returns the rules of the subtrees not to scan: the exclusions of the profile, followed by
+     * the ones of the file.
+     */
+    pub fn get_exclude_rules(&self) -> Vec<String> {
+        [self.profile.get_exclude_rules(), self.files.exclude.clone()].concat()
+    }
+
+    /**
+     * Returns whether .gitignore, .scarignore and the other ignore files are honoured.
+     */
+    pub fn is_use_ignore_files(&self) -> bool {
+        self.files.use_ignore_files
+    }
+
+    pub fn get_translation_unit_extensions(&self) -> &[String] {
+        &self.files.translation_unit_extensions
+    }
+
+    pub fn get_header_extensions(&self) -> &[String] {
+        &self.files.header_extensions
+    }
+
+    pub fn get_include_dirs(&self) -> &[String] {
+        &self.includes.include_dirs
+    }
+
+    pub fn get_quote_dirs(&self) -> &[String] {
+        &self.includes.quote_dirs
+    }
+
+    pub fn get_system_dirs(&self) -> &[String] {
+        &self.includes.system_dirs
+    }
+
+    /**
+     * Returns whether includes are resolved through the search directories only, without
+     * matching the project files ending with the include spelling.
+     */
+    pub fn is_strict_includes(&self) -> bool {
+        self.includes.strict
+    }
+
+    /**
+     * Returns the macros to define, as "NAME" or "NAME=VALUE".
+     */
+    pub fn get_defines(&self) -> &[String] {
+        &self.preprocessor.defines
+    }
+
+    pub fn get_undefines(&self) -> &[String] {
+        &self.preprocessor.undefines
+    }
+
+    pub fn is_eval_conditionals(&self) -> bool {
+        self.preprocessor.eval_conditionals
+    }
+
+    pub fn get_conditional_edges(&self) -> Option<ConditionalEdges> {
+        self.preprocessor.conditional_edges
+    }
+
+    /**
+     * Returns the max number of results to report, if set.
+     */
+    pub fn get_output_size(&self) -> Option<usize> {
+        self.output.num
+    }
+
+    pub fn is_debug(&self) -> bool {
+        self.output.debug
+    }
+
+    /**
+     * Returns the include groups of the ordering policy, in order, as "NAME=REGEX".
+     */
+    pub fn get_order_groups(&self) -> &[String] {
+        &self.order.groups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_parsing_test() -> Result<(), Box<dyn Error>> {
+        let config = ProjectConfig::parse(
+            r#"
+profile = "none"
+
+[files]
+include = ["Source"]
+exclude = ["ThirdParty"]
+translation_unit_extensions = ["cpp", "cc"]
+header_extensions = ["h", "hpp", "inl"]
+use_ignore_files = true
+
+[includes]
+include_dirs = ["Source/Public"]
+strict = true
+
+[preprocessor]
+defines = ["WITH_EDITOR=1", "UE_SERVER"]
+conditional_edges = "live"
+
+[output]
+num = 10
+
+[order]
+groups = ["project=^\"", "Qt=^<Q", "system=^<"]
+"#,
+        )?;
+
+        assert_eq!(Profile::None, config.get_profile());
+        assert_eq!(vec!["Source"], config.get_include_rules());
+        assert_eq!(vec!["ThirdParty"], config.get_exclude_rules());
+        assert_eq!(vec!["cpp", "cc"], config.get_translation_unit_extensions());
+        assert_eq!(vec!["h", "hpp", "inl"], config.get_header_extensions());
+        assert!(config.is_use_ignore_files());
+        assert_eq!(vec!["Source/Public"], config.get_include_dirs());
+        assert!(config.is_strict_includes());
+        assert_eq!(vec!["WITH_EDITOR=1", "UE_SERVER"], config.get_defines());
+        assert!(!config.is_eval_conditionals());
+        assert_eq!(Some(ConditionalEdges::Live), config.get_conditional_edges());
+        assert_eq!(Some(10), config.get_output_size());
+        assert!(!config.is_debug());
+        assert_eq!(
+            vec!["project=^\"", "Qt=^<Q", "system=^<"],
+            config.get_order_groups()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn default_config_test() -> Result<(), Box<dyn Error>> {
+        let config = ProjectConfig::parse("[files]\nexclude = [\"ThirdParty\"]")?;
+        assert_eq!(Profile::Unreal, config.get_profile());
+
+        let mut exclude = Profile::Unreal.get_exclude_rules();
+        exclude.push(String::from("ThirdParty"));
+        assert_eq!(exclude, config.get_exclude_rules());
+        assert_eq!(None, config.get_output_size());
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_config_test() {
+        let error = |content: &str| {
+            ProjectConfig::parse(content)
+                .err()
+                .map(|e| e.to_string())
+                .unwrap_or_default()
+        };
+
+        assert!(error("[files]\nexclde = []").contains("exclde"));
+        assert!(error("profile = \"godot\"").contains("profile"));
+        assert!(error("[preprocessor]\nconditional_edges = \"some\"").contains("conditional_edges"));
+        assert!(error("[output]\nnum = \"ten\"").contains("num"));
+        assert!(error("[files]\nexclude = [\"Binaries\", \"\"]").starts_with("files.exclude[1]"));
+        assert!(error("[files]\ntranslation_unit_extensions = [\".cpp\"]")
+            .starts_with("files.translation_unit_extensions[0]"));
+        assert!(error("[files]\nheader_extensions = [\"h\", \".hpp\"]")
+            .starts_with("files.header_extensions[1]"));
+        assert!(error("[preprocessor]\ndefines = [\"A=1\", \"2B\"]")
+            .starts_with("preprocessor.defines[1]"));
+        assert!(error("[output]\nnum = 0").starts_with("output.num"));
+        assert!(error("[files]\ninclude = [\"Source/[\"]").starts_with("files.include[0]"));
+        assert!(error("[files]\nexclude = [\"regex:(\"]").starts_with("files.exclude[0]"));
+        assert!(error("[order]\ngroups = [\"Qt\"]").starts_with("order.groups[0]"));
+        assert!(
+            error("[order]\ngroups = [\"Qt=^<Q\", \"broken=(\"]").starts_with("order.groups[1]")
+        );
+    }
+}
@@ -1,115 +1,444 @@
-use crate::file::File;
-use lazy_static;
+use crate::compilation_database::{CompilationDatabase, MacroFlag};
+use crate::dependency_analyzer::DependencyAnalyzer;
+use crate::file::{File, IncludeDirective, IncludeKind};
+use crate::file_rules::FileRules;
+use crate::include_resolver::{IncludeResolver, ProjectFiles, Resolution};
+use crate::preprocessor::MacroTable;
+use crate::source_extensions::SourceExtensions;
+use ignore::{DirEntry, WalkBuilder, WalkState};
+use rayon::prelude::*;
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::error::Error;
 use std::fs::read_to_string;
-use std::path::Path;
-use walkdir::{DirEntry, WalkDir};
-
-lazy_static::lazy_static! {
-    static ref CONFIG: HashMap<String, Vec<String>> = {
-        let mut config_map = HashMap::new();
-        config_map.insert(
-            String::from("white_list"),
-            vec![
-                String::from("Source"),
-            ],
-        );
-        config_map.insert(
-            String::from("black_list"),
-            vec![
-                String::from("Intermediate"),
-                String::from("Plugins"),
-                String::from("TestAutomationCore"),
-                String::from("Binaries"),
-                String::from("TestData"),
-                String::from("generated.h"),
-            ],
-        );
-        config_map
-    };
-}
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/**
+ * The name of the scar specific ignore files, following the .gitignore format.
+ */
+pub const SCAR_IGNORE_FILE_NAME: &str = ".scarignore";
 
 pub struct ProjectScanner<'a> {
     base_path: &'a Path,
-    processed_files: u64,
+    processed_files: AtomicU64,
+    file_rules: FileRules,
+    source_extensions: SourceExtensions,
+
+    /**
+     * Whether the ignore files are honoured when walking the project: .gitignore,
+     * .git/info/exclude, the global git excludes and .scarignore.
+     */
+    ignore_files: bool,
+
+    /**
+     * The macros to evaluate conditional directives with, if enabled. When scanning a compilation
+     * database, they are applied after the ones of each translation unit.
+     */
+    macros: Option<Vec<MacroFlag>>,
 }
 
 impl<'a> ProjectScanner<'a> {
-    pub fn make(base_path: &Path) -> Result<ProjectScanner, Box<dyn Error>> {
+    pub fn make(base_path: &Path) -> Result<ProjectScanner<'_>, Box<dyn Error>> {
         Ok(ProjectScanner {
-            base_path: base_path,
-            processed_files: 0u64,
+            base_path,
+            processed_files: AtomicU64::new(0),
+            file_rules: FileRules::default(),
+            source_extensions: SourceExtensions::default(),
+            ignore_files: false,
+            macros: None,
         })
     }
 
+    /**
+     * Sets the rules selecting the files to scan, replacing the ones of the default profile.
+     */
+    pub fn set_file_rules(&mut self, file_rules: FileRules) {
+        self.file_rules = file_rules;
+    }
+
+    /**
+     * Sets the extensions of the files scanned when walking the project, replacing the default
+     * ones.
+     */
+    pub fn set_source_extensions(&mut self, source_extensions: SourceExtensions) {
+        self.source_extensions = source_extensions;
+    }
+
+    /**
+     * Enables the ignore files when walking the project, as git does: the patterns of each
+     * .gitignore and .scarignore file apply to the subtree of its directory, while the ones of
+     * .git/info/exclude and of the global git excludes apply to the whole project. Files scanned
+     * because of a compilation database are not filtered.
+     */
+    pub fn enable_ignore_files(&mut self) {
+        self.ignore_files = true;
+    }
+
+    /**
+     * Enables the evaluation of conditional directives, so that only the includes actually
+     * compiled with the given macros are scanned. Without it, the includes of all the conditional
+     * branches are scanned.
+     */
+    pub fn enable_conditional_evaluation(&mut self, macros: &[MacroFlag]) {
+        self.macros = Some(macros.to_vec());
+    }
+
+    /**
+     * Walks the project and parses the files found, both distributed over the rayon thread pool.
+     * Files are returned sorted by path, whatever the number of threads.
+     */
     pub fn scan_files(&mut self) -> Result<Vec<File>, Box<dyn Error>> {
-        let walker = WalkDir::new(&self.base_path).into_iter();
-        let mut files = Vec::new();
-        for entry in walker.filter_entry(|e| Self::is_valid_entry(e)) {
-            let entry = entry?;
-            let path = entry.path();
-            let file_type = entry.file_type();
+        let mut paths = self.walk_files();
+        paths.sort();
+
+        let macros = self.macros.as_deref().map(MacroTable::make);
+        let scanner = &*self;
+        let files: Vec<Option<File>> = paths
+            .par_iter()
+            .map(|path| scanner.parse_file(path, macros.as_ref()))
+            .collect::<Result<_, String>>()?;
 
-            if file_type.is_file() {
-                match read_to_string(path) {
-                    Ok(content) => {
-                        files.push(File::make(entry.file_name().to_str().unwrap(), &content)?);
+        Ok(files.into_iter().flatten().collect())
+    }
+
+    /**
+     * Returns the paths of the files to scan, walking the project directories with as many
+     * threads as the rayon thread pool.
+     */
+    fn walk_files(&self) -> Vec<PathBuf> {
+        let base_path = self.base_path.to_path_buf();
+        let (file_rules, extensions) = (self.file_rules.clone(), self.source_extensions.clone());
+        let mut walker = WalkBuilder::new(self.base_path);
+        walker
+            .standard_filters(false)
+            .git_ignore(self.ignore_files)
+            .git_exclude(self.ignore_files)
+            .git_global(self.ignore_files)
+            .parents(self.ignore_files)
+            .require_git(false)
+            .threads(rayon::current_num_threads())
+            .filter_entry(move |e| Self::is_valid_entry(e, &base_path, &file_rules, &extensions));
+        if self.ignore_files {
+            walker.add_custom_ignore_filename(SCAR_IGNORE_FILE_NAME);
+        }
 
-                        self.on_processed_file();
+        let paths = Mutex::new(Vec::new());
+        walker.build_parallel().run(|| {
+            Box::new(|entry| {
+                match entry {
+                    Ok(entry) if entry.file_type().is_some_and(|t| t.is_file()) => {
+                        paths.lock().unwrap().push(entry.into_path());
                     }
+                    Ok(_) => {}
+                    // errors in ignore files are reported along with the entries
                     Err(error) => {
-                        println!(
-                            "Error while reading {:#?}. Error = {:?}. Skipping it.",
-                            path.to_str(),
-                            error
-                        );
+                        println!("Error while walking the project: {}. Skipping it.", error);
                     }
                 }
+                WalkState::Continue
+            })
+        });
+
+        paths.into_inner().unwrap()
+    }
+
+    /**
+     * Reads and parses a file of the project, returning None when it cannot be read.
+     */
+    fn parse_file(&self, path: &Path, macros: Option<&MacroTable>) -> Result<Option<File>, String> {
+        let content = match read_to_string(path) {
+            Ok(content) => content,
+            Err(error) => {
+                println!(
+                    "Error while reading {:#?}. Error = {:?}. Skipping it.",
+                    path.to_str(),
+                    error
+                );
+                return Ok(None);
+            }
+        };
+
+        let relative_path = self.make_relative_path(path).map_err(|e| e.to_string())?;
+        let file = match macros {
+            Some(macros) => File::make_preprocessed(&relative_path, &content, &[], macros),
+            None => File::make(&relative_path, &content),
+        };
+        self.on_processed_file();
+
+        file.map(Some).map_err(|e| e.to_string())
+    }
+
+    /**
+     * Scans the translation units of the compilation database and, transitively, the project files
+     * they include, as resolved by the given resolver. Files outside the project base path are
+     * not scanned.
+     * The macros of each translation unit (e.g., "-DCONFIG_HEADER=\"cfg.h\"") expand the computed
+     * includes of the translation unit and of the headers it reaches first, so that the files they
+     * include are scanned too. When conditional evaluation is enabled, the same macros, followed
+     * by the enabled ones, evaluate the conditional directives of these files.
+     * The computed includes left are then expanded with the macros defined along their include
+     * chains, scanning the files they lead to, until no more includes are expanded.
+     * Each level of the traversal is parsed over the rayon thread pool, and files are returned
+     * sorted by path, whatever the number of threads.
+     */
+    pub fn scan_compilation_database(
+        &mut self,
+        database: &CompilationDatabase,
+        resolver: &IncludeResolver,
+    ) -> Result<Vec<File>, Box<dyn Error>> {
+        let mut queued: HashSet<String> = HashSet::new();
+        let mut frontier: Vec<(String, Vec<String>, usize)> = Vec::new();
+        let mut macro_tables = Vec::new();
+        let mut root_macros = HashMap::new();
+
+        for command in database.get_commands() {
+            let Some(path) = resolver.make_project_path(command.get_file()) else {
+                println!(
+                    "Translation unit {} outside of the project. Skipping it.",
+                    command.get_file().display()
+                );
+                continue;
+            };
+
+            if self.file_rules.accepts_file(&path) && queued.insert(path.clone()) {
+                let forced_includes = command
+                    .get_forced_includes()
+                    .iter()
+                    .map(|f| f.to_string_lossy().to_string())
+                    .collect();
+
+                let flags = match &self.macros {
+                    Some(macros) => [command.get_macros(), macros].concat(),
+                    None => command.get_macros().to_vec(),
+                };
+                let macros = MacroTable::make(&flags);
+                root_macros.insert(path.clone(), macros.clone());
+                macro_tables.push(macros);
+                frontier.push((path, forced_includes, macro_tables.len() - 1));
             }
         }
 
+        let project_files = DiskProjectFiles {
+            base_path: self.base_path,
+        };
+
+        // the files scanned, each one with the macros of the translation unit reaching it first
+        let mut files = Vec::new();
+        let mut file_macros = Vec::new();
+
+        loop {
+            // the files of each level are parsed in parallel, then queued in order, so that a file
+            // reached from several translation units gets the macros of the same one every time
+            while !frontier.is_empty() {
+                let scanner = &*self;
+                let parsed: Vec<Option<(File, Vec<String>)>> = frontier
+                    .par_iter()
+                    .map(|(path, forced_includes, macros)| {
+                        let Some(file) = scanner.parse_included_file(
+                            path,
+                            forced_includes,
+                            &macro_tables[*macros],
+                        )?
+                        else {
+                            return Ok(None);
+                        };
+
+                        // include probes check whether files exist, without including them
+                        let directives = file
+                            .get_include_directives()
+                            .iter()
+                            .filter(|d| !d.is_soft());
+                        let included =
+                            scanner.make_included_files(path, directives, resolver, &project_files);
+                        Ok(Some((file, included)))
+                    })
+                    .collect::<Result<_, String>>()?;
+
+                let mut next = Vec::new();
+                for ((_, _, macros), parsed) in frontier.iter().zip(parsed) {
+                    let Some((file, included)) = parsed else {
+                        continue;
+                    };
+                    for included in included {
+                        if queued.insert(included.clone()) {
+                            next.push((included, Vec::new(), *macros));
+                        }
+                    }
+
+                    files.push(file);
+                    file_macros.push(*macros);
+                }
+                frontier = next;
+            }
+
+            // only the files with unexpanded includes may include further files once expanded
+            let expandable: Vec<usize> = (0..files.len())
+                .filter(|&i| files[i].has_unexpanded_includes())
+                .collect();
+            let num_expanded = DependencyAnalyzer::expand_computed_includes(
+                &mut files,
+                resolver,
+                &MacroTable::default(),
+                &root_macros,
+            )?;
+            if num_expanded == 0 {
+                break;
+            }
+
+            for i in expandable {
+                let file = &files[i];
+                let directives = file
+                    .get_include_directives()
+                    .iter()
+                    .filter(|d| d.is_computed() && d.get_kind() != IncludeKind::Computed)
+                    .filter(|d| !d.is_soft());
+                for included in
+                    self.make_included_files(file.get_path(), directives, resolver, &project_files)
+                {
+                    if queued.insert(included.clone()) {
+                        frontier.push((included, Vec::new(), file_macros[i]));
+                    }
+                }
+            }
+            if frontier.is_empty() {
+                break;
+            }
+        }
+
+        files.sort_by(|a, b| a.get_path().cmp(b.get_path()));
         Ok(files)
     }
 
-    fn is_valid_entry(entry: &DirEntry) -> bool {
-        let is_path_valid = Self::is_valid_file_path(entry.path().to_str().unwrap());
-        is_path_valid
-            && (entry.file_type().is_dir()
-                || entry
-                    .file_name()
-                    .to_str()
-                    .map(|s| Self::is_valid_file_name(s))
-                    .unwrap_or(false))
+    /**
+     * Reads and parses a file reached from the translation units of a compilation database,
+     * returning None when it cannot be read.
+     */
+    fn parse_included_file(
+        &self,
+        path: &str,
+        forced_includes: &[String],
+        macros: &MacroTable,
+    ) -> Result<Option<File>, String> {
+        let full_path = self.base_path.join(path);
+        let content = match read_to_string(&full_path) {
+            Ok(content) => content,
+            Err(error) => {
+                println!(
+                    "Error while reading {:#?}. Error = {:?}. Skipping it.",
+                    full_path.to_str(),
+                    error
+                );
+                return Ok(None);
+            }
+        };
+
+        let file = match &self.macros {
+            Some(_) => File::make_preprocessed(path, &content, forced_includes, macros),
+            None => {
+                File::make_with_forced_includes(path, &content, forced_includes).map(|mut f| {
+                    f.expand_computed_includes(macros);
+                    f
+                })
+            }
+        };
+        self.on_processed_file();
+
+        file.map(Some).map_err(|e| e.to_string())
     }
 
-    fn is_valid_file_name(path: &str) -> bool {
-        !path.starts_with(".") && (path.ends_with(".cpp") || path.ends_with(".h"))
+    /**
+     * Returns the project files to scan included by the given directives of the includer file.
+     */
+    fn make_included_files<'d>(
+        &self,
+        includer: &str,
+        directives: impl Iterator<Item = &'d IncludeDirective>,
+        resolver: &IncludeResolver,
+        project_files: &DiskProjectFiles,
+    ) -> Vec<String> {
+        directives
+            .filter_map(|directive| {
+                match resolver.resolve_directive(includer, directive, project_files) {
+                    Resolution::Project { path, .. } => Some(path),
+                    _ => None,
+                }
+            })
+            .filter(|path| self.file_rules.accepts_file(path))
+            .collect()
     }
 
-    fn is_valid_file_path(path: &str) -> bool {
-        !Self::is_blacklisted(path)
+    /**
+     * Returns the path of the given file relative to the project base path, using '/' as
+     * separator regardless of the platform, so that it can be used as the file identity.
+     */
+    fn make_relative_path(&self, path: &Path) -> Result<String, Box<dyn Error>> {
+        let relative_path = path.strip_prefix(self.base_path)?;
+        let components: Option<Vec<&str>> = relative_path
+            .components()
+            .map(|c| c.as_os_str().to_str())
+            .collect();
+
+        match components {
+            Some(components) => Ok(components.join("/")),
+            None => Err(format!("Invalid path {:?}", path).into()),
+        }
     }
 
-    fn on_processed_file(&mut self) {
-        self.processed_files += 1;
-        if self.processed_files > 0 && self.processed_files % 1000 == 0 {
-            println!("Processed num. files: {}", self.processed_files);
+    fn is_valid_entry(
+        entry: &DirEntry,
+        base_path: &Path,
+        file_rules: &FileRules,
+        extensions: &SourceExtensions,
+    ) -> bool {
+        let path = entry.path().strip_prefix(base_path).unwrap_or(entry.path());
+        let Some(path) = path.to_str() else {
+            return false;
+        };
+
+        match entry.file_type().is_some_and(|t| t.is_dir()) {
+            true => file_rules.accepts_directory(path),
+            false => {
+                file_rules.accepts_file(path)
+                    && entry
+                        .file_name()
+                        .to_str()
+                        .is_some_and(|name| Self::is_valid_file_name(name, extensions))
+            }
         }
     }
 
-    fn is_blacklisted(entry: &str) -> bool {
-        let is_blacklisted = CONFIG.get("black_list").map_or(false, |black_list| {
-            black_list.iter().any(|bl| entry.contains(bl))
-        });
+    fn is_valid_file_name(name: &str, extensions: &SourceExtensions) -> bool {
+        !name.starts_with(".") && extensions.get_kind(name).is_some()
+    }
 
-        is_blacklisted
+    fn on_processed_file(&self) {
+        let processed_files = self.processed_files.fetch_add(1, Ordering::Relaxed) + 1;
+        if processed_files.is_multiple_of(1000) {
+            println!("Processed num. files: {}", processed_files);
+        }
+    }
+}
+
+/**
+ * The project files as found on disk, used when the files to scan are discovered by following the
+ * includes.
+ */
+struct DiskProjectFiles<'a> {
+    base_path: &'a Path,
+}
+
+impl ProjectFiles for DiskProjectFiles<'_> {
+    fn contains_file(&self, path: &str) -> bool {
+        self.base_path.join(path).is_file()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::include_resolver::IncludeSettings;
     use lazy_static::lazy_static;
     use std::fs::File;
     use std::io::Write;
@@ -146,17 +475,19 @@ mod tests {
         static ref INVALID_TEST_PATH: PathBuf = PathBuf::from(".media/workspace/");
 
         static ref TEST_PATH_TO_BE_FILTERED: Vec<PathBuf> = vec![
-            PathBuf::from("/media/workspace/Source/Intermediate/Plugins/Binaries/test.cpp"),
-            PathBuf::from("/media/workspace/Source/Intermediate/Plugins/Binaries/SomePlugin/test.h"),
-            PathBuf::from("/media/workspace/repos/BarFoo/FooBar/Intermediate/Build/Linux/UnrealEditor/Inc/KitchenEntities/UHT/KEKitchenMaterialDataC.generated.h"),
-            PathBuf::from("/media/workspace/repos/BarFoo/FooBar/Plugins/SERE/Source/SimpleElementsRenderingExtension/Shaders"),
-            PathBuf::from("/home/user/repos/BarFoo/FooBar/Plugins/USQLite/Source/Runtime/Public/USQLReflector.h"),
-            PathBuf::from("/home/user/repos/BarFoo/FooBar/Plugins/USQLite/Source/Runtime/Public/USQLReflector.generated.h"),
+            PathBuf::from("Source/Intermediate/Plugins/Binaries/test.cpp"),
+            PathBuf::from("Source/Intermediate/Plugins/Binaries/SomePlugin/test.h"),
+            PathBuf::from("FooBar/Intermediate/Build/Linux/UnrealEditor/Inc/KitchenEntities/UHT/KEKitchenMaterialDataC.generated.h"),
+            PathBuf::from("FooBar/Plugins/SERE/Source/SimpleElementsRenderingExtension/Shaders"),
+            PathBuf::from("Plugins/USQLite/Source/Runtime/Public/USQLReflector.h"),
+            PathBuf::from("Source/Runtime/Public/USQLReflector.generated.h"),
         ];
 
         static ref TEST_PATH_NOT_TO_BE_FILTERED: Vec<PathBuf> = vec![
-            PathBuf::from("/media/workspace/Source/test.cpp"),
-            PathBuf::from("/media/workspace/Source/test.h"),
+            PathBuf::from("Source/test.cpp"),
+            PathBuf::from("Source/test.h"),
+            PathBuf::from("Source/MyPluginsHelper/test.h"),
+            PathBuf::from("Source/IntermediateTypes.h"),
         ];
    }
 
@@ -202,6 +533,17 @@ mod tests {
         Ok((temp_dir, inner_temp_dir))
     }
 
+    /**
+     * Scans the files of the given, already configured, project and returns their paths.
+     */
+    fn scan_paths(project: &mut ProjectScanner) -> Result<Vec<String>, Box<dyn Error>> {
+        Ok(project
+            .scan_files()?
+            .iter()
+            .map(|f| f.get_path().to_string())
+            .collect())
+    }
+
     #[test]
     fn cpp_directory_parsing_test() -> Result<(), Box<dyn Error>> {
         // arrange
@@ -219,7 +561,7 @@ mod tests {
             vec![FIRST_TEST_CONTENT, SECOND_TEST_CONTENT, THIRD_TEST_CONTENT],
         )?;
 
-        let mut project = super::ProjectScanner::make(&temp_base_dir.path())?;
+        let mut project = super::ProjectScanner::make(temp_base_dir.path())?;
 
         // act
         let files = project.scan_files()?;
@@ -227,11 +569,26 @@ mod tests {
         // assert
         assert_eq!(6, files.len());
 
+        let inner_dir_name = temp_inner_dir
+            .path()
+            .file_name()
+            .and_then(|n| n.to_str())
+            .ok_or("Invalid inner directory name")?;
+        let mut paths: Vec<String> = files.iter().map(|f| f.get_path().to_string()).collect();
+        paths.sort();
+        let mut expected_paths = vec![
+            String::from("first.cpp"),
+            String::from("second.cpp"),
+            String::from("third.h"),
+            format!("{}/first.cpp", inner_dir_name),
+            format!("{}/second.cpp", inner_dir_name),
+            format!("{}/third.h", inner_dir_name),
+        ];
+        expected_paths.sort();
+        assert_eq!(expected_paths, paths);
+
         // cleanup
-        for f in first_level_files
-            .into_iter()
-            .chain(second_level_files.into_iter())
-        {
+        for f in first_level_files.into_iter().chain(second_level_files) {
             drop(f);
         }
 
@@ -242,11 +599,115 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn compilation_database_scanning_test() -> Result<(), Box<dyn Error>> {
+        // arrange
+        let temp_dir = TempDir::new("scar_compilation_database_scanning_test")?;
+        let base_path = temp_dir.path();
+        std::fs::create_dir_all(base_path.join("include"))?;
+        std::fs::create_dir_all(base_path.join("build"))?;
+
+        create_file(
+            base_path,
+            "main.cpp",
+            "#include <used.h>\n#include <vector>",
+        )?;
+        create_file(base_path, "unused.cpp", "#include <unused.h>")?;
+        create_file(base_path, "include/used.h", "#include \"nested.h\"")?;
+        create_file(base_path, "include/nested.h", "")?;
+        create_file(base_path, "include/unused.h", "")?;
+        create_file(base_path, "include/prefix.h", "")?;
+
+        let database = CompilationDatabase::parse(&format!(
+            r#"[{{
+                "directory": "{}",
+                "command": "c++ -I../include -include ../include/prefix.h -c ../main.cpp",
+                "file": "../main.cpp"
+            }}]"#,
+            base_path.join("build").display()
+        ))?;
+        let include_settings = database.make_include_settings(&IncludeSettings::default());
+        let resolver = IncludeResolver::make(base_path, &include_settings)?;
+
+        let mut project = super::ProjectScanner::make(base_path)?;
+
+        // act
+        let files = project.scan_compilation_database(&database, &resolver)?;
+
+        // assert
+        let paths: Vec<&str> = files.iter().map(|f| f.get_path()).collect();
+        assert_eq!(
+            vec![
+                "include/nested.h",
+                "include/prefix.h",
+                "include/used.h",
+                "main.cpp"
+            ],
+            paths
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn compilation_database_conditional_scanning_test() -> Result<(), Box<dyn Error>> {
+        // arrange
+        let temp_dir = TempDir::new("scar_compilation_database_conditional_scanning_test")?;
+        let base_path = temp_dir.path();
+
+        create_file(
+            base_path,
+            "main.cpp",
+            "#include \"common.h\"\n#if WITH_EDITOR\n#include \"editor.h\"\n#endif",
+        )?;
+        create_file(
+            base_path,
+            "common.h",
+            "#ifdef SERVER\n#include \"server.h\"\n#else\n#include \"client.h\"\n#endif",
+        )?;
+        create_file(base_path, "editor.h", "")?;
+        create_file(base_path, "server.h", "")?;
+        create_file(base_path, "client.h", "")?;
+
+        let database = CompilationDatabase::parse(&format!(
+            r#"[{{
+                "directory": "{}",
+                "command": "c++ -DWITH_EDITOR=0 -DSERVER -c main.cpp",
+                "file": "main.cpp"
+            }}]"#,
+            base_path.display()
+        ))?;
+        let resolver = IncludeResolver::make(base_path, &IncludeSettings::default())?;
+
+        // act
+        let mut project = super::ProjectScanner::make(base_path)?;
+        let all_branches = project.scan_compilation_database(&database, &resolver)?;
+
+        let mut project = super::ProjectScanner::make(base_path)?;
+        project.enable_conditional_evaluation(&[MacroFlag::Undefine(String::from("SERVER"))]);
+        let evaluated = project.scan_compilation_database(&database, &resolver)?;
+
+        // assert
+        let paths = |files: &[crate::file::File]| {
+            let mut paths: Vec<String> = files.iter().map(|f| f.get_path().to_string()).collect();
+            paths.sort();
+            paths
+        };
+        assert_eq!(
+            vec!["client.h", "common.h", "editor.h", "main.cpp", "server.h"],
+            paths(&all_branches)
+        );
+        assert_eq!(vec!["client.h", "common.h", "main.cpp"], paths(&evaluated));
+
+        Ok(())
+    }
+
     #[test]
     fn valid_cpp_file_path_test() {
         let valid_path = TEST_PATH.join("file.cpp");
         assert!(ProjectScanner::is_valid_file_name(
-            valid_path.to_str().unwrap()
+            valid_path.to_str().unwrap(),
+            &SourceExtensions::default()
         ));
     }
 
@@ -254,7 +715,8 @@ mod tests {
     fn valid_header_file_path_test() {
         let valid_path = TEST_PATH.join("file.h");
         assert!(ProjectScanner::is_valid_file_name(
-            valid_path.to_str().unwrap()
+            valid_path.to_str().unwrap(),
+            &SourceExtensions::default()
         ));
     }
 
@@ -262,7 +724,8 @@ mod tests {
     fn invalid_hidden_directory_path_test() {
         let invalid_path = INVALID_TEST_PATH.join("file.h");
         assert!(!ProjectScanner::is_valid_file_name(
-            invalid_path.to_str().unwrap()
+            invalid_path.to_str().unwrap(),
+            &SourceExtensions::default()
         ));
     }
 
@@ -270,18 +733,227 @@ mod tests {
     fn invalid_hidden_file_path_test() {
         let invalid_path = INVALID_TEST_PATH.join(".file.cpp");
         assert!(!ProjectScanner::is_valid_file_name(
-            invalid_path.to_str().unwrap()
+            invalid_path.to_str().unwrap(),
+            &SourceExtensions::default()
         ));
     }
 
+    #[test]
+    fn source_extensions_file_path_test() {
+        let extensions = SourceExtensions::default();
+        for name in ["file.cc", "file.hpp", "file.inl", "file.tpp"] {
+            assert!(ProjectScanner::is_valid_file_name(name, &extensions));
+        }
+        assert!(!ProjectScanner::is_valid_file_name("file.md", &extensions));
+
+        let extensions = extensions.with_header_extensions(vec![String::from("hpp")]);
+        assert!(!ProjectScanner::is_valid_file_name("file.h", &extensions));
+        assert!(ProjectScanner::is_valid_file_name("file.hpp", &extensions));
+    }
+
     #[test]
     fn blacklisted_directory_path_test() {
+        let file_rules = FileRules::default();
         for path in TEST_PATH_TO_BE_FILTERED.iter() {
-            assert!(ProjectScanner::is_blacklisted(path.to_str().unwrap()));
+            assert!(file_rules.is_excluded(path.to_str().unwrap()));
         }
 
         for path in TEST_PATH_NOT_TO_BE_FILTERED.iter() {
-            assert!(!ProjectScanner::is_blacklisted(path.to_str().unwrap()));
+            assert!(!file_rules.is_excluded(path.to_str().unwrap()));
+        }
+    }
+
+    #[test]
+    fn file_rules_scanning_test() -> Result<(), Box<dyn Error>> {
+        let temp_dir = TempDir::new("scar_file_rules_scanning_test")?;
+        let base_path = temp_dir.path();
+        std::fs::create_dir_all(base_path.join("Source/ThirdParty"))?;
+        std::fs::create_dir_all(base_path.join("Plugins"))?;
+        std::fs::create_dir_all(base_path.join("Tools/Scripts"))?;
+        for path in [
+            "Source/main.cpp",
+            "Source/ThirdParty/zlib.h",
+            "Plugins/plugin.cpp",
+            "Tools/tool.cpp",
+            "Tools/Scripts/script.cpp",
+        ] {
+            create_file(base_path, path, "")?;
+        }
+
+        let scan = |file_rules: FileRules| -> Result<Vec<String>, Box<dyn Error>> {
+            let mut project = ProjectScanner::make(base_path)?;
+            project.set_file_rules(file_rules);
+            scan_paths(&mut project)
+        };
+
+        assert_eq!(
+            vec![
+                "Source/ThirdParty/zlib.h",
+                "Source/main.cpp",
+                "Tools/Scripts/script.cpp",
+                "Tools/tool.cpp"
+            ],
+            scan(FileRules::default())?
+        );
+        assert_eq!(
+            vec!["Source/main.cpp"],
+            scan(FileRules::make(
+                vec![String::from("Source")],
+                vec![String::from("ThirdParty")]
+            )?)?
+        );
+
+        // only the included subtree is walked, and its parents
+        assert_eq!(
+            vec!["Tools/Scripts/script.cpp"],
+            scan(FileRules::make(
+                vec![String::from("Tools/Scripts")],
+                Vec::new()
+            )?)?
+        );
+        assert_eq!(
+            vec!["Plugins/plugin.cpp", "Tools/tool.cpp"],
+            scan(FileRules::make(
+                vec![String::from("*.cpp")],
+                vec![String::from("Source"), String::from("regex:/Scripts$")]
+            )?)?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn parallel_scanning_test() -> Result<(), Box<dyn Error>> {
+        let temp_dir = TempDir::new("scar_parallel_scanning_test")?;
+        let base_path = temp_dir.path();
+        for directory in 0..8 {
+            std::fs::create_dir_all(base_path.join(format!("Source/Module{}", directory)))?;
+            for file in 0..16 {
+                let name = format!("Source/Module{}/file{}", directory, file);
+                let include = format!("#include \"file{}.h\"", (file + 1) % 16);
+                create_file(base_path, &format!("{}.h", name), &include)?;
+                create_file(base_path, &format!("{}.cpp", name), &include)?;
+            }
         }
+
+        let scan = |num_threads: usize| -> Result<Vec<String>, Box<dyn Error>> {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(num_threads)
+                .build()?;
+            let mut project = ProjectScanner::make(base_path)?;
+            let paths = pool.install(|| scan_paths(&mut project).map_err(|e| e.to_string()))?;
+            Ok(paths)
+        };
+
+        // the files come in the same order, whatever the number of threads
+        let paths = scan(1)?;
+        assert_eq!(8 * 16 * 2, paths.len());
+        assert_eq!("Source/Module0/file0.cpp", paths[0]);
+        assert_eq!(paths, scan(4)?);
+        assert_eq!(paths, scan(8)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn parallel_compilation_database_scanning_test() -> Result<(), Box<dyn Error>> {
+        let temp_dir = TempDir::new("scar_parallel_compilation_database_scanning_test")?;
+        let base_path = temp_dir.path();
+        create_file(base_path, "common.h", "#include CONFIG_HEADER")?;
+        let mut commands = Vec::new();
+        for tu in 0..16 {
+            create_file(
+                base_path,
+                &format!("tu{}.cpp", tu),
+                &format!("#include \"common.h\"\n#include \"tu{}.h\"", tu),
+            )?;
+            create_file(base_path, &format!("tu{}.h", tu), "#include \"common.h\"")?;
+            create_file(base_path, &format!("cfg{}.h", tu), "")?;
+            commands.push(format!(
+                r#"{{
+                    "directory": "{}",
+                    "command": "c++ -DCONFIG_HEADER=\\\"cfg{}.h\\\" -c tu{}.cpp",
+                    "file": "tu{}.cpp"
+                }}"#,
+                base_path.display(),
+                tu,
+                tu,
+                tu
+            ));
+        }
+        let database = CompilationDatabase::parse(&format!("[{}]", commands.join(",")))?;
+        let resolver = IncludeResolver::make(base_path, &IncludeSettings::default())?;
+
+        let scan = |num_threads: usize| -> Result<Vec<String>, Box<dyn Error>> {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(num_threads)
+                .build()?;
+            let mut project = ProjectScanner::make(base_path)?;
+            let files = pool.install(|| {
+                project
+                    .scan_compilation_database(&database, &resolver)
+                    .map_err(|e| e.to_string())
+            })?;
+            Ok(files.iter().map(|f| f.get_path().to_string()).collect())
+        };
+
+        // common.h always gets the macros of the first translation unit
+        let paths = scan(1)?;
+        assert_eq!(16 * 2 + 2, paths.len());
+        assert!(paths.contains(&String::from("cfg0.h")));
+        assert_eq!(paths, scan(4)?);
+        assert_eq!(paths, scan(8)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn ignore_files_scanning_test() -> Result<(), Box<dyn Error>> {
+        let temp_dir = TempDir::new("scar_ignore_files_scanning_test")?;
+        let base_path = temp_dir.path();
+        std::fs::create_dir_all(base_path.join(".git/info"))?;
+        std::fs::create_dir_all(base_path.join("build"))?;
+        std::fs::create_dir_all(base_path.join("Source/ThirdParty"))?;
+        for (path, content) in [
+            (".gitignore", "build/\n*.gen.h\n"),
+            (".git/info/exclude", "scratch.cpp\n"),
+            (SCAR_IGNORE_FILE_NAME, "ThirdParty\n"),
+            ("Source/.gitignore", "local.cpp\n"),
+            ("build/out.cpp", ""),
+            ("Source/ThirdParty/zlib.h", ""),
+            ("Source/local.cpp", ""),
+            ("Source/main.cpp", ""),
+            ("Source/main.gen.h", ""),
+            ("local.cpp", ""),
+            ("scratch.cpp", ""),
+        ] {
+            create_file(base_path, path, content)?;
+        }
+
+        let scan = |ignore_files: bool| -> Result<Vec<String>, Box<dyn Error>> {
+            let mut project = ProjectScanner::make(base_path)?;
+            if ignore_files {
+                project.enable_ignore_files();
+            }
+            scan_paths(&mut project)
+        };
+
+        assert_eq!(
+            vec![
+                "Source/ThirdParty/zlib.h",
+                "Source/local.cpp",
+                "Source/main.cpp",
+                "Source/main.gen.h",
+                "build/out.cpp",
+                "local.cpp",
+                "scratch.cpp"
+            ],
+            scan(false)?
+        );
+
+        // the ignore file of Source does not apply to the project root
+        assert_eq!(vec!["Source/main.cpp", "local.cpp"], scan(true)?);
+
+        Ok(())
     }
 }
@@ -0,0 +1,135 @@
+/**
+ * Default extensions of the files compiled on their own, i.e., translation units, C++20 module
+ * interface units included.
+ */
+pub const DEFAULT_TRANSLATION_UNIT_EXTENSIONS: [&str; 6] = ["c", "cc", "cpp", "cxx", "cppm", "ixx"];
+
+/**
+ * Default extensions of the files only compiled when included, i.e., headers, inline and template
+ * implementation files included.
+ */
+pub const DEFAULT_HEADER_EXTENSIONS: [&str; 8] =
+    ["h", "hh", "hpp", "hxx", "inl", "ipp", "tpp", "inc"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+    TranslationUnit,
+    Header,
+}
+
+/**
+ * The extensions of the source files, without the leading dot (e.g., "cpp"), classifying them as
+ * translation units or headers. An extension given as both is a translation unit.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceExtensions {
+    translation_units: Vec<String>,
+    headers: Vec<String>,
+}
+
+impl SourceExtensions {
+    pub fn make(translation_units: Vec<String>, headers: Vec<String>) -> Self {
+        SourceExtensions {
+            translation_units,
+            headers,
+        }
+    }
+
+    /**
+     * Replaces the translation unit extensions, when the given ones are not empty.
+     */
+    pub fn with_translation_unit_extensions(mut self, extensions: Vec<String>) -> Self {
+        if !extensions.is_empty() {
+            self.translation_units = extensions;
+        }
+        self
+    }
+
+    /**
+     * Replaces the header extensions, when the given ones are not empty.
+     */
+    pub fn with_header_extensions(mut self, extensions: Vec<String>) -> Self {
+        if !extensions.is_empty() {
+            self.headers = extensions;
+        }
+        self
+    }
+
+    pub fn get_translation_unit_extensions(&self) -> &[String] {
+        &self.translation_units
+    }
+
+    pub fn get_header_extensions(&self) -> &[String] {
+        &self.headers
+    }
+
+    /**
+     * Returns the kind of the file, according to the extension of its name, or None when it is
+     * not a source file (e.g., "README.md" or "vector").
+     */
+    pub fn get_kind(&self, path: &str) -> Option<FileKind> {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        let (_, extension) = name.rsplit_once('.')?;
+
+        if self.translation_units.iter().any(|e| e == extension) {
+            Some(FileKind::TranslationUnit)
+        } else if self.headers.iter().any(|e| e == extension) {
+            Some(FileKind::Header)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_translation_unit(&self, path: &str) -> bool {
+        self.get_kind(path) == Some(FileKind::TranslationUnit)
+    }
+
+    pub fn is_header(&self, path: &str) -> bool {
+        self.get_kind(path) == Some(FileKind::Header)
+    }
+}
+
+impl Default for SourceExtensions {
+    fn default() -> Self {
+        let to_strings = |extensions: &[&str]| extensions.iter().map(|e| e.to_string()).collect();
+        SourceExtensions::make(
+            to_strings(&DEFAULT_TRANSLATION_UNIT_EXTENSIONS),
+            to_strings(&DEFAULT_HEADER_EXTENSIONS),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_kind_test() {
+        let extensions = SourceExtensions::default();
+
+        assert_eq!(
+            Some(FileKind::TranslationUnit),
+            extensions.get_kind("main.cc")
+        );
+        assert_eq!(
+            Some(FileKind::TranslationUnit),
+            extensions.get_kind("Source/lib.c")
+        );
+        assert_eq!(
+            Some(FileKind::Header),
+            extensions.get_kind("Source/Public/foo.hpp")
+        );
+        assert_eq!(Some(FileKind::Header), extensions.get_kind("foo.inl"));
+        assert_eq!(None, extensions.get_kind("vector"));
+        assert_eq!(None, extensions.get_kind("Source.cpp/main"));
+        assert_eq!(None, extensions.get_kind("README.md"));
+
+        // translation units win over headers
+        let extensions = extensions
+            .with_translation_unit_extensions(vec![String::from("cpp"), String::from("inl")])
+            .with_header_extensions(Vec::new());
+        assert!(extensions.is_translation_unit("foo.inl"));
+        assert!(!extensions.is_translation_unit("main.cc"));
+        assert!(extensions.is_header("foo.h"));
+    }
+}
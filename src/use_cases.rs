@@ -1,18 +1,238 @@
+use crate::compilation_database::{CompilationDatabase, MacroFlag};
+use crate::dependency_analyzer::ConditionalEdges;
 use crate::dependency_analyzer::DependencyAnalyzer;
 use crate::dependency_analyzer::DependencyEntry;
+use crate::dependency_analyzer::ImpactEntry;
+use crate::dependency_analyzer::IncludeChainQuery;
+use crate::dependency_analyzer::IncludeEdge;
+use crate::dependency_analyzer::IncludeWeightEntry;
+use crate::file::{File, IncludeKind};
+use crate::file_rules::FileRules;
+use crate::include_order::IncludeOrderPolicy;
+use crate::include_resolver::{IncludeResolver, IncludeSettings};
+use crate::preprocessor::MacroTable;
 use crate::project_scanner::ProjectScanner;
+use crate::source_extensions::SourceExtensions;
 use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::error::Error;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+/**
+ * A number associated to each file (e.g., its number of impacted files), by file name.
+ */
+type FileCounts = HashMap<String, usize>;
+
+/**
+ * The include guard problems of the project headers:
+ * - the headers without include guard
+ * - the guard macros shared by different headers, with the headers sharing them
+ * - the headers whose guard macro does not wrap the whole file
+ */
+type GuardIssues = (Vec<String>, HashMap<String, Vec<String>>, Vec<String>);
+
+/**
+ * The include problems found in the project files:
+ * - the duplicate includes, as the including file and the lines of the two includes
+ * - the self includes, as the including file and the line of the include
+ * - the includes of translation units, as the including file and the line of the include
+ */
+type IncludeLints = (
+    Vec<(String, usize, usize)>,
+    Vec<(String, usize)>,
+    Vec<(String, usize)>,
+);
 
 fn get_slice_up_to<T>(slice: &[T], num: usize) -> &[T] {
     match slice.len().cmp(&num) {
-        Ordering::Less | Ordering::Equal => &slice[..],
+        Ordering::Less | Ordering::Equal => slice,
         Ordering::Greater => &slice[..num],
     }
 }
 
+/**
+ * Scans the project files, either walking the project path or following the translation
+ * units of the compilation database, and returns them together with the include resolver
+ * to use for the analysis. Computed includes are expanded with the macros defined along their
+ * include chains.
+ */
+fn scan_project(
+    path: &Path,
+    settings: &ScanSettings,
+) -> Result<(Vec<File>, IncludeResolver), Box<dyn Error>> {
+    let (mut files, resolver) = scan_project_files(path, settings)?;
+
+    // scanning a compilation database already expands them, with the macros of each translation
+    // unit
+    if settings.compile_commands.is_none() {
+        let macros = settings
+            .macros
+            .as_deref()
+            .map(MacroTable::make)
+            .unwrap_or_default();
+        DependencyAnalyzer::expand_computed_includes(
+            &mut files,
+            &resolver,
+            &macros,
+            &HashMap::new(),
+        )?;
+    }
+
+    Ok((files, resolver))
+}
+
+fn scan_project_files(
+    path: &Path,
+    settings: &ScanSettings,
+) -> Result<(Vec<File>, IncludeResolver), Box<dyn Error>> {
+    let mut project = ProjectScanner::make(path)?;
+    project.set_file_rules(settings.file_rules.clone());
+    project.set_source_extensions(settings.source_extensions.clone());
+    if settings.ignore_files {
+        project.enable_ignore_files();
+    }
+    if let Some(macros) = &settings.macros {
+        project.enable_conditional_evaluation(macros);
+    }
+
+    match &settings.compile_commands {
+        Some(compile_commands) => {
+            let database = CompilationDatabase::load(compile_commands)?;
+            let include_settings = database.make_include_settings(&settings.include_settings);
+            let resolver = IncludeResolver::make(path, &include_settings)?;
+            let files = project.scan_compilation_database(&database, &resolver)?;
+            Ok((files, resolver))
+        }
+        None => {
+            let resolver = IncludeResolver::make(path, &settings.include_settings)?;
+            let files = project.scan_files()?;
+            Ok((files, resolver))
+        }
+    }
+}
+
+/**
+ * Builds the analyzer of the scanned files, with the source extensions and the
+ * conditional edges of the scan settings.
+ */
+fn make_analyzer<'a>(
+    files: &'a [File],
+    resolver: &IncludeResolver,
+    config: &Config,
+) -> Result<DependencyAnalyzer<'a>, Box<dyn Error>> {
+    make_analyzer_with_edge_filter(
+        files,
+        resolver,
+        config,
+        config.scan_settings.conditional_edges,
+    )
+}
+
+fn make_analyzer_with_edge_filter<'a>(
+    files: &'a [File],
+    resolver: &IncludeResolver,
+    config: &Config,
+    filter: ConditionalEdges,
+) -> Result<DependencyAnalyzer<'a>, Box<dyn Error>> {
+    let mut analyzer =
+        DependencyAnalyzer::make_with_edge_filter(files, resolver, filter, config.debug)?;
+    analyzer.set_source_extensions(&config.scan_settings.source_extensions);
+
+    Ok(analyzer)
+}
+
+/**
+ * Builds the analyzer of the include directives compiled regardless of the defined macros, to
+ * tell the impact existing only under some conditions (e.g., on some platforms). Returns None
+ * when the analysis already keeps these directives only.
+ */
+fn make_unconditional_analyzer<'a>(
+    files: &'a [File],
+    resolver: &IncludeResolver,
+    config: &Config,
+) -> Result<Option<DependencyAnalyzer<'a>>, Box<dyn Error>> {
+    match config.scan_settings.conditional_edges {
+        ConditionalEdges::Always => Ok(None),
+        _ => make_analyzer_with_edge_filter(files, resolver, config, ConditionalEdges::Always)
+            .map(Some),
+    }
+}
+
+/**
+ * Returns the note of a reported number, telling how much of it exists only under some
+ * conditions, if any (e.g., ", 3 only under conditions").
+ */
+fn make_conditional_note(total: usize, unconditional: Option<usize>) -> String {
+    match unconditional {
+        Some(unconditional) if unconditional < total => {
+            format!(", {} only under conditions", total - unconditional)
+        }
+        _ => String::new(),
+    }
+}
+
+fn report_unresolved_includes(analyzer: &DependencyAnalyzer, debug: bool) {
+    let unresolved = analyzer.get_unresolved_includes();
+    let num_computed = unresolved
+        .iter()
+        .filter(|e| e.get_directive().is_computed())
+        .count();
+    println!(
+        "Unresolved includes: {} ({} computed)",
+        unresolved.len(),
+        num_computed
+    );
+
+    if debug {
+        for e in unresolved {
+            let directive = e.get_directive();
+            let description = match directive.get_kind() {
+                IncludeKind::Computed => "Unresolved computed include (unknown macros)",
+                _ if directive.is_computed() => "Unresolved computed include",
+                _ if directive.is_next() => "Unresolved include_next",
+                _ => "Unresolved include",
+            };
+            println!(
+                "{}: {} in {}:{}:{}",
+                description,
+                e.get_spelling(),
+                e.get_includer(),
+                directive.get_line(),
+                directive.get_column()
+            );
+        }
+    }
+
+    // include probes are reported apart, since they do not include the probed files
+    let probes = analyzer.get_include_probes();
+    if probes.is_empty() {
+        return;
+    }
+
+    let num_found = probes.iter().filter(|e| e.is_resolved()).count();
+    println!(
+        "Include probes (__has_include): {}, {} found",
+        probes.len(),
+        num_found
+    );
+
+    if debug {
+        for e in probes {
+            let directive = e.get_directive();
+            println!(
+                "Include probe: {} in {}:{}: {}",
+                directive.get_text(),
+                e.get_includer(),
+                directive.get_line(),
+                match e.is_resolved() {
+                    true => e.get_target(),
+                    false => "not found",
+                }
+            );
+        }
+    }
+}
+
 pub struct TopNUseCase {}
 
 impl TopNUseCase {
@@ -42,10 +262,9 @@ impl TopNUseCase {
         config: Config,
     ) -> Result<HashMap<String, usize>, Box<dyn Error>> {
         let path = Path::new(config.path);
-        let mut project = ProjectScanner::make(path)?;
-
-        let files = project.scan_files()?;
-        let analyzer = DependencyAnalyzer::make(&files, config.debug)?;
+        let (files, resolver) = scan_project(path, &config.scan_settings)?;
+        let analyzer = make_analyzer(&files, &resolver, &config)?;
+        report_unresolved_includes(&analyzer, config.debug);
 
         println!("Sorting ...");
         let sorted_inclusions = analyzer.get_sorted_inclusion();
@@ -73,28 +292,627 @@ impl TopNUseCase {
      */
     pub fn do_sorted_topn_impact(config: Config) -> Result<HashMap<String, usize>, Box<dyn Error>> {
         let path = Path::new(config.path);
-        let mut project = ProjectScanner::make(path)?;
-
-        let files = project.scan_files()?;
-        let analyzer = DependencyAnalyzer::make(&files, config.debug)?;
+        let (files, resolver) = scan_project(path, &config.scan_settings)?;
+        let analyzer = make_analyzer(&files, &resolver, &config)?;
+        report_unresolved_includes(&analyzer, config.debug);
 
         println!("Sorting impact ...");
-        let sorted_impacts = analyzer.get_sorted_impact();
+        let sorted_impacts = analyzer.get_sorted_impact_counts(true);
+        let unconditional_impacts: Option<HashMap<&str, usize>> =
+            make_unconditional_analyzer(&files, &resolver, &config)?.map(|a| {
+                a.get_sorted_impact_counts(true)
+                    .iter()
+                    .map(|e| (e.get_file_name(), e.get_num_impacted_files()))
+                    .collect()
+            });
 
         println!("Sorted!");
 
-        let sorted_impacts: &[DependencyEntry] =
-            get_slice_up_to(&sorted_impacts, config.output_size);
+        let sorted_impacts: &[ImpactEntry] = get_slice_up_to(&sorted_impacts, config.output_size);
 
         for i in sorted_impacts.iter() {
+            let unconditional = unconditional_impacts
+                .as_ref()
+                .map(|u| u.get(i.get_file_name()).copied().unwrap_or(0));
             println!(
-                "Source found: {}, num impacted files: {}",
+                "Source found: {}, num impacted files: {}{}",
                 i.get_file_name(),
-                i.get_including_file_paths().len()
+                i.get_num_impacted_files(),
+                make_conditional_note(i.get_num_impacted_files(), unconditional)
             );
+            analyzer.print_impact_tree(i.get_file_name());
         }
 
-        Ok(Self::make_output_data_from_slice(sorted_impacts))
+        Ok(sorted_impacts
+            .iter()
+            .map(|e| (e.get_file_name().to_string(), e.get_num_impacted_files()))
+            .collect())
+    }
+
+    /**
+     * TopN translation unit impact use-case
+     * Returns the top-N headers by impacted translation units, i.e., the N headers included,
+     * directly or indirectly, by the largest number of translation units, each one with the list
+     * of these translation units.
+     *
+     * - path: the project path to analyze
+     * - num: the max number of headers to report as output.
+     */
+    pub fn do_sorted_topn_translation_unit_impact(
+        config: Config,
+    ) -> Result<HashMap<String, Vec<String>>, Box<dyn Error>> {
+        let path = Path::new(config.path);
+        let (files, resolver) = scan_project(path, &config.scan_settings)?;
+        let analyzer = make_analyzer(&files, &resolver, &config)?;
+        report_unresolved_includes(&analyzer, config.debug);
+
+        println!("Sorting translation unit impact ...");
+        let sorted_impacts = analyzer.get_sorted_translation_unit_impact(true);
+        let unconditional_analyzer = make_unconditional_analyzer(&files, &resolver, &config)?;
+
+        println!("Sorted!");
+
+        let sorted_impacts: &[ImpactEntry] = get_slice_up_to(&sorted_impacts, config.output_size);
+
+        let mut output_data = HashMap::new();
+        for i in sorted_impacts.iter() {
+            let translation_units = analyzer.get_impacted_translation_units(i.get_file_name());
+            let unconditional_translation_units = unconditional_analyzer
+                .as_ref()
+                .map(|a| a.get_impacted_translation_units(i.get_file_name()));
+
+            println!(
+                "Source found: {}, num impacted translation units: {}{}",
+                i.get_file_name(),
+                i.get_num_impacted_files(),
+                make_conditional_note(
+                    i.get_num_impacted_files(),
+                    unconditional_translation_units.as_ref().map(|u| u.len())
+                )
+            );
+
+            if config.debug {
+                for t in &translation_units {
+                    let is_conditional = unconditional_translation_units
+                        .as_ref()
+                        .is_some_and(|u| !u.contains(t));
+                    match is_conditional {
+                        true => println!("    {} (only under conditions)", t),
+                        false => println!("    {}", t),
+                    }
+                }
+            }
+
+            output_data.insert(
+                i.get_file_name().to_string(),
+                translation_units.iter().map(|t| t.to_string()).collect(),
+            );
+        }
+
+        Ok(output_data)
+    }
+
+    /**
+     * TopN include weight use-case
+     * Returns the top-N translation units by include weight, i.e., the N translation units
+     * including, directly or indirectly, the largest number of files.
+     *
+     * - path: the project path to analyze
+     * - num: the max number of translation units to report as output.
+     */
+    pub fn do_sorted_topn_include_weight(
+        config: Config,
+    ) -> Result<HashMap<String, usize>, Box<dyn Error>> {
+        let path = Path::new(config.path);
+        let (files, resolver) = scan_project(path, &config.scan_settings)?;
+        let analyzer = make_analyzer(&files, &resolver, &config)?;
+        report_unresolved_includes(&analyzer, config.debug);
+
+        println!("Sorting include weight ...");
+        let sorted_weights = analyzer.get_sorted_include_weights(true);
+
+        println!("Sorted!");
+
+        let sorted_weights: &[IncludeWeightEntry] =
+            get_slice_up_to(&sorted_weights, config.output_size);
+
+        for i in sorted_weights.iter() {
+            println!(
+                "Source found: {}, num included files: {}",
+                i.get_file_name(),
+                i.get_num_included_files()
+            );
+        }
+
+        Ok(sorted_weights
+            .iter()
+            .map(|e| (e.get_file_name().to_string(), e.get_num_included_files()))
+            .collect())
+    }
+}
+
+pub struct CompileCostUseCase {}
+
+impl CompileCostUseCase {
+    /**
+     * Compile cost use-case
+     * Returns the top-N translation units by estimated preprocessed size, in lines, and the top-N
+     * headers by compile cost, i.e., their lines multiplied by the number of translation units
+     * including them.
+     *
+     * - path: the project path to analyze
+     * - num: the max number of translation units and headers to report as output.
+     */
+    pub fn do_compile_cost(config: Config) -> Result<(FileCounts, FileCounts), Box<dyn Error>> {
+        let path = Path::new(config.path);
+        let (files, resolver) = scan_project(path, &config.scan_settings)?;
+        let analyzer = make_analyzer(&files, &resolver, &config)?;
+        report_unresolved_includes(&analyzer, config.debug);
+
+        println!("Estimating compile cost ...");
+        let sizes = analyzer.get_sorted_preprocessed_sizes(true);
+        let costs = analyzer.get_sorted_header_costs(true);
+        println!("Estimated!");
+
+        let sizes = get_slice_up_to(&sizes, config.output_size);
+        for s in sizes.iter() {
+            println!(
+                "Translation unit found: {}, preprocessed lines: {}, preprocessed bytes: {}",
+                s.get_file_name(),
+                s.get_num_lines(),
+                s.get_num_bytes()
+            );
+        }
+
+        let costs = get_slice_up_to(&costs, config.output_size);
+        for c in costs.iter() {
+            println!(
+                "Header found: {}, lines: {}, translation units: {}, cost lines: {}, cost bytes: {}",
+                c.get_file_name(),
+                c.get_num_lines(),
+                c.get_num_translation_units(),
+                c.get_cost_lines(),
+                c.get_cost_bytes()
+            );
+        }
+
+        Ok((
+            sizes
+                .iter()
+                .map(|s| (s.get_file_name().to_string(), s.get_num_lines()))
+                .collect(),
+            costs
+                .iter()
+                .map(|c| (c.get_file_name().to_string(), c.get_cost_lines()))
+                .collect(),
+        ))
+    }
+}
+
+pub struct IncludeChainUseCase {}
+
+impl IncludeChainUseCase {
+    /**
+     * Include chain use-case
+     * Returns the include chains explaining why a file includes another one, each one as the
+     * list of its files, printing the line of each include directive along the way.
+     *
+     * - path: the project path to analyze
+     * - from: the including file, relative to the project path
+     * - to: the included file, relative to the project path
+     * - query: which chains to report
+     */
+    pub fn do_include_chains(
+        config: Config,
+        from: &str,
+        to: &str,
+        query: IncludeChainQuery,
+    ) -> Result<Vec<Vec<String>>, Box<dyn Error>> {
+        let path = Path::new(config.path);
+        let (files, resolver) = scan_project(path, &config.scan_settings)?;
+        let analyzer = make_analyzer(&files, &resolver, &config)?;
+        report_unresolved_includes(&analyzer, config.debug);
+
+        println!("Searching include chains ...");
+        let chains = analyzer.get_include_chains(from, to, query)?;
+        println!("Found {} include chains.", chains.len());
+
+        for (index, c) in chains.iter().enumerate() {
+            println!(
+                "Include chain {}: {} includes",
+                index + 1,
+                c.get_edges().len()
+            );
+            for e in c.get_edges() {
+                let directive = e.get_directive();
+                println!(
+                    "    {}:{}:{}: {} -> {}",
+                    e.get_directive_file(),
+                    directive.get_line(),
+                    directive.get_column(),
+                    directive.get_text(),
+                    e.get_target()
+                );
+                for condition in directive.get_conditions() {
+                    println!(
+                        "        under {}:{}: {}",
+                        e.get_directive_file(),
+                        condition.get_line(),
+                        condition.get_text()
+                    );
+                }
+            }
+        }
+
+        Ok(chains
+            .iter()
+            .map(|c| c.get_files().iter().map(|f| f.to_string()).collect())
+            .collect())
+    }
+}
+
+pub struct IncludeGuardsUseCase {}
+
+impl IncludeGuardsUseCase {
+    /**
+     * Include guards use-case
+     * Returns the project headers without "#pragma once" nor guard macro, the guard macros
+     * shared by different headers, with the headers sharing them, and the headers whose guard
+     * macro does not wrap the whole file.
+     *
+     * - path: the project path to analyze
+     * - num: the max number of headers and guard macros to report as output, for each problem.
+     */
+    pub fn do_include_guards(config: Config) -> Result<GuardIssues, Box<dyn Error>> {
+        let path = Path::new(config.path);
+        let (files, resolver) = scan_project(path, &config.scan_settings)?;
+        let analyzer = make_analyzer(&files, &resolver, &config)?;
+
+        println!("Checking include guards ...");
+        let issues = analyzer.get_include_guard_issues();
+        println!(
+            "Found {} headers without include guard, {} colliding guard macros, {} guard macros not wrapping the whole file.",
+            issues.get_missing_guards().len(),
+            issues.get_colliding_guards().len(),
+            issues.get_partial_guards().len()
+        );
+
+        let missing = get_slice_up_to(issues.get_missing_guards(), config.output_size);
+        for f in missing.iter() {
+            println!("Header without include guard: {}", f.get_path());
+        }
+
+        let colliding = get_slice_up_to(issues.get_colliding_guards(), config.output_size);
+        for c in colliding.iter() {
+            println!(
+                "Colliding guard macro: {}, headers: {}",
+                c.get_guard_macro(),
+                c.get_files().join(", ")
+            );
+        }
+
+        let partial = get_slice_up_to(issues.get_partial_guards(), config.output_size);
+        for f in partial.iter() {
+            let guard = f.get_include_guard();
+            println!(
+                "Guard macro not wrapping the whole file: {}:{}: {}",
+                f.get_path(),
+                guard.get_guard_line(),
+                guard.get_guard_macro().unwrap_or_default()
+            );
+        }
+
+        Ok((
+            missing.iter().map(|f| f.get_path().to_string()).collect(),
+            colliding
+                .iter()
+                .map(|c| {
+                    (
+                        c.get_guard_macro().to_string(),
+                        c.get_files().iter().map(|f| f.to_string()).collect(),
+                    )
+                })
+                .collect(),
+            partial.iter().map(|f| f.get_path().to_string()).collect(),
+        ))
+    }
+}
+
+pub struct IncludeLintUseCase {}
+
+impl IncludeLintUseCase {
+    /**
+     * Include lint use-case
+     * Returns the duplicate includes, as the including file with the lines of the first include
+     * and of the repeated one, the self includes and the includes of translation units, as the
+     * including file with the line of the include.
+     *
+     * - path: the project path to analyze
+     * - num: the max number of includes to report as output, for each problem.
+     */
+    pub fn do_include_lint(config: Config) -> Result<IncludeLints, Box<dyn Error>> {
+        let path = Path::new(config.path);
+        let (files, resolver) = scan_project(path, &config.scan_settings)?;
+        let analyzer = make_analyzer(&files, &resolver, &config)?;
+        report_unresolved_includes(&analyzer, config.debug);
+
+        println!("Checking includes ...");
+        let duplicates = analyzer.get_duplicate_includes();
+        let self_includes = analyzer.get_self_includes();
+        let translation_unit_includes = analyzer.get_translation_unit_includes();
+        println!(
+            "Found {} duplicate includes, {} self includes, {} includes of translation units.",
+            duplicates.len(),
+            self_includes.len(),
+            translation_unit_includes.len()
+        );
+
+        let duplicates = get_slice_up_to(&duplicates, config.output_size);
+        for (first, second) in duplicates.iter() {
+            println!(
+                "Duplicate include: {}:{}: {}, already included at line {}",
+                second.get_includer(),
+                second.get_line(),
+                second.get_directive().get_text(),
+                first.get_line()
+            );
+        }
+
+        let self_includes = get_slice_up_to(&self_includes, config.output_size);
+        for e in self_includes.iter() {
+            println!(
+                "Self include: {}:{}: {}",
+                e.get_includer(),
+                e.get_line(),
+                e.get_directive().get_text()
+            );
+        }
+
+        let translation_unit_includes =
+            get_slice_up_to(&translation_unit_includes, config.output_size);
+        for e in translation_unit_includes.iter() {
+            println!(
+                "Include of translation unit: {}:{}: {} -> {}",
+                e.get_includer(),
+                e.get_line(),
+                e.get_directive().get_text(),
+                e.get_target()
+            );
+        }
+
+        let locations = |edges: &[&IncludeEdge]| -> Vec<(String, usize)> {
+            edges
+                .iter()
+                .map(|e| (e.get_includer().to_string(), e.get_line()))
+                .collect()
+        };
+
+        Ok((
+            duplicates
+                .iter()
+                .map(|(first, second)| {
+                    (
+                        second.get_includer().to_string(),
+                        first.get_line(),
+                        second.get_line(),
+                    )
+                })
+                .collect(),
+            locations(self_includes),
+            locations(translation_unit_includes),
+        ))
+    }
+}
+
+pub struct IncludeOrderUseCase {}
+
+impl IncludeOrderUseCase {
+    /**
+     * Include order use-case
+     * Returns the lines of the includes violating the ordering policy, for each file with
+     * violations, printing the fixed include blocks if required.
+     *
+     * - path: the project path to analyze
+     * - num: the max number of files to report as output.
+     * - policy: the include ordering policy
+     * - fix: whether to print the fixed version of the include blocks with violations
+     */
+    pub fn do_include_order(
+        config: Config,
+        policy: &IncludeOrderPolicy,
+        fix: bool,
+    ) -> Result<HashMap<String, Vec<usize>>, Box<dyn Error>> {
+        let path = Path::new(config.path);
+        let (mut files, _) = scan_project(path, &config.scan_settings)?;
+        files.sort_by(|a, b| a.get_path().cmp(b.get_path()));
+
+        println!("Checking include order ...");
+        let reports: Vec<(&File, _)> = files
+            .iter()
+            .map(|f| (f, policy.check(f)))
+            .filter(|(_, r)| !r.get_violations().is_empty())
+            .collect();
+        println!(
+            "Found {} include order violations in {} files.",
+            reports
+                .iter()
+                .map(|(_, r)| r.get_violations().len())
+                .sum::<usize>(),
+            reports.len()
+        );
+
+        let reports = get_slice_up_to(&reports, config.output_size);
+        for (f, r) in reports.iter() {
+            for v in r.get_violations() {
+                println!(
+                    "Include order violation: {}:{}: {}",
+                    f.get_path(),
+                    v.get_line(),
+                    v.get_message()
+                );
+            }
+
+            if fix {
+                for b in r.get_fixed_blocks() {
+                    println!(
+                        "Fixed include block: {}:{}-{}:",
+                        f.get_path(),
+                        b.get_first_line(),
+                        b.get_last_line()
+                    );
+                    for line in b.get_lines() {
+                        match line.is_empty() {
+                            true => println!(),
+                            false => println!("    {}", line),
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(reports
+            .iter()
+            .map(|(f, r)| {
+                (
+                    f.get_path().to_string(),
+                    r.get_violations().iter().map(|v| v.get_line()).collect(),
+                )
+            })
+            .collect())
+    }
+}
+
+pub struct IncludeCyclesUseCase {}
+
+impl IncludeCyclesUseCase {
+    /**
+     * Include cycles use-case
+     * Returns the N largest include cycles, each one as the list of its files.
+     *
+     * - path: the project path to analyze
+     * - num: the max number of cycles to report as output.
+     */
+    pub fn do_include_cycles(config: Config) -> Result<Vec<Vec<String>>, Box<dyn Error>> {
+        let path = Path::new(config.path);
+        let (files, resolver) = scan_project(path, &config.scan_settings)?;
+        let analyzer = make_analyzer(&files, &resolver, &config)?;
+        report_unresolved_includes(&analyzer, config.debug);
+
+        println!("Searching include cycles ...");
+        let cycles = analyzer.get_include_cycles();
+        println!("Found {} include cycles.", cycles.len());
+
+        let cycles = get_slice_up_to(&cycles, config.output_size);
+        for c in cycles.iter() {
+            println!(
+                "Include cycle found: {} files, e.g.: {}",
+                c.get_files().len(),
+                c.get_example_path().join(" -> ")
+            );
+        }
+
+        Ok(cycles
+            .iter()
+            .map(|c| c.get_files().iter().map(|f| f.to_string()).collect())
+            .collect())
+    }
+}
+
+/**
+ * Settings determining which files are scanned and how their includes are resolved.
+ * - include_settings: the include search directories
+ * - compile_commands: the compilation database whose translation units are scanned, instead of
+ *   walking the whole project path
+ * - source_extensions: the extensions of the files scanned when walking the project path,
+ *   telling translation units and headers apart
+ * - macros: the macros to evaluate conditional directives with, if enabled; otherwise, the
+ *   includes of all the conditional branches are scanned
+ * - conditional_edges: which includes are analyzed, depending on the conditions enclosing them
+ * - file_rules: the rules selecting the project files to scan
+ * - ignore_files: whether .gitignore, .scarignore and the other ignore files are honoured when
+ *   walking the project path
+ */
+#[derive(Debug)]
+pub struct ScanSettings {
+    include_settings: IncludeSettings,
+    compile_commands: Option<PathBuf>,
+    source_extensions: SourceExtensions,
+    macros: Option<Vec<MacroFlag>>,
+    conditional_edges: ConditionalEdges,
+    file_rules: FileRules,
+    ignore_files: bool,
+}
+
+impl ScanSettings {
+    pub fn make(include_settings: IncludeSettings, compile_commands: Option<PathBuf>) -> Self {
+        ScanSettings {
+            include_settings,
+            compile_commands,
+            source_extensions: SourceExtensions::default(),
+            macros: None,
+            conditional_edges: ConditionalEdges::default(),
+            file_rules: FileRules::default(),
+            ignore_files: false,
+        }
+    }
+
+    /**
+     * Enables the evaluation of conditional directives with the given macros, applied after the
+     * ones of the compilation database, if any.
+     */
+    pub fn with_conditional_evaluation(mut self, macros: Vec<MacroFlag>) -> Self {
+        self.macros = Some(macros);
+        self
+    }
+
+    /**
+     * Sets which includes are analyzed, depending on the conditions enclosing them (e.g., only
+     * the ones not under "#if 0").
+     */
+    pub fn with_conditional_edges(mut self, conditional_edges: ConditionalEdges) -> Self {
+        self.conditional_edges = conditional_edges;
+        self
+    }
+
+    /**
+     * Sets the rules selecting the project files to scan (e.g., excluding "ThirdParty").
+     */
+    pub fn with_file_rules(mut self, file_rules: FileRules) -> Self {
+        self.file_rules = file_rules;
+        self
+    }
+
+    /**
+     * Skips the files ignored by .gitignore, .git/info/exclude, the global git excludes and
+     * .scarignore files when walking the project path.
+     */
+    pub fn with_ignore_files(mut self) -> Self {
+        self.ignore_files = true;
+        self
+    }
+
+    /**
+     * Replaces the default translation unit extensions, when the given ones are not empty.
+     */
+    pub fn with_translation_unit_extensions(mut self, extensions: Vec<String>) -> Self {
+        self.source_extensions = self
+            .source_extensions
+            .with_translation_unit_extensions(extensions);
+        self
+    }
+
+    /**
+     * Replaces the default header extensions, when the given ones are not empty.
+     */
+    pub fn with_header_extensions(mut self, extensions: Vec<String>) -> Self {
+        self.source_extensions = self.source_extensions.with_header_extensions(extensions);
+        self
+    }
+}
+
+impl Default for ScanSettings {
+    fn default() -> Self {
+        ScanSettings::make(IncludeSettings::default(), None)
     }
 }
 
@@ -102,14 +920,25 @@ pub struct Config<'a> {
     path: &'a str,
     output_size: usize,
     debug: bool,
+    scan_settings: ScanSettings,
 }
 
 impl<'a> Config<'a> {
     pub fn make(path: &'a str, output_size: usize, debug: bool) -> Self {
+        Self::make_with_scan_settings(path, output_size, debug, ScanSettings::default())
+    }
+
+    pub fn make_with_scan_settings(
+        path: &'a str,
+        output_size: usize,
+        debug: bool,
+        scan_settings: ScanSettings,
+    ) -> Self {
         Config {
             path,
             output_size,
             debug,
+            scan_settings,
         }
     }
 }
@@ -117,6 +946,24 @@ impl<'a> Config<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tempdir::TempDir;
+
+    /**
+     * Creates a project in a temporary directory with the given files, as (path, content) pairs,
+     * creating their directories as needed.
+     */
+    fn make_project(files: &[(&str, &str)]) -> Result<TempDir, Box<dyn Error>> {
+        let temp_dir = TempDir::new("scar_use_case")?;
+        for (path, content) in files {
+            let path = temp_dir.path().join(path);
+            if let Some(directory) = path.parent() {
+                std::fs::create_dir_all(directory)?;
+            }
+            std::fs::write(path, content)?;
+        }
+
+        Ok(temp_dir)
+    }
 
     #[test]
     fn get_slice_up_to_num_test() {
@@ -133,10 +980,10 @@ mod tests {
         let config = Config::make("tests/simple", 100, false);
         let inclusions = TopNUseCase::do_sorted_topn_inclusions(config)?;
         assert_eq!(4, inclusions.len());
-        assert_eq!(2, inclusions["test001.h"]);
-        assert_eq!(1, inclusions["test002.h"]);
-        assert_eq!(0, inclusions["test001.cpp"]);
-        assert_eq!(0, inclusions["test002.cpp"]);
+        assert_eq!(2, inclusions["Source/Public/test001.h"]);
+        assert_eq!(1, inclusions["Source/Public/test002.h"]);
+        assert_eq!(0, inclusions["Source/Private/test001.cpp"]);
+        assert_eq!(0, inclusions["Source/Private/test002.cpp"]);
         Ok(())
     }
 
@@ -145,10 +992,10 @@ mod tests {
         let config = Config::make("tests/simple", 100, false);
         let impacts = TopNUseCase::do_sorted_topn_impact(config)?;
         assert_eq!(4, impacts.len());
-        assert_eq!(3, impacts["test001.h"]);
-        assert_eq!(1, impacts["test002.h"]);
-        assert_eq!(0, impacts["test001.cpp"]);
-        assert_eq!(0, impacts["test002.cpp"]);
+        assert_eq!(3, impacts["Source/Public/test001.h"]);
+        assert_eq!(1, impacts["Source/Public/test002.h"]);
+        assert_eq!(0, impacts["Source/Private/test001.cpp"]);
+        assert_eq!(0, impacts["Source/Private/test002.cpp"]);
         Ok(())
     }
 
@@ -159,15 +1006,20 @@ mod tests {
         assert_eq!(14, inclusions.len());
 
         // commented out
-        assert!(!inclusions.contains_key("even_more_nested.h"));
+        assert!(!inclusions.keys().any(|k| k.ends_with("even_more_nested.h")));
 
         // filtered because inside Intermediate directory
         assert!(!inclusions.contains_key("thread"));
 
+        // project files are identified by their path, external files by their include spelling
+        assert!(inclusions.contains_key("include/complex.h"));
+        assert!(inclusions.contains_key("iostream"));
+        assert!(!inclusions.contains_key("complex.h"));
+
         // test only some possible inclusions
-        assert_eq!(1, inclusions["signal_set.hpp"]);
+        assert_eq!(1, inclusions["asio/signal_set.hpp"]);
         assert_eq!(1, inclusions["complex.generated.h"]);
-        assert_eq!(1, inclusions["nested.h"]);
+        assert_eq!(1, inclusions["include/nested/nested.h"]);
         assert_eq!(0, inclusions["main.cpp"]);
 
         Ok(())
@@ -180,15 +1032,403 @@ mod tests {
         assert_eq!(14, impacts.len());
 
         // test only some possible impacts
-        assert_eq!(3, impacts["udp.hpp"]);
-        assert_eq!(3, impacts["tcp.hpp"]);
-        assert_eq!(3, impacts["signal_set.hpp"]);
+        assert_eq!(3, impacts["asio/ip/udp.hpp"]);
+        assert_eq!(3, impacts["asio/ip/tcp.hpp"]);
+        assert_eq!(3, impacts["asio/signal_set.hpp"]);
         assert_eq!(2, impacts["use_cases.generated.h"]);
         assert_eq!(1, impacts["iostream"]);
-        assert_eq!(2, impacts["nested.h"]);
-        assert_eq!(1, impacts["use_cases.h"]);
+        assert_eq!(2, impacts["include/nested/nested.h"]);
+        assert_eq!(1, impacts["include/use_cases.h"]);
+        assert_eq!(0, impacts["main.cpp"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn integration_use_case_include_weight_complex() -> Result<(), Box<dyn Error>> {
+        let config = Config::make("tests/complex", 100, false);
+        let weights = TopNUseCase::do_sorted_topn_include_weight(config)?;
+        assert_eq!(HashMap::from([(String::from("main.cpp"), 13)]), weights);
+
+        let config = Config::make("tests/simple", 100, false);
+        let weights = TopNUseCase::do_sorted_topn_include_weight(config)?;
+        assert_eq!(2, weights.len());
+        assert_eq!(1, weights["Source/Private/test001.cpp"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn integration_use_case_translation_unit_impact_simple() -> Result<(), Box<dyn Error>> {
+        let config = Config::make("tests/simple", 100, false);
+        let impacts = TopNUseCase::do_sorted_topn_translation_unit_impact(config)?;
+
+        // only headers are reported
+        assert!(impacts.keys().all(|k| k.ends_with(".h")));
+        assert_eq!(
+            vec![
+                String::from("Source/Private/test001.cpp"),
+                String::from("Source/Private/test002.cpp")
+            ],
+            impacts["Source/Public/test001.h"]
+        );
+        assert_eq!(
+            vec![String::from("Source/Private/test002.cpp")],
+            impacts["Source/Public/test002.h"]
+        );
+
+        // no translation unit at all with other extensions
+        let config = Config::make_with_scan_settings(
+            "tests/simple",
+            100,
+            false,
+            ScanSettings::default().with_translation_unit_extensions(vec![String::from("cc")]),
+        );
+        let impacts = TopNUseCase::do_sorted_topn_translation_unit_impact(config)?;
+        assert!(impacts.values().all(|t| t.is_empty()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn integration_use_case_compile_cost_cycles() -> Result<(), Box<dyn Error>> {
+        let config = Config::make("tests/cycles", 100, false);
+        let (sizes, costs) = CompileCostUseCase::do_compile_cost(config)?;
+
+        let header_lines = |name: &str| {
+            std::fs::read_to_string(Path::new("tests/cycles/include").join(name))
+                .unwrap()
+                .lines()
+                .count()
+        };
+        let all_headers = ["a.h", "b.h", "c.h", "d.h", "e.h", "self.h"];
+
+        let main_lines = std::fs::read_to_string("tests/cycles/main.cpp")?
+            .lines()
+            .count();
+        assert_eq!(
+            main_lines + all_headers.iter().map(|h| header_lines(h)).sum::<usize>(),
+            sizes["main.cpp"]
+        );
+
+        // a single translation unit: each header costs its own lines
+        assert_eq!(all_headers.len(), costs.len());
+        assert_eq!(header_lines("e.h"), costs["include/e.h"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn integration_use_case_impact_conditional_evaluation() -> Result<(), Box<dyn Error>> {
+        let project = make_project(&[
+            (
+                "main.cpp",
+                "#include \"common.h\"\n#if PLATFORM_WINDOWS\n#include \"windows.h\"\n#endif",
+            ),
+            ("common.h", ""),
+            ("windows.h", ""),
+        ])?;
+        let path = project.path().to_str().ok_or("Invalid path")?;
+
+        let config = Config::make(path, 100, false);
+        let impacts = TopNUseCase::do_sorted_topn_impact(config)?;
+        assert_eq!(1, impacts["windows.h"]);
+
+        let scan_settings = ScanSettings::default()
+            .with_conditional_evaluation(vec![MacroFlag::parse_define("PLATFORM_WINDOWS=0")]);
+        let config = Config::make_with_scan_settings(path, 100, false, scan_settings);
+        let impacts = TopNUseCase::do_sorted_topn_impact(config)?;
+        assert_eq!(0, impacts["windows.h"]);
+        assert_eq!(1, impacts["common.h"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn integration_use_case_impact_conditional_edges() -> Result<(), Box<dyn Error>> {
+        let project = make_project(&[
+            ("main.cpp", "#ifdef _WIN32\n#include \"windows.h\"\n#endif\n#if 0\n#include \"legacy.h\"\n#endif"),
+            ("windows.h", ""),
+            ("legacy.h", ""),
+        ])?;
+        let path = project.path().to_str().ok_or("Invalid path")?;
+
+        let scan_settings = ScanSettings::default().with_conditional_edges(ConditionalEdges::Live);
+        let config = Config::make_with_scan_settings(path, 100, false, scan_settings);
+        let impacts = TopNUseCase::do_sorted_topn_impact(config)?;
+        assert_eq!(1, impacts["windows.h"]);
+        assert_eq!(0, impacts["legacy.h"]);
+
+        let scan_settings =
+            ScanSettings::default().with_conditional_edges(ConditionalEdges::Always);
+        let config = Config::make_with_scan_settings(path, 100, false, scan_settings);
+        let impacts = TopNUseCase::do_sorted_topn_impact(config)?;
+        assert_eq!(0, impacts["windows.h"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn integration_use_case_include_lint() -> Result<(), Box<dyn Error>> {
+        let project = make_project(&[
+            (
+                "main.cpp",
+                "#include \"a.h\"\n#include \"util.cpp\"\n#include \"a.h\"",
+            ),
+            ("a.h", "#pragma once\n#include \"a.h\""),
+            ("util.cpp", ""),
+        ])?;
+        let path = project.path().to_str().ok_or("Invalid path")?;
+
+        let config = Config::make(path, 100, false);
+        let (duplicates, self_includes, translation_unit_includes) =
+            IncludeLintUseCase::do_include_lint(config)?;
+        assert_eq!(vec![(String::from("main.cpp"), 1, 3)], duplicates);
+        assert_eq!(vec![(String::from("a.h"), 2)], self_includes);
+        assert_eq!(
+            vec![(String::from("main.cpp"), 2)],
+            translation_unit_includes
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn integration_use_case_include_order() -> Result<(), Box<dyn Error>> {
+        let project = make_project(&[
+            (
+                "main.cpp",
+                "#include <vector>\n#include \"main.h\"\n#include \"a.h\"",
+            ),
+            ("main.h", "#include \"b.h\"\n#include \"a.h\""),
+            ("a.h", "#include <string>\n"),
+        ])?;
+        let path = project.path().to_str().ok_or("Invalid path")?;
+
+        let config = Config::make(path, 100, false);
+        let violations =
+            IncludeOrderUseCase::do_include_order(config, &IncludeOrderPolicy::default(), true)?;
+        assert_eq!(2, violations.len());
+        assert_eq!(vec![2, 3], violations["main.cpp"]);
+        assert_eq!(vec![2], violations["main.h"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn integration_use_case_include_guards() -> Result<(), Box<dyn Error>> {
+        let project = make_project(&[
+            ("main.cpp", "#include \"a.h\"\n#include \"b.h\""),
+            ("a.h", "#ifndef A_H\n#define A_H\n#endif"),
+            ("b.h", "#ifndef A_H\n#define A_H\n#endif\nint b();"),
+            ("c.h", "int c();"),
+        ])?;
+        let path = project.path().to_str().ok_or("Invalid path")?;
+
+        let config = Config::make(path, 100, false);
+        let (missing, colliding, partial) = IncludeGuardsUseCase::do_include_guards(config)?;
+        assert_eq!(vec!["c.h"], missing);
+        assert_eq!(vec!["a.h", "b.h"], colliding["A_H"]);
+        assert_eq!(vec!["b.h"], partial);
+
+        Ok(())
+    }
+
+    #[test]
+    fn integration_use_case_impact_complex_include_dirs() -> Result<(), Box<dyn Error>> {
+        let include_settings = IncludeSettings::make(
+            vec![],
+            vec![String::from("include"), String::from("include/nested")],
+            vec![],
+            false,
+        );
+        let config = Config::make_with_scan_settings(
+            "tests/complex",
+            100,
+            false,
+            ScanSettings::make(include_settings, None),
+        );
+        let impacts = TopNUseCase::do_sorted_topn_impact(config)?;
+        assert_eq!(14, impacts.len());
+
+        assert_eq!(3, impacts["include/nested/local.h"]);
+        assert_eq!(2, impacts["include/nested/nested.h"]);
+        assert_eq!(1, impacts["include/complex.h"]);
         assert_eq!(0, impacts["main.cpp"]);
 
         Ok(())
     }
+
+    #[test]
+    fn integration_use_case_impact_complex_compilation_database() -> Result<(), Box<dyn Error>> {
+        let project_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/complex");
+        let temp_dir = TempDir::new("scar_use_case_compilation_database")?;
+        let compile_commands = temp_dir.path().join("compile_commands.json");
+        std::fs::write(
+            &compile_commands,
+            format!(
+                r#"[{{
+                    "directory": "{}",
+                    "arguments": ["c++", "-Iinclude", "-Iinclude/nested", "-c", "main.cpp"],
+                    "file": "main.cpp"
+                }}]"#,
+                project_path.display()
+            ),
+        )?;
+
+        let include_settings = IncludeSettings::make(vec![], vec![], vec![], false);
+        let config = Config::make_with_scan_settings(
+            project_path.to_str().unwrap(),
+            100,
+            false,
+            ScanSettings::make(include_settings, Some(compile_commands)),
+        );
+        let impacts = TopNUseCase::do_sorted_topn_impact(config)?;
+
+        // same graph as walking the whole directory, since every file is reachable from main.cpp
+        assert_eq!(14, impacts.len());
+        assert_eq!(3, impacts["asio/ip/udp.hpp"]);
+        assert_eq!(3, impacts["include/nested/local.h"]);
+        assert_eq!(2, impacts["include/nested/nested.h"]);
+        assert_eq!(1, impacts["include/use_cases.h"]);
+        assert_eq!(0, impacts["main.cpp"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn integration_use_case_impact_compilation_database_macros() -> Result<(), Box<dyn Error>> {
+        let project = make_project(&[
+            ("main.cpp", "#include CONFIG_HEADER\n"),
+            ("cfg.h", "#include \"detail.h\"\n"),
+            ("detail.h", ""),
+        ])?;
+        let path = project.path().to_str().ok_or("Invalid path")?;
+        let compile_commands = project.path().join("compile_commands.json");
+        std::fs::write(
+            &compile_commands,
+            format!(
+                r#"[{{
+                    "directory": "{}",
+                    "arguments": ["c++", "-DCONFIG_HEADER=\"cfg.h\"", "-c", "main.cpp"],
+                    "file": "main.cpp"
+                }}]"#,
+                path
+            ),
+        )?;
+
+        // the defines of the database expand the computed include, without evaluating conditionals
+        let include_settings = IncludeSettings::make(vec![], vec![], vec![], false);
+        let scan_settings = ScanSettings::make(include_settings, Some(compile_commands));
+        let config = Config::make_with_scan_settings(path, 100, false, scan_settings);
+        let impacts = TopNUseCase::do_sorted_topn_impact(config)?;
+
+        assert_eq!(3, impacts.len());
+        assert_eq!(2, impacts["detail.h"]);
+        assert_eq!(1, impacts["cfg.h"]);
+        assert_eq!(0, impacts["main.cpp"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn integration_use_case_impact_compilation_database_include_chains(
+    ) -> Result<(), Box<dyn Error>> {
+        let project = make_project(&[
+            ("main.cpp", "#include \"platform.h\"\n#include \"foo.h\"\n"),
+            (
+                "platform.h",
+                "#define STRINGIFY_INNER(x) #x\n#define STRINGIFY(x) STRINGIFY_INNER(x)\n\
+#define PLATFORM_HEADER(name) STRINGIFY(PLATFORM/name)\n",
+            ),
+            ("foo.h", "#include PLATFORM_HEADER(foo_impl.h)\n"),
+            ("Windows/foo_impl.h", "#include \"detail.h\"\n"),
+            ("Windows/detail.h", ""),
+        ])?;
+        let path = project.path().to_str().ok_or("Invalid path")?;
+        let compile_commands = project.path().join("compile_commands.json");
+        std::fs::write(
+            &compile_commands,
+            format!(
+                r#"[{{
+                    "directory": "{}",
+                    "arguments": ["c++", "-DPLATFORM=Windows", "-c", "main.cpp"],
+                    "file": "main.cpp"
+                }}]"#,
+                path
+            ),
+        )?;
+
+        // the include of foo.h needs both the define of the database and the macros of
+        // platform.h, and leads to files reached by no other include
+        let include_settings = IncludeSettings::make(vec![], vec![], vec![], false);
+        let scan_settings = ScanSettings::make(include_settings, Some(compile_commands));
+        let config = Config::make_with_scan_settings(path, 100, false, scan_settings);
+        let impacts = TopNUseCase::do_sorted_topn_impact(config)?;
+
+        assert_eq!(5, impacts.len());
+        assert_eq!(3, impacts["Windows/detail.h"]);
+        assert_eq!(2, impacts["Windows/foo_impl.h"]);
+        assert_eq!(1, impacts["foo.h"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn integration_use_case_include_chains() -> Result<(), Box<dyn Error>> {
+        let config = Config::make("tests/cycles", 100, false);
+        let chains = IncludeChainUseCase::do_include_chains(
+            config,
+            "main.cpp",
+            "include/e.h",
+            IncludeChainQuery::Shortest,
+        )?;
+        assert_eq!(
+            vec![vec![
+                "main.cpp",
+                "include/a.h",
+                "include/b.h",
+                "include/c.h",
+                "include/d.h",
+                "include/e.h"
+            ]],
+            chains
+        );
+
+        let config = Config::make("tests/cycles", 100, false);
+        let chains = IncludeChainUseCase::do_include_chains(
+            config,
+            "main.cpp",
+            "self.h",
+            IncludeChainQuery::AllSimple(10),
+        )?;
+        assert_eq!(vec![vec!["main.cpp", "include/self.h"]], chains);
+
+        Ok(())
+    }
+
+    #[test]
+    fn integration_use_case_include_cycles() -> Result<(), Box<dyn Error>> {
+        let config = Config::make("tests/cycles", 100, false);
+        let cycles = IncludeCyclesUseCase::do_include_cycles(config)?;
+
+        assert_eq!(
+            vec![
+                vec!["include/a.h", "include/b.h", "include/c.h"],
+                vec!["include/d.h", "include/e.h"],
+                vec!["include/self.h"],
+            ],
+            cycles
+        );
+
+        let config = Config::make("tests/cycles", 1, false);
+        let cycles = IncludeCyclesUseCase::do_include_cycles(config)?;
+        assert_eq!(1, cycles.len());
+
+        let config = Config::make("tests/complex", 100, false);
+        let cycles = IncludeCyclesUseCase::do_include_cycles(config)?;
+        assert!(cycles.is_empty());
+
+        Ok(())
+    }
 }
@@ -0,0 +1,4 @@
+#include "a.h"
+#include "self.h"
+
+int main() { return 0; }